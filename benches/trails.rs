@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::time::Instant;
+
+use nannou::prelude::{vec2, Point2};
+
+// the cost of keeping a trace at its length as each frame's point comes in: push the new point
+// on the back and drop the oldest off the front. a vec has to shift every other point along to
+// drop the first, so it gets slower the longer the trace, while a vecdeque just moves its start.
+// this is why the traces in main.rs are vecdeques. run with
+//
+//     cargo bench --bench trails
+//
+// criterion isn't a dependency, so it's timed by hand with the best of a few runs, the way the
+// stress command times the physics
+
+const APPENDS: usize = 20_000;
+const RUNS: usize = 5;
+
+fn point(i: usize) -> Option<Point2> {
+    Some(vec2(i as f32, -(i as f32)))
+}
+
+// appends per second, from the fastest of the runs
+
+fn time(mut run: impl FnMut()) -> f64 {
+
+    let fastest = (0..RUNS)
+        .map(|_| {
+            let clock = Instant::now();
+            run();
+            clock.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min);
+
+    APPENDS as f64 / fastest
+
+}
+
+fn main() {
+
+    for length in [500, 50_000] {
+
+        let vec_rate = time(|| {
+            let mut trail: Vec<Option<Point2>> = (0..length).map(point).collect();
+
+            for i in 0..APPENDS {
+                trail.push(point(i));
+
+                while trail.len() > length {
+                    trail.remove(0);
+                }
+            }
+
+            black_box(&trail);
+        });
+
+        let deque_rate = time(|| {
+            let mut trail: VecDeque<Option<Point2>> = (0..length).map(point).collect();
+
+            for i in 0..APPENDS {
+                trail.push_back(point(i));
+
+                while trail.len() > length {
+                    trail.pop_front();
+                }
+            }
+
+            black_box(&trail);
+        });
+
+        println!("{} points: Vec with remove(0) {:.3e} appends/s, VecDeque with pop_front {:.3e} appends/s ({:.0}x)", length, vec_rate, deque_rate, deque_rate / vec_rate);
+    }
+
+}
@@ -0,0 +1,143 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+// sounds for events in the motion, with --audio: each arm going over the top plays its own
+// sound, so the rhythm of the flips can be heard without watching closely. there's no audio
+// library in the build, so the samples are mixed here and piped into whichever command line
+// player the system has. if there isn't one, or it stops, the run carries on silently
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sound {
+    Click,
+    Tone(f32), // a short ringing note at this pitch, in hertz
+    Off,
+}
+
+impl Sound {
+
+    // click, off, or a pitch in hertz for a tone
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "click" => Some(Sound::Click),
+            "off" => Some(Sound::Off),
+            _ => text.parse().ok().filter(|pitch: &f32| *pitch > 0.0 && pitch.is_finite() && *pitch < RATE as f32 / 2.0).map(Sound::Tone),
+        }
+    }
+
+    // the sound's value at `sample` samples after it started, or None once it's died away
+
+    fn at(self, sample: usize) -> Option<f32> {
+
+        let t = sample as f32 / RATE as f32;
+
+        match self {
+            Sound::Click => (t < CLICK_LENGTH).then(|| if sample % 20 < 10 { 1.0 } else { -1.0 } * (-t / 0.002).exp()),
+            Sound::Tone(pitch) => (t < TONE_LENGTH).then(|| (std::f32::consts::TAU * pitch * t).sin() * (-t / 0.04).exp()),
+            Sound::Off => None,
+        }
+
+    }
+
+}
+
+const RATE: u32 = 44100; // samples a second
+const BLOCK: usize = 441; // samples mixed at a time, 10 ms
+const VOLUME: f32 = 0.3;
+const CLICK_LENGTH: f32 = 0.01; // seconds
+const TONE_LENGTH: f32 = 0.15;
+
+// players that take raw mono 16 bit samples on stdin, tried in order. each is asked for a short
+// buffer, so a sound comes out soon after it's played
+
+const PLAYERS: [(&str, &[&str]); 2] = [
+    ("aplay", &["-q", "-t", "raw", "-f", "S16_LE", "-c", "1", "-r", "44100", "--buffer-time=50000"]),
+    ("paplay", &["--raw", "--format=s16le", "--channels=1", "--rate=44100", "--latency-msec=50"]),
+];
+
+pub struct Audio {
+    sounds: Sender<Sound>,
+}
+
+impl Audio {
+
+    // start the first player that's there, feeding it from a thread of its own
+
+    pub fn start() -> Option<Self> {
+
+        let started = PLAYERS.iter().find_map(|(name, args)| {
+            Command::new(name).args(*args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().ok()
+        });
+
+        let Some(mut player) = started else {
+            let names: Vec<&str> = PLAYERS.iter().map(|(name, _)| *name).collect();
+            eprintln!("no audio player found (tried {}), carrying on without sound", names.join(" and "));
+            return None;
+        };
+
+        let (sounds, received) = channel();
+        let stdin = player.stdin.take()?;
+
+        std::thread::spawn(move || {
+            mix(received, stdin);
+            let _ = player.kill();
+        });
+
+        Some(Self { sounds })
+
+    }
+
+    // a send only fails once the mixer's stopped, which is already reported from there
+
+    pub fn play(&self, sound: Sound) {
+
+        if sound != Sound::Off {
+            let _ = self.sounds.send(sound);
+        }
+
+    }
+
+}
+
+// write silence with whatever's playing mixed in, a block at a time. the player only takes
+// samples as fast as it plays them, so that's what paces this. it ends when the app does, by
+// dropping its end of the channel, or when the player goes away
+
+fn mix(sounds: Receiver<Sound>, mut player: impl Write) {
+
+    let mut playing: Vec<(Sound, usize)> = Vec::new();
+    let mut bytes = Vec::with_capacity(2 * BLOCK);
+
+    loop {
+
+        loop {
+            match sounds.try_recv() {
+                Ok(sound) => playing.push((sound, 0)),
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => return,
+            }
+        }
+
+        bytes.clear();
+
+        for _ in 0..BLOCK {
+            let value: f32 = playing.iter().filter_map(|(sound, sample)| sound.at(*sample)).sum();
+
+            for (_, sample) in &mut playing {
+                *sample += 1;
+            }
+
+            let value = (VOLUME * value).clamp(-1.0, 1.0) * i16::MAX as f32;
+            bytes.extend_from_slice(&(value as i16).to_le_bytes());
+        }
+
+        playing.retain(|(sound, sample)| sound.at(*sample).is_some());
+
+        if player.write_all(&bytes).is_err() {
+            eprintln!("the audio player stopped, carrying on without sound");
+            return;
+        }
+    }
+
+}
@@ -0,0 +1,110 @@
+use double_pendulum::presets;
+use double_pendulum::sim::{Integrator, Simulation};
+use double_pendulum::trajectory;
+
+// the simulation without a window, for servers and scripted experiments: step a preset for a
+// while and write its trajectory as a csv, to stdout or a file. it only uses the library, so
+// it builds without nannou (cargo build --no-default-features --bin headless). the csv is the
+// same as export's, so the app's --replay can play it back
+
+const USAGE: &str = "\
+usage: headless [options]
+
+  --preset NAME       starting setup, from src/presets.rs (default)
+  --duration T        simulated seconds to run for (20)
+  --dt D              simulated seconds per step (0.001)
+  --every N           write one row every N steps (1)
+  --integrator NAME   euler, rk2, rk4, leapfrog, implicit-midpoint or rkf45 (rk4)
+  --output FILE       write to FILE instead of stdout";
+
+struct Options {
+    preset: String,
+    duration: f64,
+    dt: f64,
+    every: usize,
+    integrator: Integrator,
+    output: Option<String>,
+}
+
+fn main() {
+
+    let options = parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        std::process::exit(1)
+    });
+
+    if let Err(e) = run(&options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+
+    let mut options = Options { preset: "default".to_string(), duration: 20.0, dt: 0.001, every: 1, integrator: Integrator::Rk4, output: None };
+
+    while let Some(arg) = args.next() {
+
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "--preset" => options.preset = value()?,
+            "--duration" => options.duration = number(&value()?, &arg)?,
+            "--dt" => options.dt = number(&value()?, &arg)?,
+            "--every" => options.every = value()?.parse().map_err(|_| format!("{} takes a whole number", arg))?,
+            "--integrator" => options.integrator = Integrator::parse(&value()?)?,
+            "--output" => options.output = Some(value()?),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    if !(options.duration >= 0.0 && options.duration.is_finite()) {
+        return Err(format!("--duration must be a number no less than 0, not {}", options.duration));
+    }
+
+    if !(options.dt > 0.0 && options.dt.is_finite()) {
+        return Err(format!("--dt must be a positive number, not {}", options.dt));
+    }
+
+    if options.every == 0 {
+        return Err("--every must be at least 1".to_string());
+    }
+
+    Ok(options)
+
+}
+
+fn number(value: &str, flag: &str) -> Result<f64, String> {
+    value.parse().map_err(|_| format!("{} can't take {}", flag, value))
+}
+
+fn run(options: &Options) -> Result<(), String> {
+
+    let preset = presets::find(&options.preset)?;
+    let mut sim = Simulation::new(preset.state, preset.params)?;
+
+    sim.integrator = options.integrator;
+
+    let rows = sim.record(options.duration, options.dt, options.every)?;
+
+    // the csv goes to stdout unless there's a file, so only then is there room to say so
+
+    match &options.output {
+        Some(file) => {
+            let file_handle = std::fs::File::create(file).map_err(|e| format!("couldn't write {}: {}", file, e))?;
+            trajectory::write_states(&mut std::io::BufWriter::new(file_handle), &rows).map_err(|e| format!("couldn't write {}: {}", file, e))?;
+            println!("wrote {} states to {}", rows.len(), file);
+        }
+        None => {
+            trajectory::write_states(&mut std::io::BufWriter::new(std::io::stdout().lock()), &rows).map_err(|e| format!("couldn't write to stdout: {}", e))?;
+        }
+    }
+
+    Ok(())
+
+}
@@ -0,0 +1,100 @@
+use nannou::draw::{Renderer, RendererBuilder};
+use nannou::prelude::*;
+use nannou::wgpu;
+
+// an offscreen texture the trace is drawn onto bit by bit and kept between frames, so it can
+// grow without limit instead of being redrawn from a bounded list of points every frame.
+//
+// the texture covers the window in physical pixels, and starts (and is cleared back to)
+// transparent so it can be laid over the background and heatmap
+
+pub struct Canvas {
+    texture: wgpu::Texture,
+    renderer: Renderer,
+    scale_factor: f32,
+}
+
+impl Canvas {
+
+    pub fn new(window: &Window) -> Self {
+
+        let device = window.device();
+        let [width, height] = window.inner_size_pixels().into();
+
+        let texture = wgpu::TextureBuilder::new()
+            .size([width.max(1), height.max(1)])
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            .sample_count(1)
+            .format(Frame::TEXTURE_FORMAT)
+            .build(device);
+
+        let renderer = RendererBuilder::new().build_from_texture_descriptor(device, texture.descriptor());
+
+        Self { texture, renderer, scale_factor: window.scale_factor() }
+
+    }
+
+    // start again with an empty canvas if the window has changed size, since nothing on it
+    // lines up any more. returns whether it did
+
+    pub fn fit(&mut self, window: &Window) -> bool {
+
+        let size: (u32, u32) = window.inner_size_pixels();
+
+        if self.texture.size() == [size.0.max(1), size.1.max(1)] && self.scale_factor == window.scale_factor() {
+            return false;
+        }
+
+        *self = Self::new(window);
+        true
+
+    }
+
+    // add line segments, given in window coordinates as (start, end, colour), with the given
+    // weight in points
+
+    pub fn draw_segments(&mut self, window: &Window, segments: &[(Point2, Point2, Srgb<u8>)], weight: f32) {
+
+        let draw = Draw::new();
+        let s = self.scale_factor;
+
+        for (start, end, colour) in segments {
+            draw.line()
+                .start(*start * s)
+                .end(*end * s)
+                .weight(weight * s)
+                .caps_round()
+                .color(*colour);
+        }
+
+        self.render(window, &draw);
+
+    }
+
+    pub fn clear(&mut self, window: &Window) {
+
+        let draw = Draw::new();
+        draw.background().color(srgba(0.0, 0.0, 0.0, 0.0));
+
+        self.render(window, &draw);
+
+    }
+
+    fn render(&mut self, window: &Window, draw: &Draw) {
+
+        let device = window.device();
+        let descriptor = wgpu::CommandEncoderDescriptor { label: Some("trace canvas") };
+        let mut encoder = device.create_command_encoder(&descriptor);
+
+        self.renderer.render_to_texture(device, &mut encoder, draw, &self.texture);
+        window.queue().submit(Some(encoder.finish()));
+
+    }
+
+    // lay the canvas over whatever's been drawn so far, filling the window
+
+    pub fn show(&self, draw: &Draw, window: Rect) {
+        draw.texture(&self.texture).xy(window.xy()).wh(window.wh());
+    }
+
+}
@@ -0,0 +1,165 @@
+use crate::physics::{Params, State};
+
+// the pendulum hung from a cart that runs freely along a horizontal track instead of from a
+// fixed pivot, pushed along by a horizontal force: a cart and double pole, as in control and
+// reinforcement learning benchmarks. the cart's motion swings the arms, and their swinging
+// drags the cart back and forth. the heavier the cart, the less it's moved, so in the limit of
+// an infinitely heavy one this is the fixed pivot double pendulum of physics.rs
+//
+// it's worked out from the lagrangian with the cart's position x as a third coordinate. bob 1
+// sits at (x + l1 sin(theta_1), -l1 cos(theta_1)) and bob 2 l2 further along its own arm, so
+// the mass matrix over (x, theta_1, theta_2) is
+//
+//     [[M + m1 + m2,              (m1 + m2) l1 cos(theta_1), m2 l2 cos(theta_2)       ],
+//      [(m1 + m2) l1 cos(theta_1), (m1 + m2) l1^2,           m2 l1 l2 cos(theta_1 - theta_2)],
+//      [m2 l2 cos(theta_2),        m2 l1 l2 cos(theta_1 - theta_2), m2 l2^2           ]]
+//
+// with M the cart's mass. the lower right block is the fixed pivot's, and the first row and
+// column couple the cart in. the three accelerations come from solving that against the
+// forces, the arms' torques included as in derivatives
+
+#[derive(Copy, Clone, Debug)]
+pub struct Cart {
+    pub mass: f64, // kg
+    pub force: f64, // newtons along the track, positive to the right
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CartState {
+    pub x: f64, // metres along the track from where it started
+    pub dot_x: f64,
+    pub pendulum: State,
+}
+
+// the rate of change of each part of the state, as derivatives gives for the pendulum alone
+
+pub fn cart_derivatives(state: &CartState, params: &Params, cart: &Cart) -> CartState {
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let State { theta_1, theta_2, dot_theta_1: w1, dot_theta_2: w2 } = state.pendulum;
+    let (sin_1, cos_1) = theta_1.sin_cos();
+    let (sin_2, cos_2) = theta_2.sin_cos();
+    let (sin_d, cos_d) = (theta_1 - theta_2).sin_cos();
+
+    let mass = [
+        [cart.mass + m1 + m2, (m1 + m2) * l1 * cos_1, m2 * l2 * cos_2],
+        [(m1 + m2) * l1 * cos_1, (m1 + m2) * l1 * l1, m2 * l1 * l2 * cos_d],
+        [m2 * l2 * cos_2, m2 * l1 * l2 * cos_d, m2 * l2 * l2],
+    ];
+
+    // the forces on each coordinate, with what's left of the accelerations once the velocity
+    // terms are moved across: the arms swinging round pull the cart outwards, and each arm's
+    // swing pulls on the other as it does with a fixed pivot
+
+    let tau_1 = params.torque.0 as f64 + params.control.torque(&state.pendulum);
+    let tau_2 = params.torque.1 as f64;
+
+    let force = [
+        cart.force + (m1 + m2) * l1 * sin_1 * w1 * w1 + m2 * l2 * sin_2 * w2 * w2,
+        tau_1 - m2 * l1 * l2 * sin_d * w2 * w2 - (m1 * g1 + m2 * g2) * l1 * sin_1,
+        tau_2 + m2 * l1 * l2 * sin_d * w1 * w1 - m2 * g2 * l2 * sin_2,
+    ];
+
+    let [ddot_x, ddot_theta_1, ddot_theta_2] = solve(mass, force);
+
+    CartState {
+        x: state.dot_x,
+        dot_x: ddot_x,
+        pendulum: State { theta_1: w1, theta_2: w2, dot_theta_1: ddot_theta_1, dot_theta_2: ddot_theta_2 },
+    }
+
+}
+
+// one classic rk4 step, as physics::rk4 takes for the pendulum alone
+
+pub fn cart_rk4(state: &CartState, params: &Params, cart: &Cart, dt: f32) -> CartState {
+
+    let h = dt as f64;
+    let along = |s: &CartState, k: &CartState, f: f64| CartState {
+        x: s.x + f * k.x,
+        dot_x: s.dot_x + f * k.dot_x,
+        pendulum: s.pendulum + k.pendulum * f,
+    };
+
+    let k1 = cart_derivatives(state, params, cart);
+    let k2 = cart_derivatives(&along(state, &k1, 0.5 * h), params, cart);
+    let k3 = cart_derivatives(&along(state, &k2, 0.5 * h), params, cart);
+    let k4 = cart_derivatives(&along(state, &k3, h), params, cart);
+
+    CartState {
+        x: state.x + h / 6.0 * (k1.x + 2.0 * k2.x + 2.0 * k3.x + k4.x),
+        dot_x: state.dot_x + h / 6.0 * (k1.dot_x + 2.0 * k2.dot_x + 2.0 * k3.dot_x + k4.dot_x),
+        pendulum: state.pendulum + (k1.pendulum + k2.pendulum * 2.0 + k3.pendulum * 2.0 + k4.pendulum) * (h / 6.0),
+    }
+
+}
+
+// the total energy, the cart's kinetic energy on top of the pendulum's kinetic and potential
+// energies as it carries them along
+
+pub fn cart_energy(state: &CartState, params: &Params, cart: &Cart) -> f64 {
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let State { theta_1, theta_2, dot_theta_1: w1, dot_theta_2: w2 } = state.pendulum;
+    let v = state.dot_x;
+
+    let (vx1, vy1) = (v + l1 * theta_1.cos() * w1, l1 * theta_1.sin() * w1);
+    let (vx2, vy2) = (vx1 + l2 * theta_2.cos() * w2, vy1 + l2 * theta_2.sin() * w2);
+
+    0.5 * cart.mass * v * v
+        + 0.5 * m1 * (vx1 * vx1 + vy1 * vy1)
+        + 0.5 * m2 * (vx2 * vx2 + vy2 * vy2)
+        - (m1 * g1 + m2 * g2) * l1 * theta_1.cos()
+        - m2 * g2 * l2 * theta_2.cos()
+
+}
+
+// the momentum of everything along the track, which only the force on the cart changes
+
+pub fn cart_momentum(state: &CartState, params: &Params, cart: &Cart) -> f64 {
+
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let State { theta_1, theta_2, dot_theta_1: w1, dot_theta_2: w2 } = state.pendulum;
+
+    (cart.mass + m1 + m2) * state.dot_x + (m1 + m2) * l1 * theta_1.cos() * w1 + m2 * l2 * theta_2.cos() * w2
+
+}
+
+// cramer's rule, which is plenty for a 3x3 system. the mass matrix is positive definite for
+// any positive masses and lengths, so the determinant can't vanish
+
+fn solve(a: [[f64; 3]; 3], b: [f64; 3]) -> [f64; 3] {
+
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+
+    let whole = det(a);
+
+    [0, 1, 2].map(|column| {
+        let mut replaced = a;
+
+        for row in 0..3 {
+            replaced[row][column] = b[row];
+        }
+
+        det(replaced) / whole
+    })
+
+}
@@ -0,0 +1,363 @@
+use std::collections::VecDeque;
+use std::f64::consts::{PI, TAU};
+
+use crate::physics::{rk4, substep, Params, State};
+
+// how chaotic a starting state is, as the average exponential rate (per second) at which a
+// second run started a tiny distance away pulls away from it over `horizon` seconds. that's a
+// short-horizon estimate of the largest lyapunov exponent: around zero for regular motion,
+// and positive once it's chaotic
+//
+// left alone the separation would stop growing once the runs are a whole swing apart, so after
+// every step its growth is added up and it's scaled back down to the starting size, always
+// pointing in the direction it's grown in
+
+const SEPARATION: f64 = 1e-8;
+
+pub fn divergence_rate(state: &State, params: &Params, horizon: f64, dt: f32) -> f64 {
+
+    let mut reference = *state;
+    let mut neighbour = State { theta_2: state.theta_2 + SEPARATION, ..*state };
+
+    let steps = (horizon / dt as f64).ceil() as usize;
+    let mut total_growth = 0.0;
+
+    for _ in 0..steps {
+
+        reference = rk4(&reference, params, dt);
+        neighbour = rk4(&neighbour, params, dt);
+
+        let offset = phase_distance(&neighbour, &reference);
+
+        // the runs have landed on top of each other exactly, so there's nothing to measure
+
+        if offset == 0.0 {
+            return 0.0;
+        }
+
+        total_growth += (offset / SEPARATION).ln();
+
+        let d = (neighbour - reference) * (SEPARATION / offset);
+        neighbour = reference + d;
+    }
+
+    total_growth / (steps as f64 * dt as f64)
+
+}
+
+// the same estimate kept up to date as a run goes, averaged over the last `window` simulated
+// seconds. rather than a second run of its own, it keeps just the direction the separation has
+// grown in, and each frame steps the pendulum and a neighbour SEPARATION along that direction
+// from it, so it follows the live run through anything that moves it (dragging, kicks, changed
+// parameters). its rk4 steps are kept to MAX_STEP, however long the frame
+
+#[derive(Clone, Debug)]
+pub struct LyapunovEstimate {
+    direction: State, // unit length in phase_distance
+    growth: VecDeque<(f64, f64)>, // (dt, log of how much the separation grew over it)
+    window: f64,
+    full: bool, // whether it's covered a whole window yet
+}
+
+const MAX_STEP: f32 = 0.01;
+
+impl LyapunovEstimate {
+
+    pub fn new(window: f64) -> Self {
+        let direction = State { theta_1: 0.0, theta_2: 1.0, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+        Self { direction, growth: VecDeque::new(), window, full: false }
+    }
+
+    // take in the frame about to be stepped from state
+
+    pub fn advance(&mut self, state: &State, params: &Params, dt: f32) {
+
+        if dt <= 0.0 {
+            return;
+        }
+
+        let reference = substep(rk4, state, params, dt, MAX_STEP);
+        let neighbour = substep(rk4, &(*state + self.direction * SEPARATION), params, dt, MAX_STEP);
+        let offset = phase_distance(&neighbour, &reference);
+
+        if offset == 0.0 || !offset.is_finite() {
+            return;
+        }
+
+        self.direction = (neighbour - reference) / offset;
+        self.growth.push_back((dt as f64, (offset / SEPARATION).ln()));
+
+        let mut covered: f64 = self.growth.iter().map(|(dt, _)| dt).sum();
+
+        while covered > self.window && self.growth.len() > 1 {
+            covered -= self.growth.pop_front().unwrap().0;
+            self.full = true;
+        }
+
+    }
+
+    // the average rate in 1/s, or None until it's seen a whole window. a shorter stretch can
+    // read high even for regular motion, which is often still stretching one way or the other
+
+    pub fn exponent(&self) -> Option<f64> {
+
+        let (time, growth) = self.growth.iter().fold((0.0, 0.0), |(t, g), (dt, growth)| (t + dt, g + growth));
+
+        self.full.then(|| growth / time)
+
+    }
+
+    pub fn clear(&mut self) {
+        self.growth.clear();
+        self.full = false;
+    }
+
+}
+
+// a rough reading of what kind of motion a divergence rate means
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Regime {
+    Periodic,
+    Quasiperiodic,
+    Chaotic,
+}
+
+// below quasiperiodic (1/s) reads as periodic and above chaotic as chaotic, with anything
+// between quasiperiodic. to move across a threshold the rate has to get hysteresis past it, so
+// an estimate hovering near one doesn't flicker between the two
+
+#[derive(Clone, Copy, Debug)]
+pub struct RegimeThresholds {
+    pub quasiperiodic: f64,
+    pub chaotic: f64,
+    pub hysteresis: f64,
+}
+
+impl Regime {
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Regime::Periodic => "periodic",
+            Regime::Quasiperiodic => "quasiperiodic",
+            Regime::Chaotic => "chaotic",
+        }
+    }
+
+    // the regime for the rate, given the one it was in before (if any). each threshold is moved
+    // hysteresis further away from the previous regime, so it takes a clear change to leave it
+
+    pub fn classify(rate: f64, previous: Option<Regime>, thresholds: &RegimeThresholds) -> Regime {
+
+        let previous = previous.map(|regime| regime as usize);
+
+        let crossed = [thresholds.quasiperiodic, thresholds.chaotic].into_iter()
+            .enumerate()
+            .filter(|(i, threshold)| {
+                let shift = match previous {
+                    Some(previous) if *i >= previous => thresholds.hysteresis,
+                    Some(_) => -thresholds.hysteresis,
+                    None => 0.0,
+                };
+
+                rate > threshold + shift
+            })
+            .count();
+
+        [Regime::Periodic, Regime::Quasiperiodic, Regime::Chaotic][crossed]
+
+    }
+
+}
+
+// watches for the run coming back round to somewhere it's already been, which on a periodic
+// orbit happens once a period. it keeps a coarse history, a state every `interval` simulated
+// seconds going back `span` seconds, and checks each new state against it for one within
+// `threshold` (in wrapped_distance). the states just before this one are always that close, so
+// it only looks past the last time the run was further away than that. having closed a loop it
+// holds off for half the period, so one return isn't counted over and over
+
+#[derive(Clone, Debug)]
+pub struct LoopDetector {
+    pub threshold: f64,
+    interval: f64,
+    span: f64,
+    history: VecDeque<(f64, State)>, // (sim_time, state), oldest first
+    quiet_until: f64,
+}
+
+impl LoopDetector {
+
+    pub fn new(threshold: f64, interval: f64, span: f64) -> Self {
+        Self { threshold, interval, span, history: VecDeque::new(), quiet_until: f64::NEG_INFINITY }
+    }
+
+    // take in the state at `time`, giving back the period if it's just closed a loop
+
+    pub fn record(&mut self, time: f64, state: &State) -> Option<f64> {
+
+        let closed = if time < self.quiet_until { None } else { self.closure(time, state) };
+
+        if let Some(period) = closed {
+            self.quiet_until = time + 0.5 * period;
+        }
+
+        if self.history.back().is_none_or(|(last, _)| time >= last + self.interval) {
+            self.history.push_back((time, *state));
+        }
+
+        while self.history.front().is_some_and(|(first, _)| *first < time - self.span) {
+            self.history.pop_front();
+        }
+
+        closed
+
+    }
+
+    // the nearest state within threshold in the most recent stretch of them that's that close,
+    // leaving out the stretch leading up to now
+
+    fn closure(&self, time: f64, state: &State) -> Option<f64> {
+
+        let mut history = self.history.iter().rev()
+            .map(|(then, past)| (*then, wrapped_distance(state, past)))
+            .skip_while(|(_, distance)| *distance < self.threshold)
+            .skip_while(|(_, distance)| *distance >= self.threshold)
+            .take_while(|(_, distance)| *distance < self.threshold);
+
+        let first = history.next()?;
+        let (then, _) = history.fold(first, |best, next| if next.1 < best.1 { next } else { best });
+
+        Some(time - then)
+
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.quiet_until = f64::NEG_INFINITY;
+    }
+
+}
+
+// phase_distance with the difference in each angle wrapped into [-pi, pi), so an arm that's
+// gone over the top a whole turn is back where it started
+
+pub fn wrapped_distance(a: &State, b: &State) -> f64 {
+
+    let d = *a - *b;
+    let wrap = |angle: f64| (angle + PI).rem_euclid(TAU) - PI;
+
+    let (t1, t2) = (wrap(d.theta_1), wrap(d.theta_2));
+
+    (t1 * t1 + t2 * t2 + d.dot_theta_1 * d.dot_theta_1 + d.dot_theta_2 * d.dot_theta_2).sqrt()
+
+}
+
+// euclidean distance between two states, treating angles and angular velocities alike
+
+pub fn phase_distance(a: &State, b: &State) -> f64 {
+
+    let d = *a - *b;
+
+    (d.theta_1 * d.theta_1 + d.theta_2 * d.theta_2 + d.dot_theta_1 * d.dot_theta_1 + d.dot_theta_2 * d.dot_theta_2).sqrt()
+
+}
+
+// the phase of one arm's swing, from where it sits on its own (angle, angular velocity) plane:
+// angular velocity is scaled by `frequency` (rad/s) so a swing at that frequency goes round a
+// circle, and the angle is wrapped so an arm that's gone over the top still has a phase
+
+pub fn oscillation_phase(theta: f64, dot_theta: f64, frequency: f64) -> f64 {
+
+    let theta = (theta + PI).rem_euclid(TAU) - PI;
+
+    (-dot_theta / frequency).atan2(theta)
+
+}
+
+// the torque on the first arm from coupling it to an external oscillator at `phase`, at most
+// `coupling` newton metres: proportional to the sine of how far the arm's own phase is from the
+// oscillator's, so it keeps nudging the swing into step. it's applied in proportion to the
+// cosine of the arm's phase, i.e. mostly near the ends of the swing, which is where a torque
+// shifts the phase. over a swing that leaves the energy alone rather than pumping it. the arm's
+// phase is measured against the oscillator's frequency (rad/s)
+
+pub fn drive_torque(state: &State, phase: f64, frequency: f64, coupling: f64) -> f64 {
+    let own = oscillation_phase(state.theta_1, state.dot_theta_1, frequency);
+    -coupling * (phase - own).sin() * own.cos()
+}
+
+// a kuramoto-style order parameter for how locked together two phases are, from samples of the
+// difference between them: the length of the average of the unit vectors at those differences.
+// 1 when the difference holds steady (whatever it holds at, so both in-phase and anti-phase
+// swings count), and towards 0 as it wanders all the way round
+
+pub fn synchronization(differences: impl Iterator<Item = f64>) -> f64 {
+
+    let (mut x, mut y, mut n) = (0.0, 0.0, 0);
+
+    for difference in differences {
+        x += difference.cos();
+        y += difference.sin();
+        n += 1;
+    }
+
+    if n == 0 {
+        return 0.0;
+    }
+
+    (x * x + y * y).sqrt() / n as f64
+
+}
+
+// `count` copies of state spread out over a tiny range of theta_2 (up to `spread` radians
+// above it), so they start as good as identical and show how quickly the motion fans out
+
+pub fn ensemble(state: &State, count: usize, spread: f64) -> Vec<State> {
+    (1..=count)
+        .map(|k| State { theta_2: state.theta_2 + spread * k as f64 / count as f64, ..*state })
+        .collect()
+}
+
+// the convex hull of a set of points, anticlockwise with no repeated or collinear points
+// (andrew's monotone chain). fewer than three distinct points can't enclose anything, so those
+// come back as they are, and points all on one line come back as its two ends
+
+pub fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+
+    if points.len() < 3 {
+        return points;
+    }
+
+    // > 0 if o -> a -> b turns anticlockwise
+
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(2 * points.len());
+
+    // lower half left to right, then upper half back again, dropping any point that would make
+    // a clockwise turn
+
+    for pass in [points.clone(), points.iter().rev().copied().collect()] {
+
+        let start = hull.len();
+
+        for point in pass {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+
+        // the last point of each half is the first of the other
+
+        hull.pop();
+    }
+
+    hull
+
+}
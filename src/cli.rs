@@ -0,0 +1,912 @@
+use double_pendulum::expression::Expression;
+use double_pendulum::sim::Integrator;
+
+use crate::audio::Sound;
+use crate::headless::SweepParameter;
+use crate::library::LIBRARY_FILE;
+use crate::portrait::Variable;
+
+// the command line: a subcommand picking what to do, each with its own options. with no
+// subcommand (or going straight into options) it's the same as `run`, so the window opens
+
+pub enum Command {
+    Run(Box<RunOptions>), // boxed, since it has far more options than the rest
+    Export(ExportOptions),
+    FlipMap(FlipMapOptions),
+    Gallery(GalleryOptions),
+    Verify,
+    FindChaotic(FindChaoticOptions),
+    MonteCarlo(MonteCarloOptions),
+    Stress(StressOptions),
+    Sweep(SweepOptions),
+}
+
+const USAGE: &str = "\
+usage: double_pendulum [command] [options]
+
+commands:
+  run           open the simulation in a window (the default)
+  export        simulate without a window and write the trajectory to a csv
+  flipmap       render how long each starting position takes to flip over, as a png
+  gallery       render the trace of every preset to a png
+  verify        check the equations of motion against the lagrangian
+  find-chaotic  search for the starting positions that diverge fastest
+  montecarlo    flip times for many randomly jittered copies of one start
+  stress        time how fast many pendulums can be stepped, on one thread and on all
+  sweep         how fast the second bob moves across a range of one parameter, as a csv
+
+run `double_pendulum <command> --help` for each command's options";
+
+const RUN_USAGE: &str = "\
+usage: double_pendulum [run] [options]
+
+  --replay FILE            play back a csv of states instead of simulating
+  --replay-loop            start the replay again when it finishes
+  --record-input FILE      log keypresses against simulated time
+  --replay-input FILE      press the keys from a log at their times
+  --export-phase FILE      write the raw state at fixed intervals of simulated time to a csv
+  --phase-interval D       simulated seconds between those samples (0.01)
+  --portrait FILE          plot the phase-space trajectory to a png when the window closes
+  --portrait-axes X,Y      which state variables it plots (theta_1,dot_theta_1)
+  --video FILE             pipe every frame into ffmpeg
+  --fps F                  frame rate of the video (60)
+  --clip-seconds S         how many simulated seconds y saves as a gif (5)
+  --clip-fps F             frames per simulated second in that gif (20)
+  --background SPEC        #rrggbb, a gradient as #top,#bottom, or an image file to draw behind
+  --audio                  play a sound each time an arm goes over the top
+  --flip-sound-1 SOUND     the first arm's: click, off, or a tone's pitch in Hz (click)
+  --flip-sound-2 SOUND     the second arm's (660)
+  --timelapse FILE         record an ensemble spreading into chaos as a time-lapse video
+  --timelapse-duration T   simulated seconds the time-lapse covers (60)
+  --timelapse-frames N     how many frames it's squeezed into (600)
+  --frame-dt D             advance exactly D simulated seconds per frame
+  --max-fps F              draw at most F frames per second (uncapped)
+  --physics-rate R         step the physics R times a second on its own thread
+  --duration T             quit after T simulated seconds
+  --trace-budget L         pause once the second bob's trace is L metres long
+  --start-at T             simulate T seconds before the first frame is drawn
+  --paused                 start paused (space resumes)
+  --always-on-top          keep the window above all the others
+  --canvas                 keep the whole trace on a canvas instead of fading it out
+  --dimensionless          simulate in units of l1, m1 and sqrt(l1/g)
+  --energy-setpoint E      drive the total energy towards E joules
+  --energy-gain K          how hard to drive it (0.5)
+  --drive W                couple the first arm to an external oscillator at W rad/s
+  --drive-coupling K       how strongly, in newton metres (0.5)
+  --drive-torque EXPR      also turn the first arm with a torque in t, e.g. \"0.5*sin(2*t) + 0.1*t\"
+  --cart M                 hang the pendulum from a cart of M kg running along a track
+  --cart-force EXPR        push the cart along with a force in t, in newtons
+  --ensemble N             run N slightly perturbed copies alongside
+  --ensemble-spread S      how far apart they start, in radians (1e-6)
+  --continue               pick up from the state and parameters saved when the last run closed
+  --reference FILE         draw a trace saved with export or --export-phase behind the live one
+  --compare CHANGES        run a second pendulum alongside with changed parameters, e.g. m2=2,l2=0.5
+  --rival NAME             also step the same start with another integrator, charting how far apart they get
+  --preset NAME            start from a setup in src/presets.rs
+  --load-named NAME        start from a setup saved in the library, configs.toml
+  --save-named NAME        add the state the run closes with to the library under NAME
+  --library FILE           keep the library in FILE instead of configs.toml
+  --inverted               start balanced upside down (the same as --preset inverted)
+  --m1 M, --m2 M           bob masses in kg, on top of whichever start is picked
+  --l1 L, --l2 L           arm lengths in metres
+  --gravity G              gravity in m/s^2
+  --theta1 A, --theta2 A   starting angles in radians from hanging straight down
+  --kick1 W, --kick2 W     starting angular velocities in rad/s
+  --trace-len N            how many points each trace keeps (500)";
+
+const EXPORT_USAGE: &str = "\
+usage: double_pendulum export FILE [options]
+
+  --preset NAME       starting setup, from src/presets.rs (default)
+  --duration T        simulated seconds to run for (20)
+  --dt D              simulated seconds per step (1/60, one row per frame when replayed)
+  --every N           write one row every N steps (1)
+  --integrator NAME   euler, rk2, rk4, leapfrog, implicit-midpoint or rkf45 (rk4)";
+
+const FLIPMAP_USAGE: &str = "\
+usage: double_pendulum flipmap FILE [options]
+
+  --size N        image width and height in pixels, one starting position each (200)
+  --max-time T    simulated seconds to wait for a flip before giving up (10)";
+
+const GALLERY_USAGE: &str = "\
+usage: double_pendulum gallery [DIR] [options]
+
+  DIR             where to write the pngs (gallery)
+  --size N        image width and height in pixels (512)";
+
+const VERIFY_USAGE: &str = "\
+usage: double_pendulum verify
+
+checks the accelerations from derivatives against ones worked out numerically from the
+lagrangian, and exits with an error if they disagree";
+
+const FIND_CHAOTIC_USAGE: &str = "\
+usage: double_pendulum find-chaotic [COUNT] [options]
+
+  COUNT           how many of the best to print, as presets (5)
+  --samples N     random starting positions to try (1000)";
+
+const MONTECARLO_USAGE: &str = "\
+usage: double_pendulum montecarlo [options]
+
+  --preset NAME   starting setup to jitter, from src/presets.rs (default)
+  --samples N     how many jittered runs (1000)
+  --jitter S      standard deviation of the noise added to each of the angles and angular
+                  velocities (1e-3)
+  --seed N        seed for the noise, so a set of runs can be repeated (0)
+  --max-time T    simulated seconds to wait for a flip before giving up (30)
+  --fall          time how long until an arm falls below horizontal instead, for starts
+                  balanced near the top like the inverted preset";
+
+const STRESS_USAGE: &str = "\
+usage: double_pendulum stress [N] [options]
+
+  N               how many independent pendulums (10000)
+  --steps S       rk4 steps to take each (1000)
+  --dt D          simulated seconds per step (0.01)";
+
+const SWEEP_USAGE: &str = "\
+usage: double_pendulum sweep FILE [options]
+
+  --param NAME    what to sweep: l1, l2, m1, m2, gravity, length-ratio (l2/l1, keeping l1)
+                  or mass-ratio (m2/m1, keeping m1) (length-ratio)
+  --from A        the first value (0.25)
+  --to B          the last value (4)
+  --samples N     how many values, evenly spaced from A to B (50)
+  --preset NAME   starting setup, from src/presets.rs (default)
+  --duration T    simulated seconds to run each for (30)
+  --dt D          simulated seconds per rk4 step (0.01)";
+
+pub struct RunOptions {
+    pub replay: Option<String>, // csv file of states to play back instead of simulating
+    pub replay_loop: bool, // start the replay again when it finishes, rather than stopping
+    pub reference: Option<String>, // csv of states whose trace is drawn faintly for comparison
+    pub compare: Option<Vec<(SweepParameter, f32)>>, // changes for a second run shown beside this one
+    pub rival: Option<String>, // the integrator to step the same run with alongside, by name
+
+    // log keypresses with the simulated time they happened at, or feed a log back in
+
+    pub record_input: Option<String>,
+    pub replay_input: Option<String>,
+
+    // sample the state every phase_interval simulated seconds into a csv
+
+    pub export_phase: Option<String>,
+    pub phase_interval: f64,
+
+    // plot the trajectory through phase space, one variable against another, to a png on exit
+    // (and when p is pressed, which writes to phase_portrait.png without --portrait)
+
+    pub portrait: Option<String>,
+    pub portrait_axes: (Variable, Variable),
+
+    // pipe every frame into ffmpeg to make a video at the given frame rate
+
+    pub video: Option<String>,
+    pub fps: f32,
+
+    // what y captures of the motion just gone, as a gif
+
+    pub clip_seconds: f64,
+    pub clip_fps: f64,
+
+    pub background: Option<String>, // #rrggbb, a top,bottom gradient of two, or an image's path
+
+    // the sound each arm makes going over the top, if there's sound at all
+
+    pub audio: bool,
+    pub flip_sounds: [Sound; 2],
+
+    // a video keeping ever fewer frames as it goes, so that a long run fits a short clip
+
+    pub timelapse: Option<String>,
+    pub timelapse_duration: f64,
+    pub timelapse_frames: u32,
+
+    pub duration: Option<f64>, // quit once this many seconds have been simulated
+    pub trace_budget: Option<f64>, // pause once the second bob's trace has grown this long
+    pub start_at: Option<f64>, // simulated seconds to step through before the first frame
+    pub paused: bool, // start with the simulation paused
+    pub always_on_top: bool, // keep the window above all the others
+    pub canvas: bool, // accumulate the trace on a texture rather than redrawing its points
+    pub dimensionless: bool, // rescale to natural units, so only the ratios of l and m matter
+    pub frame_dt: Option<f32>, // simulated seconds per frame, instead of following the clock
+    pub max_fps: Option<f32>, // sleep between frames to keep to at most this frame rate
+    pub physics_rate: Option<f64>, // fixed steps per second on a thread of their own
+
+    // drive the total energy towards a setpoint, with the given strength
+
+    pub energy_setpoint: Option<f64>,
+    pub energy_gain: f64,
+
+    // couple the first arm to an external oscillator at this frequency, this strongly
+
+    pub drive: Option<f64>,
+    pub drive_coupling: f64,
+
+    pub drive_torque: Option<Expression>, // newton metres on the first arm, at simulated time t
+
+    pub cart: Option<f64>, // the mass of a cart to hang the pendulum from instead of a fixed pivot
+    pub cart_force: Option<Expression>, // newtons along its track, at simulated time t
+
+    // run this many slightly perturbed copies alongside the pendulum, up to spread apart
+
+    pub ensemble: usize,
+    pub ensemble_spread: f64,
+
+    pub continue_run: bool, // start from the state saved by the last run
+    pub preset: Option<String>, // start from this preset instead of the state in model()
+
+    // the library of named setups, which of them to start from, and what to save this run as
+
+    pub library: String,
+    pub load_named: Option<String>,
+    pub save_named: Option<String>,
+
+    // changes to the start, made after it's been picked by any of the above, so e.g. --preset
+    // raised --m2 3 is the raised preset with a heavier second bob
+
+    pub masses: [Option<f32>; 2],
+    pub lengths: [Option<f32>; 2],
+    pub gravity: Option<f32>,
+    pub angles: [Option<f64>; 2],
+    pub kicks: [Option<f64>; 2],
+
+    pub trace_len: Option<usize>, // points kept in each bob's trace
+}
+
+pub struct ExportOptions {
+    pub file: String,
+    pub preset: String,
+    pub duration: f64,
+    pub dt: f64,
+    pub every: usize, // steps between rows
+    pub integrator: Integrator,
+}
+
+pub struct FlipMapOptions {
+    pub file: String,
+    pub size: u32,
+    pub max_time: f64,
+}
+
+pub struct GalleryOptions {
+    pub dir: String,
+    pub size: u32,
+}
+
+pub struct MonteCarloOptions {
+    pub preset: String,
+    pub samples: usize,
+    pub jitter: f64,
+    pub seed: u64,
+    pub max_time: f64,
+    pub fall: bool, // time falls below horizontal rather than flips over the top
+}
+
+pub struct StressOptions {
+    pub count: usize,
+    pub steps: usize,
+    pub dt: f32,
+}
+
+pub struct SweepOptions {
+    pub file: String,
+    pub parameter: SweepParameter,
+    pub from: f64,
+    pub to: f64,
+    pub samples: usize,
+    pub preset: String,
+    pub duration: f64,
+    pub dt: f32,
+}
+
+pub struct FindChaoticOptions {
+    pub count: usize,
+    pub samples: usize,
+}
+
+impl Command {
+
+    pub fn from_args() -> Self {
+
+        let mut args = std::env::args().skip(1).peekable();
+
+        // anything other than a command name is an option for run
+
+        let command = match args.peek().map(String::as_str) {
+            Some("-h" | "--help") => exit_with_usage(USAGE, 0),
+            Some(arg) if !arg.starts_with('-') => args.next().unwrap(),
+            _ => "run".to_string(),
+        };
+
+        let mut args: Vec<String> = args.collect();
+
+        let usage = match command.as_str() {
+            "run" => RUN_USAGE,
+            "export" => EXPORT_USAGE,
+            "flipmap" => FLIPMAP_USAGE,
+            "gallery" => GALLERY_USAGE,
+            "verify" => VERIFY_USAGE,
+            "find-chaotic" => FIND_CHAOTIC_USAGE,
+            "montecarlo" => MONTECARLO_USAGE,
+            "stress" => STRESS_USAGE,
+            "sweep" => SWEEP_USAGE,
+            _ => exit_with(&format!("unknown command {}\n\n{}", command, USAGE)),
+        };
+
+        if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+            exit_with_usage(usage, 0);
+        }
+
+        // commands that take a positional argument have it first
+
+        let positional = match args.first() {
+            Some(arg) if !arg.starts_with('-') => Some(args.remove(0)),
+            _ => None,
+        };
+
+        let mut args = args.into_iter();
+
+        match command.as_str() {
+            "run" => {
+                if let Some(arg) = positional {
+                    exit_with(&format!("unexpected {}\n\n{}", arg, usage));
+                }
+
+                Command::Run(Box::new(RunOptions::from_args(&mut args, usage)))
+            }
+
+            "export" => {
+                let mut options = ExportOptions {
+                    file: positional.unwrap_or_else(|| exit_with_usage(usage, 1)),
+                    preset: "default".to_string(),
+                    duration: 20.0,
+                    dt: 1.0 / 60.0,
+                    every: 1,
+                    integrator: Integrator::Rk4,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--preset" => options.preset = value(&mut args, &arg),
+                        "--duration" => options.duration = parse(&value(&mut args, &arg), &arg),
+                        "--dt" => options.dt = parse(&value(&mut args, &arg), &arg),
+                        "--every" => options.every = parse(&value(&mut args, &arg), &arg),
+                        "--integrator" => options.integrator = Integrator::parse(&value(&mut args, &arg)).unwrap_or_else(|e| exit_with(&e)),
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                check_duration(options.duration, "--duration");
+                check_positive(options.dt, "--dt");
+
+                if options.every == 0 {
+                    exit_with("--every must be at least 1");
+                }
+
+                Command::Export(options)
+            }
+
+            "flipmap" => {
+                let mut options = FlipMapOptions {
+                    file: positional.unwrap_or_else(|| exit_with_usage(usage, 1)),
+                    size: 200,
+                    max_time: 10.0,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--size" => options.size = parse(&value(&mut args, &arg), &arg),
+                        "--max-time" => options.max_time = parse(&value(&mut args, &arg), &arg),
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                Command::FlipMap(options)
+            }
+
+            "gallery" => {
+                let mut options = GalleryOptions {
+                    dir: positional.unwrap_or_else(|| "gallery".to_string()),
+                    size: 512,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--size" => options.size = parse(&value(&mut args, &arg), &arg),
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                Command::Gallery(options)
+            }
+
+            "verify" => {
+                if let Some(arg) = positional.or_else(|| args.next()) {
+                    unknown(&arg, usage);
+                }
+
+                Command::Verify
+            }
+
+            "find-chaotic" => {
+                let mut options = FindChaoticOptions {
+                    count: positional.map_or(5, |count| parse(&count, "find-chaotic")),
+                    samples: 1000,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--samples" => options.samples = parse(&value(&mut args, &arg), &arg),
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                Command::FindChaotic(options)
+            }
+
+            "montecarlo" => {
+                if let Some(arg) = positional {
+                    unknown(&arg, usage);
+                }
+
+                let mut options = MonteCarloOptions {
+                    preset: "default".to_string(),
+                    samples: 1000,
+                    jitter: 1e-3,
+                    seed: 0,
+                    max_time: 30.0,
+                    fall: false,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--preset" => options.preset = value(&mut args, &arg),
+                        "--samples" => options.samples = parse(&value(&mut args, &arg), &arg),
+                        "--jitter" => options.jitter = parse(&value(&mut args, &arg), &arg),
+                        "--seed" => options.seed = parse(&value(&mut args, &arg), &arg),
+                        "--max-time" => options.max_time = parse(&value(&mut args, &arg), &arg),
+                        "--fall" => options.fall = true,
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                Command::MonteCarlo(options)
+            }
+
+            "stress" => {
+                let mut options = StressOptions {
+                    count: positional.map_or(10_000, |count| parse(&count, "stress")),
+                    steps: 1000,
+                    dt: 0.01,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--steps" => options.steps = parse(&value(&mut args, &arg), &arg),
+                        "--dt" => options.dt = parse(&value(&mut args, &arg), &arg),
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                check_positive(options.dt as f64, "--dt");
+
+                Command::Stress(options)
+            }
+
+            "sweep" => {
+                let mut options = SweepOptions {
+                    file: positional.unwrap_or_else(|| exit_with_usage(usage, 1)),
+                    parameter: SweepParameter::LengthRatio,
+                    from: 0.25,
+                    to: 4.0,
+                    samples: 50,
+                    preset: "default".to_string(),
+                    duration: 30.0,
+                    dt: 0.01,
+                };
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--param" => {
+                            let name = value(&mut args, &arg);
+                            options.parameter = SweepParameter::parse(&name)
+                                .unwrap_or_else(|| exit_with(&format!("--param can't sweep {}\n\n{}", name, usage)));
+                        }
+                        "--from" => options.from = parse(&value(&mut args, &arg), &arg),
+                        "--to" => options.to = parse(&value(&mut args, &arg), &arg),
+                        "--samples" => options.samples = parse(&value(&mut args, &arg), &arg),
+                        "--preset" => options.preset = value(&mut args, &arg),
+                        "--duration" => options.duration = parse(&value(&mut args, &arg), &arg),
+                        "--dt" => options.dt = parse(&value(&mut args, &arg), &arg),
+                        _ => unknown(&arg, usage),
+                    }
+                }
+
+                if options.samples == 0 {
+                    exit_with("--samples must be at least 1");
+                }
+
+                check_duration(options.duration, "--duration");
+                check_positive(options.dt as f64, "--dt");
+
+                Command::Sweep(options)
+            }
+
+            _ => unreachable!("unknown commands were turned away above"),
+        }
+
+    }
+
+}
+
+impl RunOptions {
+
+    fn from_args(args: &mut impl Iterator<Item = String>, usage: &str) -> Self {
+
+        let mut options = RunOptions {
+            replay: None,
+            replay_loop: false,
+            reference: None,
+            compare: None,
+            rival: None,
+            record_input: None,
+            replay_input: None,
+            export_phase: None,
+            phase_interval: 0.01,
+            portrait: None,
+            portrait_axes: (Variable::Theta1, Variable::DotTheta1),
+            video: None,
+            fps: 60.0,
+            clip_seconds: 5.0,
+            clip_fps: 20.0,
+            background: None,
+            audio: false,
+            flip_sounds: [Sound::Click, Sound::Tone(660.0)],
+            timelapse: None,
+            timelapse_duration: 60.0,
+            timelapse_frames: 600,
+            duration: None,
+            trace_budget: None,
+            start_at: None,
+            paused: false,
+            always_on_top: false,
+            canvas: false,
+            dimensionless: false,
+            frame_dt: None,
+            max_fps: None,
+            physics_rate: None,
+            energy_setpoint: None,
+            energy_gain: 0.5,
+            drive: None,
+            drive_coupling: 0.5,
+            drive_torque: None,
+            cart: None,
+            cart_force: None,
+            ensemble: 0,
+            ensemble_spread: 1e-6,
+            continue_run: false,
+            preset: None,
+            library: LIBRARY_FILE.to_string(),
+            load_named: None,
+            save_named: None,
+            masses: [None; 2],
+            lengths: [None; 2],
+            gravity: None,
+            angles: [None; 2],
+            kicks: [None; 2],
+            trace_len: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--replay" => options.replay = Some(value(args, &arg)),
+                "--replay-loop" => options.replay_loop = true,
+                "--record-input" => options.record_input = Some(value(args, &arg)),
+                "--replay-input" => options.replay_input = Some(value(args, &arg)),
+                "--export-phase" => options.export_phase = Some(value(args, &arg)),
+                "--phase-interval" => options.phase_interval = parse(&value(args, &arg), &arg),
+                "--reference" => options.reference = Some(value(args, &arg)),
+                "--compare" => options.compare = Some(parse_changes(&value(args, &arg))),
+                "--rival" => options.rival = Some(value(args, &arg)),
+                "--portrait" => options.portrait = Some(value(args, &arg)),
+                "--portrait-axes" => options.portrait_axes = parse_axes(&value(args, &arg)),
+                "--video" => options.video = Some(value(args, &arg)),
+                "--fps" => options.fps = parse(&value(args, &arg), &arg),
+                "--clip-seconds" => options.clip_seconds = parse(&value(args, &arg), &arg),
+                "--clip-fps" => options.clip_fps = parse(&value(args, &arg), &arg),
+                "--background" => options.background = Some(value(args, &arg)),
+                "--audio" => options.audio = true,
+                "--flip-sound-1" => options.flip_sounds[0] = parse_sound(&value(args, &arg), &arg),
+                "--flip-sound-2" => options.flip_sounds[1] = parse_sound(&value(args, &arg), &arg),
+                "--timelapse" => options.timelapse = Some(value(args, &arg)),
+                "--timelapse-duration" => options.timelapse_duration = parse(&value(args, &arg), &arg),
+                "--timelapse-frames" => options.timelapse_frames = parse(&value(args, &arg), &arg),
+                "--energy-setpoint" => options.energy_setpoint = Some(parse(&value(args, &arg), &arg)),
+                "--energy-gain" => options.energy_gain = parse(&value(args, &arg), &arg),
+                "--drive" => options.drive = Some(parse(&value(args, &arg), &arg)),
+                "--drive-coupling" => options.drive_coupling = parse(&value(args, &arg), &arg),
+                "--drive-torque" => options.drive_torque = Some(parse_expression(&value(args, &arg), &arg)),
+                "--cart" => options.cart = Some(parse(&value(args, &arg), &arg)),
+                "--cart-force" => options.cart_force = Some(parse_expression(&value(args, &arg), &arg)),
+                "--frame-dt" => options.frame_dt = Some(parse(&value(args, &arg), &arg)),
+                "--max-fps" => options.max_fps = Some(parse(&value(args, &arg), &arg)),
+                "--physics-rate" => options.physics_rate = Some(parse(&value(args, &arg), &arg)),
+                "--ensemble" => options.ensemble = parse(&value(args, &arg), &arg),
+                "--ensemble-spread" => options.ensemble_spread = parse(&value(args, &arg), &arg),
+                "--always-on-top" => options.always_on_top = true,
+                "--dimensionless" => options.dimensionless = true,
+                "--canvas" => options.canvas = true,
+                "--continue" => options.continue_run = true,
+                "--m1" => options.masses[0] = Some(parse(&value(args, &arg), &arg)),
+                "--m2" => options.masses[1] = Some(parse(&value(args, &arg), &arg)),
+                "--l1" => options.lengths[0] = Some(parse(&value(args, &arg), &arg)),
+                "--l2" => options.lengths[1] = Some(parse(&value(args, &arg), &arg)),
+                "--gravity" => options.gravity = Some(parse(&value(args, &arg), &arg)),
+                "--theta1" => options.angles[0] = Some(parse(&value(args, &arg), &arg)),
+                "--theta2" => options.angles[1] = Some(parse(&value(args, &arg), &arg)),
+                "--kick1" => options.kicks[0] = Some(parse(&value(args, &arg), &arg)),
+                "--kick2" => options.kicks[1] = Some(parse(&value(args, &arg), &arg)),
+                "--trace-len" => options.trace_len = Some(parse(&value(args, &arg), &arg)),
+                "--preset" => options.preset = Some(value(args, &arg)),
+                "--load-named" => options.load_named = Some(value(args, &arg)),
+                "--save-named" => options.save_named = Some(value(args, &arg)),
+                "--library" => options.library = value(args, &arg),
+                "--inverted" => options.preset = Some("inverted".to_string()),
+                "--duration" => options.duration = Some(parse(&value(args, &arg), &arg)),
+                "--trace-budget" => options.trace_budget = Some(parse(&value(args, &arg), &arg)),
+                "--start-at" => options.start_at = Some(parse(&value(args, &arg), &arg)),
+                "--paused" => options.paused = true,
+                _ => unknown(&arg, usage),
+            }
+        }
+
+        if let Some(fps) = options.max_fps.filter(|fps| !(*fps > 0.0 && fps.is_finite())) {
+            exit_with(&format!("--max-fps must be a positive number, not {}", fps));
+        }
+
+        if let Some(time) = options.start_at {
+            if !(time >= 0.0 && time.is_finite()) {
+                exit_with(&format!("--start-at must be a number of seconds, not {}", time));
+            }
+
+            if options.replay.is_some() {
+                exit_with("--start-at can't be used with --replay, which plays back from the first row");
+            }
+        }
+
+        // the masses, lengths and gravity are checked along with the rest of the parameters once
+        // they're put together, in model()
+
+        for (value, name) in options.angles.iter().zip(["--theta1", "--theta2"]).chain(options.kicks.iter().zip(["--kick1", "--kick2"])) {
+            if let Some(value) = value.filter(|value| !value.is_finite()) {
+                exit_with(&format!("{} must be a number, not {}", name, value));
+            }
+        }
+
+        if options.trace_len == Some(0) {
+            exit_with("--trace-len must be at least 1");
+        }
+
+        let changes_start = options.masses.iter().chain(&options.lengths).any(Option::is_some)
+            || options.gravity.is_some()
+            || options.angles.iter().chain(&options.kicks).any(Option::is_some);
+
+        if changes_start && options.replay.is_some() {
+            exit_with("--replay plays back its own states, so it can't be used with --m1, --m2, --l1, --l2, --gravity, --theta1, --theta2, --kick1 or --kick2");
+        }
+
+        if options.load_named.is_some() && (options.preset.is_some() || options.continue_run || options.replay.is_some()) {
+            exit_with("--load-named can't be used with --preset, --continue or --replay, which all pick their own start");
+        }
+
+        // the name goes in a [name] header, so it can't have anything that would end it early
+
+        if let Some(name) = options.save_named.as_deref() {
+            if name.trim().is_empty() || name.trim() != name || name.contains(['[', ']', '#', '"', '\n']) {
+                exit_with(&format!("--save-named needs a name without brackets, quotes, # or surrounding spaces, not {:?}", name));
+            }
+        }
+
+        for (value, name) in [(options.clip_seconds, "--clip-seconds"), (options.clip_fps, "--clip-fps")] {
+            if !(value > 0.0 && value.is_finite()) {
+                exit_with(&format!("{} must be a positive number, not {}", name, value));
+            }
+        }
+
+        // the time-lapse is of an ensemble, so it brings one along if there isn't one already,
+        // and needs every frame to take the same simulated time for its schedule to line up
+
+        if options.timelapse.is_some() {
+            if options.video.is_some() || options.replay.is_some() || options.physics_rate.is_some() {
+                exit_with("--timelapse can't be used with --video, --replay or --physics-rate");
+            }
+
+            if !(options.timelapse_duration > 0.0 && options.timelapse_duration.is_finite()) {
+                exit_with(&format!("--timelapse-duration must be a positive number, not {}", options.timelapse_duration));
+            }
+
+            if options.ensemble == 0 {
+                options.ensemble = TIMELAPSE_ENSEMBLE;
+            }
+
+            options.frame_dt.get_or_insert(1.0 / 60.0);
+        }
+
+        // what's read in or changed from outside is in si units, with nothing to say how to
+        // convert it
+
+        if options.dimensionless && (options.replay.is_some() || options.reference.is_some() || options.compare.is_some()) {
+            exit_with("--dimensionless can't be used with --replay, --reference or --compare");
+        }
+
+        if options.rival.is_some() && options.replay.is_some() {
+            exit_with("--rival can't be used with --replay, which isn't stepped at all");
+        }
+
+        if options.compare.is_some() && options.replay.is_some() {
+            exit_with("--compare can't be used with --replay, which has nothing to change");
+        }
+
+        if !(options.phase_interval > 0.0 && options.phase_interval.is_finite()) {
+            exit_with(&format!("--phase-interval must be a positive number, not {}", options.phase_interval));
+        }
+
+        if let Some(frequency) = options.drive.filter(|frequency| !(*frequency > 0.0 && frequency.is_finite())) {
+            exit_with(&format!("--drive must be a positive number, not {}", frequency));
+        }
+
+        if !options.drive_coupling.is_finite() {
+            exit_with(&format!("--drive-coupling must be a number, not {}", options.drive_coupling));
+        }
+
+        if let Some(budget) = options.trace_budget.filter(|budget| !(*budget > 0.0 && budget.is_finite())) {
+            exit_with(&format!("--trace-budget must be a positive number, not {}", budget));
+        }
+
+        if let Some(mass) = options.cart.filter(|mass| !(*mass > 0.0 && mass.is_finite())) {
+            exit_with(&format!("--cart must be a positive number, not {}", mass));
+        }
+
+        if options.cart_force.is_some() && options.cart.is_none() {
+            exit_with("--cart-force needs a cart to push, given with --cart");
+        }
+
+        // everything else steps the pendulum from a fixed pivot
+
+        if options.cart.is_some() && (options.replay.is_some() || options.ensemble > 0 || options.compare.is_some() || options.rival.is_some() || options.dimensionless) {
+            exit_with("--cart can't be used with --replay, --ensemble, --compare, --rival or --dimensionless");
+        }
+
+        if let Some(rate) = options.physics_rate {
+            if !(rate > 0.0 && rate.is_finite()) {
+                exit_with(&format!("--physics-rate must be a positive number, not {}", rate));
+            }
+
+            // the thread only knows how to simulate the one pendulum
+
+            if options.replay.is_some() || options.ensemble > 0 || options.drive.is_some() || options.drive_torque.is_some() || options.compare.is_some() || options.rival.is_some() || options.cart.is_some() {
+                exit_with("--physics-rate can't be used with --replay, --ensemble, --drive, --drive-torque, --compare, --rival or --cart");
+            }
+        }
+
+        options
+
+    }
+
+}
+
+// the ensemble --timelapse runs when --ensemble doesn't set one
+
+const TIMELAPSE_ENSEMBLE: usize = 20;
+
+// the argument following a flag that needs one
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> String {
+    args.next().unwrap_or_else(|| exit_with(&format!("{} needs a value", flag)))
+}
+
+fn parse<T: std::str::FromStr>(value: &str, flag: &str) -> T {
+    value.parse().unwrap_or_else(|_| exit_with(&format!("{} can't take {}", flag, value)))
+}
+
+// step sizes and durations. a dt of zero makes the number of steps infinite, and a negative or
+// NaN one quietly takes none
+
+fn check_positive(value: f64, flag: &str) {
+
+    if !(value > 0.0 && value.is_finite()) {
+        exit_with(&format!("{} must be a positive number, not {}", flag, value));
+    }
+
+}
+
+fn check_duration(value: f64, flag: &str) {
+
+    if !(value >= 0.0 && value.is_finite()) {
+        exit_with(&format!("{} must be a number no less than 0, not {}", flag, value));
+    }
+
+}
+
+// a torque or force in the simulated time like 0.5*sin(2*t), for --drive-torque and
+// --cart-force. it's tried out at the start so one that comes out as NaN there (like ln(t)) is
+// caught before the run, not just syntax mistakes
+
+fn parse_expression(text: &str, flag: &str) -> Expression {
+
+    let expression = Expression::parse(text)
+        .unwrap_or_else(|e| exit_with(&format!("{}: {} in {:?}", flag, e, text)));
+
+    if !expression.evaluate(0.0).is_finite() {
+        exit_with(&format!("{}: {} isn't a number at t = 0", flag, text));
+    }
+
+    expression
+
+}
+
+fn parse_sound(text: &str, flag: &str) -> Sound {
+    Sound::parse(text).unwrap_or_else(|| exit_with(&format!("{} expects click, off or a pitch in hertz, not {}", flag, text)))
+}
+
+// a pair of state variables like theta_2,dot_theta_2, for --portrait-axes
+
+fn parse_axes(text: &str) -> (Variable, Variable) {
+
+    let variable = |name: &str| Variable::parse(name.trim()).unwrap_or_else(|| exit_with(&format!(
+        "--portrait-axes: {} isn't one of theta_1, dot_theta_1, theta_2 or dot_theta_2", name,
+    )));
+
+    match text.split_once(',') {
+        Some((x, y)) => (variable(x), variable(y)),
+        None => exit_with(&format!("--portrait-axes expects two variables separated by a comma, not {}", text)),
+    }
+
+}
+
+// parameters to change like m2=2,l2=0.5, for --compare. they're named as for sweep --param, and
+// applied in order
+
+fn parse_changes(text: &str) -> Vec<(SweepParameter, f32)> {
+
+    text.split(',')
+        .map(|change| {
+            let Some((name, value)) = change.split_once('=') else {
+                exit_with(&format!("--compare expects changes like m2=2, not {}", change));
+            };
+
+            let parameter = SweepParameter::parse(name.trim())
+                .unwrap_or_else(|| exit_with(&format!("--compare can't change {}", name)));
+
+            (parameter, parse(value.trim(), "--compare"))
+        })
+        .collect()
+
+}
+
+fn unknown(arg: &str, usage: &str) -> ! {
+    exit_with(&format!("unknown option {}\n\n{}", arg, usage))
+}
+
+// usage goes to stdout when it was asked for, and to stderr along with a failure otherwise
+
+fn exit_with_usage(usage: &str, code: i32) -> ! {
+
+    if code == 0 {
+        println!("{}", usage);
+    } else {
+        eprintln!("{}", usage);
+    }
+
+    std::process::exit(code)
+
+}
+
+pub fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
+
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use nannou::color::named::{CADETBLUE, DIMGRAY, MEDIUMORCHID, WHITESMOKE};
+use nannou::image::gif::{GifEncoder, Repeat};
+use nannou::image::{Delay, DynamicImage, Frame, Rgb, RgbImage};
+
+use double_pendulum::physics::{to_cartesian, Params, State};
+
+use crate::gallery::{draw_line, lerp};
+
+// the last few simulated seconds of motion, kept so that y can save them as an animated gif
+// after the fact, without having to start a recording beforehand. every frame's state goes in,
+// and the gif samples them at its own frame rate, drawing the pendulum as in the gallery with
+// bob 2's trace over the clip so far
+
+pub struct Clip {
+    pub lookback: f64, // simulated seconds kept
+    pub fps: f64, // frames per simulated second in the gif
+    states: VecDeque<(f64, State, Params)>, // sim_time, state and the parameters it was drawn with
+}
+
+const SIZE: u32 = 400; // pixels along each side
+const BOB_RADIUS: f32 = 6.0;
+
+impl Clip {
+
+    pub fn new(lookback: f64, fps: f64) -> Self {
+        Self { lookback, fps, states: VecDeque::new() }
+    }
+
+    pub fn record(&mut self, sim_time: f64, state: &State, params: &Params) {
+
+        self.states.push_back((sim_time, *state, *params));
+
+        while self.states.front().is_some_and(|(time, _, _)| *time < sim_time - self.lookback) {
+            self.states.pop_front();
+        }
+
+    }
+
+    // write the gif to a file named after the current time, away from the app's thread since
+    // encoding takes a while, and report where it went (or what went wrong) when it's done
+
+    pub fn save(&self) {
+
+        let Some((&(start, _, _), &(end, _, _))) = self.states.front().zip(self.states.back()) else {
+            eprintln!("nothing to capture yet");
+            return;
+        };
+
+        // the newest state at or before each frame's time
+
+        let count = ((end - start) * self.fps).floor() as usize + 1;
+        let mut frames = Vec::with_capacity(count);
+        let mut next = 0;
+
+        for k in 0..count {
+            let time = start + k as f64 / self.fps;
+
+            while next + 1 < self.states.len() && self.states[next + 1].0 <= time {
+                next += 1;
+            }
+
+            frames.push((self.states[next].1, self.states[next].2));
+        }
+
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("capture-{}.gif", seconds);
+        let fps = self.fps;
+
+        std::thread::spawn(move || match encode(&frames, fps, &path) {
+            Ok(()) => println!("wrote the last {:.1} s to {}", (count - 1) as f64 / fps, path),
+            Err(e) => eprintln!("{}", e),
+        });
+
+    }
+
+}
+
+fn encode(frames: &[(State, Params)], fps: f64, path: &str) -> Result<(), String> {
+
+    let file = std::fs::File::create(path).map_err(|e| format!("couldn't create {}: {}", path, e))?;
+    let mut encoder = GifEncoder::new(std::io::BufWriter::new(file));
+
+    encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("couldn't write {}: {}", path, e))?;
+
+    // sized so the longest reach in the clip fits, with y flipped as image rows count downwards
+
+    let reach = frames.iter().fold(0.0_f32, |reach, (_, params)| reach.max(params.l1 + params.l2));
+    let scale = 0.45 * SIZE as f32 / reach;
+    let centre = SIZE as f32 / 2.0;
+    let delay = Delay::from_numer_denom_ms(1000, fps.round().max(1.0) as u32);
+
+    let positions: Vec<[(f32, f32); 2]> = frames.iter().map(|(state, params)| {
+        let p1 = to_cartesian(scale * params.l1, state.theta_1);
+        let p2 = to_cartesian(scale * params.l2, state.theta_2);
+        [(centre + p1.x, centre - p1.y), (centre + p1.x + p2.x, centre - (p1.y + p2.y))]
+    }).collect();
+
+    let last = positions.len().saturating_sub(1).max(1) as f32;
+    let rod = Rgb([DIMGRAY.red, DIMGRAY.green, DIMGRAY.blue]);
+
+    for (i, [bob_1, bob_2]) in positions.iter().enumerate() {
+
+        let mut image = RgbImage::from_pixel(SIZE, SIZE, Rgb([WHITESMOKE.red, WHITESMOKE.green, WHITESMOKE.blue]));
+
+        for (j, pair) in positions[..=i].windows(2).enumerate() {
+            draw_line(&mut image, pair[0][1], pair[1][1], lerp(CADETBLUE, MEDIUMORCHID, j as f32 / last));
+        }
+
+        draw_line(&mut image, (centre, centre), *bob_1, rod);
+        draw_line(&mut image, *bob_1, *bob_2, rod);
+
+        for (x, y) in [(centre, centre), *bob_1, *bob_2] {
+            draw_disc(&mut image, x, y, rod);
+        }
+
+        let rgba = DynamicImage::ImageRgb8(image).into_rgba8();
+
+        encoder.encode_frame(Frame::from_parts(rgba, 0, 0, delay)).map_err(|e| format!("couldn't write {}: {}", path, e))?;
+    }
+
+    Ok(())
+
+}
+
+fn draw_disc(image: &mut RgbImage, x: f32, y: f32, colour: Rgb<u8>) {
+
+    let r = BOB_RADIUS.ceil() as i64;
+
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let (px, py) = (x as i64 + dx, y as i64 + dy);
+
+            if (dx * dx + dy * dy) as f32 <= BOB_RADIUS * BOB_RADIUS && px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                image.put_pixel(px as u32, py as u32, colour);
+            }
+        }
+    }
+
+}
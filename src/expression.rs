@@ -0,0 +1,279 @@
+// a small arithmetic expression in the simulated time t, for a driving torque given on the
+// command line, e.g. 0.5*sin(2*t) + 0.1*t. it's parsed once into a tree and then evaluated as
+// often as needed. it understands numbers, t, pi, + - * / ^ (right associative, and binding
+// tighter than a leading minus, so -t^2 is -(t^2)), brackets, and the functions sin, cos, tan,
+// exp, ln, sqrt and abs
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Time,
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(Function, Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Sqrt,
+    Abs,
+}
+
+impl Function {
+
+    const ALL: [(&'static str, Function); 7] = [
+        ("sin", Function::Sin),
+        ("cos", Function::Cos),
+        ("tan", Function::Tan),
+        ("exp", Function::Exp),
+        ("ln", Function::Ln),
+        ("sqrt", Function::Sqrt),
+        ("abs", Function::Abs),
+    ];
+
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+            Function::Sqrt => x.sqrt(),
+            Function::Abs => x.abs(),
+        }
+    }
+
+}
+
+impl Expression {
+
+    // the errors say what was expected and where, counting characters from 1
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+
+        let mut parser = Parser { chars: text.chars().collect(), at: 0 };
+        let expression = parser.sum()?;
+
+        parser.skip_spaces();
+
+        match parser.peek() {
+            None => Ok(expression),
+            Some(c) => Err(parser.error(&format!("unexpected {:?}", c))),
+        }
+
+    }
+
+    pub fn evaluate(&self, t: f64) -> f64 {
+        match self {
+            Expression::Number(value) => *value,
+            Expression::Time => t,
+            Expression::Negate(inner) => -inner.evaluate(t),
+            Expression::Binary(operator, left, right) => {
+                let (a, b) = (left.evaluate(t), right.evaluate(t));
+
+                match operator {
+                    Operator::Add => a + b,
+                    Operator::Subtract => a - b,
+                    Operator::Multiply => a * b,
+                    Operator::Divide => a / b,
+                    Operator::Power => a.powf(b),
+                }
+            }
+            Expression::Call(function, argument) => function.apply(argument.evaluate(t)),
+        }
+    }
+
+}
+
+// recursive descent, one function for each level of precedence from loosest to tightest
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Parser {
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    // the next character, if it's one of these, moving past it
+
+    fn take(&mut self, options: &[char]) -> Option<char> {
+
+        self.skip_spaces();
+
+        let c = self.peek().filter(|c| options.contains(c))?;
+        self.at += 1;
+
+        Some(c)
+
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at character {}", message, self.at + 1)
+    }
+
+    fn sum(&mut self) -> Result<Expression, String> {
+
+        let mut expression = self.product()?;
+
+        while let Some(c) = self.take(&['+', '-']) {
+            let operator = if c == '+' { Operator::Add } else { Operator::Subtract };
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(self.product()?));
+        }
+
+        Ok(expression)
+
+    }
+
+    fn product(&mut self) -> Result<Expression, String> {
+
+        let mut expression = self.unary()?;
+
+        while let Some(c) = self.take(&['*', '/']) {
+            let operator = if c == '*' { Operator::Multiply } else { Operator::Divide };
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(self.unary()?));
+        }
+
+        Ok(expression)
+
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+
+        if self.take(&['-']).is_some() {
+            return Ok(Expression::Negate(Box::new(self.unary()?)));
+        }
+
+        if self.take(&['+']).is_some() {
+            return self.unary();
+        }
+
+        self.power()
+
+    }
+
+    // the exponent can have its own leading minus, as in 2^-t
+
+    fn power(&mut self) -> Result<Expression, String> {
+
+        let base = self.atom()?;
+
+        if self.take(&['^']).is_some() {
+            return Ok(Expression::Binary(Operator::Power, Box::new(base), Box::new(self.unary()?)));
+        }
+
+        Ok(base)
+
+    }
+
+    fn atom(&mut self) -> Result<Expression, String> {
+
+        self.skip_spaces();
+
+        let start = self.at;
+
+        match self.peek() {
+            None => Err(self.error("expected a number, t, a function or (, but the expression ended")),
+
+            Some('(') => {
+                self.at += 1;
+                let inner = self.sum()?;
+
+                match self.take(&[')']) {
+                    Some(_) => Ok(inner),
+                    None => Err(self.error("expected )")),
+                }
+            }
+
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.at += 1;
+                }
+
+                // an exponent, as in 1e-3
+
+                if self.peek().is_some_and(|c| c == 'e' || c == 'E') {
+                    let mark = self.at;
+                    self.at += 1;
+
+                    if self.peek().is_some_and(|c| c == '+' || c == '-') {
+                        self.at += 1;
+                    }
+
+                    if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                            self.at += 1;
+                        }
+                    } else {
+                        self.at = mark;
+                    }
+                }
+
+                let text: String = self.chars[start..self.at].iter().collect();
+
+                text.parse().map(Expression::Number).map_err(|_| {
+                    self.at = start;
+                    self.error(&format!("{} isn't a number", text))
+                })
+            }
+
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    self.at += 1;
+                }
+
+                let name: String = self.chars[start..self.at].iter().collect();
+
+                match name.as_str() {
+                    "t" => return Ok(Expression::Time),
+                    "pi" => return Ok(Expression::Number(std::f64::consts::PI)),
+                    _ => {}
+                }
+
+                let Some((_, function)) = Function::ALL.iter().find(|(known, _)| *known == name) else {
+                    self.at = start;
+                    let names: Vec<&str> = Function::ALL.iter().map(|(known, _)| *known).collect();
+                    return Err(self.error(&format!("unknown name {}, expected t, pi or one of {}", name, names.join(", "))));
+                };
+
+                if self.take(&['(']).is_none() {
+                    return Err(self.error(&format!("expected ( after {}", name)));
+                }
+
+                let argument = self.sum()?;
+
+                match self.take(&[')']) {
+                    Some(_) => Ok(Expression::Call(*function, Box::new(argument))),
+                    None => Err(self.error("expected )")),
+                }
+            }
+
+            Some(c) => Err(self.error(&format!("unexpected {:?}", c))),
+        }
+
+    }
+
+}
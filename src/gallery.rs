@@ -0,0 +1,85 @@
+use nannou::color::named::{CADETBLUE, MEDIUMORCHID, WHITESMOKE};
+use nannou::color::Srgb;
+use nannou::image::{Rgb, RgbImage};
+
+use double_pendulum::physics::{rk4, to_cartesian};
+use double_pendulum::presets::PRESETS;
+
+use crate::cli::{exit_with, GalleryOptions};
+
+// renders the trace of every preset to a png, named after the preset, without opening a window
+
+const DURATION: f32 = 20.0; // simulated seconds per preset
+const DT: f32 = 1.0 / 240.0;
+
+pub fn render(options: &GalleryOptions) {
+
+    let size = options.size;
+
+    std::fs::create_dir_all(&options.dir)
+        .unwrap_or_else(|e| exit_with(&format!("couldn't create {}: {}", options.dir, e)));
+
+    for preset in PRESETS {
+
+        // same transform as view in main.rs, except scaled so the longest reach fits the image
+        // and with y flipped, since image rows count downwards
+
+        let scale = 0.45 * size as f32 / (preset.params.l1 + preset.params.l2);
+        let centre = size as f32 / 2.0;
+
+        let mut state = preset.state;
+        let mut path = Vec::new();
+
+        for _ in 0..(DURATION / DT) as usize {
+
+            state = rk4(&state, &preset.params, DT);
+
+            let p1 = to_cartesian(scale * preset.params.l1, state.theta_1);
+            let p2 = to_cartesian(scale * preset.params.l2, state.theta_2);
+            path.push((centre + p1.x + p2.x, centre - (p1.y + p2.y)));
+        }
+
+        // same gradient as the default theme, oldest to newest
+
+        let mut image = RgbImage::from_pixel(size, size, Rgb([WHITESMOKE.red, WHITESMOKE.green, WHITESMOKE.blue]));
+        let last = path.len().saturating_sub(1).max(1) as f32;
+
+        for (i, pair) in path.windows(2).enumerate() {
+            let colour = lerp(CADETBLUE, MEDIUMORCHID, i as f32 / last);
+            draw_line(&mut image, pair[0], pair[1], colour);
+        }
+
+        let file = format!("{}/{}.png", options.dir, preset.name);
+        image.save(&file).unwrap_or_else(|e| exit_with(&format!("couldn't write {}: {}", file, e)));
+        println!("wrote {}", file);
+    }
+
+}
+
+pub fn lerp(a: Srgb<u8>, b: Srgb<u8>, t: f32) -> Rgb<u8> {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Rgb([mix(a.red, b.red), mix(a.green, b.green), mix(a.blue, b.blue)])
+}
+
+// steps along the line one pixel at a time, stamping a 2x2 block to match the 2px trace
+
+pub fn draw_line(image: &mut RgbImage, from: (f32, f32), to: (f32, f32), colour: Rgb<u8>) {
+
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+
+    for step in 0..=steps {
+
+        let t = step as f32 / steps as f32;
+        let x = (from.0 + (to.0 - from.0) * t) as i64;
+        let y = (from.1 + (to.1 - from.1) * t) as i64;
+
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let (px, py) = (x + dx, y + dy);
+
+            if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                image.put_pixel(px as u32, py as u32, colour);
+            }
+        }
+    }
+
+}
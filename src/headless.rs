@@ -0,0 +1,461 @@
+use std::io::Write;
+
+use nannou::color::named::{CADETBLUE, CRIMSON, GOLD, WHITESMOKE};
+use nannou::image::{Rgb, RgbImage};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{random_range, Rng, SeedableRng};
+
+use double_pendulum::chaos::divergence_rate;
+use double_pendulum::physics::{bob_speeds, derivatives, lagrangian_accelerations, rk4, turns, Params, State};
+use double_pendulum::presets::{self, Preset, DEFAULT_PARAMS, PRESETS};
+use double_pendulum::sim::Simulation;
+use double_pendulum::trajectory;
+
+use crate::cli::{exit_with, ExportOptions, FindChaoticOptions, FlipMapOptions, MonteCarloOptions, StressOptions, SweepOptions};
+use crate::gallery::lerp;
+
+// the commands that run without opening a window
+
+// compare the accelerations from derivatives against ones worked out numerically from the
+// lagrangian, over random states with each preset's parameters
+
+pub fn verify() {
+
+    let mut worst: f64 = 0.0;
+
+    for preset in PRESETS {
+        for _ in 0..1000 {
+
+            let state = State {
+                theta_1: random_range(-std::f64::consts::PI, std::f64::consts::PI),
+                theta_2: random_range(-std::f64::consts::PI, std::f64::consts::PI),
+                dot_theta_1: random_range(-10.0, 10.0),
+                dot_theta_2: random_range(-10.0, 10.0),
+            };
+
+            let analytic = derivatives(&state, &preset.params);
+            let numeric = lagrangian_accelerations(&state, &preset.params);
+
+            for (a, n) in [(analytic.dot_theta_1, numeric.0), (analytic.dot_theta_2, numeric.1)] {
+                worst = worst.max((a - n).abs() / a.abs().max(1.0));
+            }
+        }
+    }
+
+    println!("max relative discrepancy over {} random states: {:.2e}", 1000 * PRESETS.len(), worst);
+
+    // the numerical estimate loses accuracy at high speeds, but a mistake would be out by
+    // far more than this
+
+    if worst >= 1e-4 {
+        exit_with("derivatives disagrees with the lagrangian, check the equations of motion");
+    }
+
+}
+
+// try random starting angles (from rest, with the default parameters) and print the ones
+// whose nearby runs pull away from each other fastest, written out as presets ready to paste
+// into src/presets.rs
+
+pub fn find_chaotic(options: &FindChaoticOptions) {
+
+    let mut found: Vec<(f64, State)> = (0..options.samples)
+        .map(|_| {
+            let state = State {
+                theta_1: random_range(-std::f64::consts::PI, std::f64::consts::PI),
+                theta_2: random_range(-std::f64::consts::PI, std::f64::consts::PI),
+                dot_theta_1: 0.0,
+                dot_theta_2: 0.0,
+            };
+
+            (divergence_rate(&state, &DEFAULT_PARAMS, 10.0, 0.01), state)
+        })
+        .collect();
+
+    found.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (i, (rate, state)) in found.iter().take(options.count).enumerate() {
+        println!("    // nearby runs separate at {:.2}/s", rate);
+        println!();
+        println!(
+            "    Preset {{ name: \"chaotic-{}\", state: at_rest({:.4}, {:.4}), params: DEFAULT_PARAMS }},",
+            i + 1, state.theta_1, state.theta_2,
+        );
+        println!();
+    }
+
+}
+
+fn find_preset(name: &str) -> &'static Preset {
+    presets::find(name).unwrap_or_else(|e| exit_with(&e))
+}
+
+// run a preset and write its states to a csv that --replay can play back
+
+pub fn export(options: &ExportOptions) {
+
+    let preset = find_preset(&options.preset);
+    let mut sim = Simulation::new(preset.state, preset.params).unwrap_or_else(|e| exit_with(&e));
+
+    sim.integrator = options.integrator;
+
+    let rows = sim.record(options.duration, options.dt, options.every).unwrap_or_else(|e| exit_with(&e));
+    let failed = |e: std::io::Error| -> ! { exit_with(&format!("couldn't write {}: {}", options.file, e)) };
+
+    let file = std::fs::File::create(&options.file).unwrap_or_else(|e| failed(e));
+    trajectory::write_states(&mut std::io::BufWriter::new(file), &rows).unwrap_or_else(|e| failed(e));
+
+    println!("wrote {} states to {}", rows.len(), options.file);
+
+}
+
+// how long the pendulum takes to flip an arm over the top, starting from rest at each pair of
+// angles in a grid covering -pi..pi both ways (theta_1 across, theta_2 up). quick flips are red,
+// through yellow to blue for slow ones on a log scale, and starts that don't flip before
+// max_time are left as the background. starts too low down don't have the energy to flip at
+// all, which shows up as the blank region in the middle
+
+const FLIP_DT: f32 = 0.01;
+
+pub fn flipmap(options: &FlipMapOptions) {
+
+    let size = options.size as usize;
+    let mut times: Vec<Option<f64>> = vec![None; size * size];
+
+    let angle = |i: usize| std::f64::consts::PI * (2.0 * (i as f64 + 0.5) / size as f64 - 1.0);
+
+    // each start is independent, so share the rows out between threads
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let rows_per_thread = size.div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        for (chunk, rows) in times.chunks_mut(rows_per_thread * size).enumerate() {
+            scope.spawn(move || {
+                for (k, time) in rows.iter_mut().enumerate() {
+
+                    let (row, col) = (chunk * rows_per_thread + k / size, k % size);
+                    let start = State { theta_1: angle(col), theta_2: angle(size - 1 - row), dot_theta_1: 0.0, dot_theta_2: 0.0 };
+
+                    *time = flip_time(&start, &DEFAULT_PARAMS, options.max_time);
+                }
+            });
+        }
+    });
+
+    let mut image = RgbImage::from_pixel(options.size, options.size, Rgb([WHITESMOKE.red, WHITESMOKE.green, WHITESMOKE.blue]));
+
+    for (k, time) in times.iter().enumerate() {
+        if let Some(time) = time {
+
+            let t = ((1.0 + time).ln() / (1.0 + options.max_time).ln()) as f32;
+            let colour = if t < 0.5 { lerp(CRIMSON, GOLD, 2.0 * t) } else { lerp(GOLD, CADETBLUE, 2.0 * t - 1.0) };
+
+            image.put_pixel((k % size) as u32, (k / size) as u32, colour);
+        }
+    }
+
+    image.save(&options.file).unwrap_or_else(|e| exit_with(&format!("couldn't write {}: {}", options.file, e)));
+    println!("wrote {}", options.file);
+
+}
+
+fn flip_time(start: &State, params: &Params, max_time: f64) -> Option<f64> {
+
+    let initial = (turns(start.theta_1), turns(start.theta_2));
+    let mut state = *start;
+
+    for step in 1..=(max_time / FLIP_DT as f64) as usize {
+
+        state = rk4(&state, params, FLIP_DT);
+
+        if (turns(state.theta_1), turns(state.theta_2)) != initial {
+            return Some(step as f64 * FLIP_DT as f64);
+        }
+    }
+
+    None
+
+}
+
+// how long until either arm has fallen more than a quarter turn from straight up, for starts
+// balanced near the top. the angles are upright at odd multiples of pi, where cos is -1
+
+fn fall_time(start: &State, params: &Params, max_time: f64) -> Option<f64> {
+
+    let mut state = *start;
+
+    for step in 1..=(max_time / FLIP_DT as f64) as usize {
+
+        state = rk4(&state, params, FLIP_DT);
+
+        if state.theta_1.cos() > 0.0 || state.theta_2.cos() > 0.0 {
+            return Some(step as f64 * FLIP_DT as f64);
+        }
+    }
+
+    None
+
+}
+
+// run many copies of a preset, each with gaussian noise added to every part of its starting
+// state, and summarise how long they took to flip an arm over: a histogram, then the mean and
+// spread of the flip times and how many flipped at all within max_time. with fall set it's the
+// time to fall from the top instead
+
+const HISTOGRAM_BINS: usize = 20;
+
+pub fn montecarlo(options: &MonteCarloOptions) {
+
+    let preset = find_preset(&options.preset);
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    let times: Vec<Option<f64>> = (0..options.samples)
+        .map(|_| {
+            let mut noise = || options.jitter * gaussian(&mut rng);
+
+            let start = State {
+                theta_1: preset.state.theta_1 + noise(),
+                theta_2: preset.state.theta_2 + noise(),
+                dot_theta_1: preset.state.dot_theta_1 + noise(),
+                dot_theta_2: preset.state.dot_theta_2 + noise(),
+            };
+
+            if options.fall {
+                fall_time(&start, &preset.params, options.max_time)
+            } else {
+                flip_time(&start, &preset.params, options.max_time)
+            }
+        })
+        .collect();
+
+    let flipped: Vec<f64> = times.iter().flatten().copied().collect();
+
+    // one row per bin of flip time, with a bar as long as its share of the runs
+
+    let mut bins = [0; HISTOGRAM_BINS];
+    let bin_width = options.max_time / HISTOGRAM_BINS as f64;
+
+    for time in &flipped {
+        bins[((time / bin_width) as usize).min(HISTOGRAM_BINS - 1)] += 1;
+    }
+
+    let tallest = bins.iter().copied().max().unwrap_or(0).max(1);
+
+    for (i, count) in bins.iter().enumerate() {
+        println!(
+            "{:6.2}-{:6.2}s {:6} {}",
+            i as f64 * bin_width,
+            (i + 1) as f64 * bin_width,
+            count,
+            "#".repeat(count * 50 / tallest),
+        );
+    }
+
+    let n = flipped.len() as f64;
+    let mean = flipped.iter().sum::<f64>() / n.max(1.0);
+    let variance = flipped.iter().map(|time| (time - mean) * (time - mean)).sum::<f64>() / (n - 1.0).max(1.0);
+
+    let (event, name) = if options.fall { ("fell", "fall") } else { ("flipped", "flip") };
+
+    println!();
+    println!("{}={}/{} ({:.1}%)", event, flipped.len(), times.len(), 100.0 * n / times.len().max(1) as f64);
+
+    if !flipped.is_empty() {
+        println!("mean_{}_time={:.3}", name, mean);
+        println!("std_{}_time={:.3}", name, variance.sqrt());
+    }
+
+}
+
+// a benchmark of the physics on its own: step many independent pendulums (starting spread out
+// over the angles, so none of them are alike) first on one thread and then shared out between
+// all of them, printing how long each took and the steps per second. both should land on
+// exactly the same states, which is checked so the work can't be skipped either
+
+pub fn stress(options: &StressOptions) {
+
+    let starts: Vec<State> = (0..options.count)
+        .map(|i| {
+            let t = i as f64 / options.count.max(1) as f64;
+            State { theta_1: 3.0 * t, theta_2: 3.0 - 6.0 * t, dot_theta_1: 0.0, dot_theta_2: 1.0 - 2.0 * t }
+        })
+        .collect();
+
+    let run = |states: &mut [State]| {
+        for state in states {
+            for _ in 0..options.steps {
+                *state = rk4(state, &DEFAULT_PARAMS, options.dt);
+            }
+        }
+    };
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let total_steps = (options.count * options.steps) as f64;
+
+    let mut single = starts.clone();
+    let clock = std::time::Instant::now();
+    run(&mut single);
+    let single_time = clock.elapsed().as_secs_f64();
+
+    let mut multi = starts;
+    let per_thread = options.count.div_ceil(threads).max(1);
+    let clock = std::time::Instant::now();
+
+    std::thread::scope(|scope| {
+        for chunk in multi.chunks_mut(per_thread) {
+            scope.spawn(|| run(chunk));
+        }
+    });
+
+    let multi_time = clock.elapsed().as_secs_f64();
+
+    if single != multi {
+        exit_with("the single and multi threaded runs disagree");
+    }
+
+    println!("{} pendulums, {} steps each", options.count, options.steps);
+    println!("1 thread: {:.3}s, {:.3e} steps/s", single_time, total_steps / single_time);
+    println!("{} threads: {:.3}s, {:.3e} steps/s ({:.1}x)", threads, multi_time, total_steps / multi_time, single_time / multi_time);
+
+}
+
+// the parameter a sweep steps through. the ratios change the second arm's length or mass and
+// leave the first's as the preset has it
+
+#[derive(Clone, Copy)]
+pub enum SweepParameter {
+    L1,
+    L2,
+    M1,
+    M2,
+    Gravity,
+    LengthRatio,
+    MassRatio,
+}
+
+impl SweepParameter {
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "l1" => Some(SweepParameter::L1),
+            "l2" => Some(SweepParameter::L2),
+            "m1" => Some(SweepParameter::M1),
+            "m2" => Some(SweepParameter::M2),
+            "gravity" => Some(SweepParameter::Gravity),
+            "length-ratio" => Some(SweepParameter::LengthRatio),
+            "mass-ratio" => Some(SweepParameter::MassRatio),
+            _ => None,
+        }
+    }
+
+    // the heading of its column in the csv
+
+    pub fn column(self) -> &'static str {
+        match self {
+            SweepParameter::L1 => "l1",
+            SweepParameter::L2 => "l2",
+            SweepParameter::M1 => "m1",
+            SweepParameter::M2 => "m2",
+            SweepParameter::Gravity => "gravity",
+            SweepParameter::LengthRatio => "l2/l1",
+            SweepParameter::MassRatio => "m2/m1",
+        }
+    }
+
+    pub fn apply(self, params: &Params, value: f32) -> Params {
+        match self {
+            SweepParameter::L1 => Params { l1: value, ..*params },
+            SweepParameter::L2 => Params { l2: value, ..*params },
+            SweepParameter::M1 => Params { m1: value, ..*params },
+            SweepParameter::M2 => Params { m2: value, ..*params },
+            SweepParameter::Gravity => Params { gravity: value, ..*params },
+            SweepParameter::LengthRatio => Params { l2: value * params.l1, ..*params },
+            SweepParameter::MassRatio => Params { m2: value * params.m1, ..*params },
+        }
+    }
+
+}
+
+// run a preset at evenly spaced values of one parameter, and write how fast the second bob
+// went at each: its top speed, and the root mean square of its speed over every step, which
+// sums up how violent the motion was as a whole
+
+pub fn sweep(options: &SweepOptions) {
+
+    let preset = find_preset(&options.preset);
+
+    let values: Vec<f64> = (0..options.samples)
+        .map(|i| match options.samples {
+            1 => options.from,
+            n => options.from + (options.to - options.from) * i as f64 / (n - 1) as f64,
+        })
+        .collect();
+
+    let params: Vec<Params> = values.iter()
+        .map(|value| {
+            let params = options.parameter.apply(&preset.params, *value as f32);
+
+            params.validate()
+                .unwrap_or_else(|e| exit_with(&format!("{} = {}: {}", options.parameter.column(), value, e)));
+
+            params
+        })
+        .collect();
+
+    let mut speeds: Vec<(f64, f64)> = vec![(0.0, 0.0); values.len()];
+    let steps = (options.duration / options.dt as f64).round() as usize;
+
+    // each value is independent, so share them out between threads
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let per_thread = values.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        for (chunk, results) in params.chunks(per_thread).zip(speeds.chunks_mut(per_thread)) {
+            scope.spawn(move || {
+                for (params, result) in chunk.iter().zip(results) {
+
+                    let mut state = preset.state;
+                    let (mut max, mut squares) = (0.0_f64, 0.0);
+
+                    for _ in 0..steps {
+                        state = rk4(&state, params, options.dt);
+
+                        let speed = bob_speeds(&state, params).1;
+                        max = max.max(speed);
+                        squares += speed * speed;
+                    }
+
+                    *result = (max, (squares / steps.max(1) as f64).sqrt());
+                }
+            });
+        }
+    });
+
+    let failed = |e: std::io::Error| -> ! { exit_with(&format!("couldn't write {}: {}", options.file, e)) };
+
+    let file = std::fs::File::create(&options.file).unwrap_or_else(|e| failed(e));
+    let mut csv = std::io::BufWriter::new(file);
+
+    writeln!(csv, "{},max_speed_2,rms_speed_2", options.parameter.column()).unwrap_or_else(|e| failed(e));
+
+    for (value, (max, rms)) in values.iter().zip(&speeds) {
+        writeln!(csv, "{},{},{}", value, max, rms).unwrap_or_else(|e| failed(e));
+    }
+
+    csv.flush().unwrap_or_else(|e| failed(e));
+    println!("wrote {} values of {} to {}", values.len(), options.parameter.column(), options.file);
+
+}
+
+// a sample from the standard normal distribution, by the box-muller transform
+
+fn gaussian(rng: &mut impl Rng) -> f64 {
+
+    let u1: f64 = 1.0 - rng.gen::<f64>(); // in (0, 1], so its log is finite
+    let u2: f64 = rng.gen();
+
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+
+}
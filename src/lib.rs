@@ -0,0 +1,11 @@
+// the physics behind the simulation, kept free of any drawing so it can be reused and tested.
+// it doesn't need nannou, which only the app (main.rs) uses, so it builds on its own with
+// --no-default-features. Simulation in sim.rs is the simplest way in
+
+pub mod cart;
+pub mod chaos;
+pub mod expression;
+pub mod physics;
+pub mod presets;
+pub mod sim;
+pub mod trajectory;
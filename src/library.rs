@@ -0,0 +1,167 @@
+use std::io::Write;
+
+use double_pendulum::physics::{Params, State};
+use double_pendulum::presets::DEFAULT_PARAMS;
+
+// a library of named setups kept in one file, to build up a collection of interesting ones
+// across runs. --load-named starts from one, n steps through them all in the order they're
+// written, and --save-named adds the state a run closes with. it's a small part of toml: a
+// [name] header for each setup, then one `key = number` per line, with # starting a comment
+//
+//     [chaos-1]
+//     theta_1 = 2.0
+//     theta_2 = 2.0
+//     m2 = 0.5
+//
+// the angles are required. the angular velocities are 0 if they're left out, and the
+// parameters are those of the default preset
+
+pub const LIBRARY_FILE: &str = "configs.toml";
+
+pub struct Config {
+    pub name: String,
+    pub state: State,
+    pub params: Params,
+}
+
+// a setup as it's read, before its values are checked
+
+struct Section {
+    name: String,
+    line_number: usize, // of its header
+    values: Vec<(&'static str, f64)>,
+}
+
+const KEYS: [&str; 9] = ["theta_1", "theta_2", "dot_theta_1", "dot_theta_2", "l1", "l2", "m1", "m2", "gravity"];
+
+pub fn load(path: &str) -> Result<Vec<Config>, String> {
+
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+
+    // gather each setup's values first, so they can be checked once it's all been read
+
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (i, line) in contents.lines().enumerate() {
+
+        let line_number = i + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let error = |message: String| format!("{}:{}: {}", path, line_number, message);
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.strip_suffix(']')
+                .ok_or_else(|| error(format!("expected [name], got {:?}", line)))?
+                .trim()
+                .trim_matches('"');
+
+            if name.is_empty() {
+                return Err(error("a setup needs a name".to_string()));
+            }
+
+            if let Some(first) = sections.iter().find(|section| section.name == name) {
+                return Err(error(format!("{} is already the name of the setup on line {}", name, first.line_number)));
+            }
+
+            sections.push(Section { name: name.to_string(), line_number, values: Vec::new() });
+            continue;
+        }
+
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| error(format!("expected key = number, got {:?}", line)))?;
+        let key = key.trim();
+
+        let Some(key) = KEYS.iter().find(|known| **known == key) else {
+            return Err(error(format!("unknown key {}, expected one of {}", key, KEYS.join(", "))));
+        };
+
+        let value: f64 = value.trim().parse()
+            .ok()
+            .filter(|value: &f64| value.is_finite())
+            .ok_or_else(|| error(format!("{} isn't a number", key)))?;
+
+        let Some(Section { values, .. }) = sections.last_mut() else {
+            return Err(error(format!("{} comes before any [name]", key)));
+        };
+
+        if values.iter().any(|(existing, _)| existing == key) {
+            return Err(error(format!("{} is given twice", key)));
+        }
+
+        values.push((*key, value));
+    }
+
+    sections.into_iter().map(|Section { name, line_number, values }| {
+
+        let get = |key: &str| values.iter().find(|(existing, _)| *existing == key).map(|(_, value)| *value);
+        let angle = |key: &str| get(key).ok_or_else(|| format!("{}:{}: {} is missing {}", path, line_number, name, key));
+
+        let state = State {
+            theta_1: angle("theta_1")?,
+            theta_2: angle("theta_2")?,
+            dot_theta_1: get("dot_theta_1").unwrap_or(0.0),
+            dot_theta_2: get("dot_theta_2").unwrap_or(0.0),
+        };
+
+        let parameter = |key: &str, default: f32| get(key).map_or(default, |value| value as f32);
+
+        let params = Params::new(
+            parameter("l1", DEFAULT_PARAMS.l1),
+            parameter("l2", DEFAULT_PARAMS.l2),
+            parameter("m1", DEFAULT_PARAMS.m1),
+            parameter("m2", DEFAULT_PARAMS.m2),
+            parameter("gravity", DEFAULT_PARAMS.gravity),
+        ).map_err(|e| format!("{}:{}: {}: {}", path, line_number, name, e))?;
+
+        Ok(Config { name, state, params })
+
+    }).collect()
+
+}
+
+// the position in the library of the setup with the given name, or an error listing the ones
+// there are
+
+pub fn find(configs: &[Config], name: &str, path: &str) -> Result<usize, String> {
+    configs.iter().position(|config| config.name == name).ok_or_else(|| {
+        let names: Vec<&str> = configs.iter().map(|config| config.name.as_str()).collect();
+
+        if names.is_empty() {
+            format!("no setup called {} in {}, which has none", name, path)
+        } else {
+            format!("no setup called {} in {}, try one of {}", name, path, names.join(", "))
+        }
+    })
+}
+
+// add a setup to the end of the library, creating the file if there isn't one yet. the
+// applied torque isn't saved, as with --continue
+
+pub fn append(path: &str, name: &str, state: &State, params: &Params) -> Result<(), String> {
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("couldn't open {}: {}", path, e))?;
+
+    let entry = format!(
+        "\n[{}]\ntheta_1 = {}\ntheta_2 = {}\ndot_theta_1 = {}\ndot_theta_2 = {}\nl1 = {}\nl2 = {}\nm1 = {}\nm2 = {}\ngravity = {}\n",
+        name,
+        state.theta_1,
+        state.theta_2,
+        state.dot_theta_1,
+        state.dot_theta_2,
+        params.l1,
+        params.l2,
+        params.m1,
+        params.m2,
+        params.gravity,
+    );
+
+    file.write_all(entry.as_bytes()).map_err(|e| format!("couldn't write {}: {}", path, e))
+
+}
@@ -1,299 +1,4135 @@
+use std::collections::VecDeque;
+use std::io::Write;
+
 use nannou::prelude::*;
 
+use double_pendulum::cart::{cart_rk4, Cart, CartState};
+use double_pendulum::expression::Expression;
+use double_pendulum::chaos::{convex_hull, drive_torque, ensemble, oscillation_phase, phase_distance, synchronization, LoopDetector, LyapunovEstimate, Regime, RegimeThresholds};
+use double_pendulum::physics::{angular_momentum, bob_accelerations, bob_speeds, energy, energy_pump_torque, gravity_torque, interpolate, mass_denominator, natural_units, normal_mode_frequencies, project_energy, rk4, rod_forces, substep, swing_up_torque, to_cartesian, turns, velocity_components, Adaptive, GravitySchedule, JointControl, Params, State, StepStats, DENOMINATOR_WARNING};
+use double_pendulum::presets;
+use double_pendulum::sim::{Integrator, Simulation};
+use double_pendulum::trajectory;
+
+mod audio;
+mod canvas;
+mod cli;
+mod clip;
+mod gallery;
+mod headless;
+mod library;
+mod persist;
+mod phase_export;
+mod portrait;
+mod sim_thread;
+mod video;
+
+use audio::{Audio, Sound};
+use canvas::Canvas;
+use clip::Clip;
+use phase_export::PhaseExport;
+use portrait::Portrait;
+use sim_thread::SimThread;
+use cli::{exit_with, Command, RunOptions};
+use video::{TimeLapse, Video};
+
+// the command line is read before nannou starts, so commands that don't need a window can run
+// without one. model() picks the run options up from here
+
+static OPTIONS: std::sync::OnceLock<RunOptions> = std::sync::OnceLock::new();
+
 fn main() {
-    nannou::app(model)
-        .update(update)
-        .simple_window(view)
-        .run()
+
+    match Command::from_args() {
+        Command::Run(options) => {
+            OPTIONS.get_or_init(|| *options);
+
+            nannou::app(model)
+                .update(update)
+                .exit(exit)
+                .run()
+        }
+
+        Command::Export(options) => headless::export(&options),
+        Command::FlipMap(options) => headless::flipmap(&options),
+        Command::Gallery(options) => gallery::render(&options),
+        Command::Verify => headless::verify(),
+        Command::FindChaotic(options) => headless::find_chaotic(&options),
+        Command::MonteCarlo(options) => headless::montecarlo(&options),
+        Command::Stress(options) => headless::stress(&options),
+        Command::Sweep(options) => headless::sweep(&options),
+    }
+
+}
+
+struct Model {
+    state: State, // vector [theta_1, theta_2, dot_theta_1, dot_theta_2]
+    initial_state: State, // where the run started, to go back to when the integrator changes
+    params: Params, // arm lengths, bob masses, and gravity
+
+    sim_time: f64, // simulated seconds since the start
+    duration: Option<f64>, // when to quit, if ever
+    paused: bool, // nothing moves until space is pressed
+
+    // a fixed amount of simulated time to advance each frame. normally dt is however long the
+    // last frame took, which keeps to real time but makes a recording stutter wherever
+    // rendering lagged
+
+    frame_dt: Option<f32>,
+
+    // how many simulated seconds pass per second of dt, for slow motion or fast forward, which
+    // [ and ] turn down and up. a frame's dt is covered in as many of sim's fixed steps as it
+    // brings due, so however fast it runs the steps stay short enough for the fixed step
+    // integrators to be stable. max_step is the longest step anything is stepped with in one go,
+    // which only comes into it for rkf45 and the runs alongside it unless set below the fixed step
+
+    time_scale: f32,
+    max_step: f32,
+
+    // with --physics-rate, the thread doing the stepping instead of update
+
+    sim_thread: Option<SimThread>,
+
+    // with --max-fps, the shortest a frame is allowed to take, and when the last one started
+
+    min_frame: Option<std::time::Duration>,
+    last_frame: std::time::Instant,
+
+    // the main run's stepping: its integrator, rkf45's adaptive steps when that's the one, the
+    // fixed steps it keeps up with the frames in, and the state it's got to, which state is
+    // drawn a little behind. anything that moves the pendulum by hand places it here too
+
+    sim: Simulation,
+
+    // how much simulated time rkf45's step statistics cover, so they can be logged once per
+    // simulated second
+
+    stats_time: f32,
+    dragging_tolerance: bool, // whether the tolerance slider is being dragged
+
+    // steps rejected since switching to rkf45, and how many more (real) seconds the indicator
+    // by the slider stays lit after the latest one
+
+    rejections: u32,
+    rejection_flash: f32,
+
+    // keys currently held down, with how many (real) seconds for. once a mass, length, gravity
+    // or time scale key has been held for repeat_delay it keeps nudging its value, repeat_rate
+    // presses' worth per second
+
+    held_keys: Vec<(Key, f32)>,
+    repeat_delay: f32,
+    repeat_rate: f32,
+
+    // which bob (1 or 2) is held by the mouse, if either. the pendulum stays still while it's
+    // held, with the held arm pointing at the cursor, and is let go from rest
+
+    grabbed_bob: Option<usize>,
+    click_to_place: bool, // clicking away from the bobs starts the pendulum afresh from there
+
+    // j and u kick the first and second arm on by kick rad/s, and kick_flash counts down the
+    // (real) seconds left of the ring drawn round each kicked bob
+
+    kick: f64,
+    kick_flash: [f32; 2],
+
+    // rk4 slowly drifts in energy, so optionally rescale the velocities after each step to
+    // hold the total energy at target_energy (set at the start, and again whenever the masses
+    // or lengths are changed)
+
+    conserve_energy: bool,
+    target_energy: f64,
+
+    // the angular momentum about the pivot at the same moment, which the monitor measures
+    // drift from along with the energy
+
+    target_momentum: f64,
+
+    // alternatively drive the energy towards energy_setpoint with torques on the arms, damping
+    // the motion when there's too much and pumping it when there's too little. this keeps a run
+    // going indefinitely without it either dying out or flying apart
+
+    energy_setpoint: Option<f64>,
+    energy_gain: f64,
+
+    drive: Option<Drive>, // an external oscillator the first arm is coupled to
+
+    // with --drive-torque, a torque on the first arm given as an expression in the simulated
+    // time, and what it came to this frame
+
+    drive_torque: Option<Expression>,
+    drive_torque_now: f64,
+
+    // gravity follows this schedule through the run if there is one, and otherwise stays at
+    // params.gravity
+
+    gravity_schedule: Option<GravitySchedule>,
+
+    // with --dimensionless, how many seconds the natural unit of time sqrt(l1/g) came to before
+    // everything was rescaled into it. the readout labels are then in natural units
+
+    tau: Option<f64>,
+
+    // when replaying a recorded trajectory the integrator isn't used at all
+
+    replay: Option<Replay>,
+
+    // keypresses are written to input_log as they happen when recording, and when replaying a
+    // log they're held in scripted_keys until the simulation reaches their time
+
+    input_log: Option<std::io::BufWriter<std::fs::File>>,
+    scripted_keys: VecDeque<(f64, Key)>,
+
+    phase_export: Option<PhaseExport>, // with --export-phase, where the raw states are sampled to
+
+    // every state since the start, plotted to portrait_file as a phase portrait on exit and to
+    // PORTRAIT_FILE (or portrait_file if set) when p is pressed
+
+    portrait: Portrait,
+    portrait_file: Option<String>,
+
+    // the setups in the library file, in order, and which of them n last loaded. with
+    // --save-named, the name the state the run closes with is added under
+
+    library: Vec<library::Config>,
+    library_file: String,
+    library_index: Option<usize>,
+    save_named: Option<String>,
+
+    window: window::Id,
+    video: Option<Video>,
+    timelapse: Option<TimeLapse>, // which frames go into the video, with --timelapse
+
+    // copies of the pendulum started a tiny distance from it, drawn faintly and coloured by how
+    // far they've since drifted from it. they're always stepped with rk4, but otherwise follow
+    // the same rules (energy projection, the energy pump, and any changed parameters)
+
+    ensemble: Vec<State>,
+    ensemble_size: usize,
+    ensemble_spread: f64,
+
+    // running totals summarised when the app closes
+
+    stats: RunStats,
+
+    // the last max_path_length positions of each bob, used for tracing their paths. stored in
+    // metres from the pivot so they can be redrawn at any scale. None marks a break in the
+    // trace (e.g. where it was frozen) so no line is drawn across it
+    //
+    // only update ever changes them, even with --physics-rate (the thread hands over states,
+    // never points), and view only runs between updates with the model borrowed immutably. so
+    // every frame is drawn from the traces exactly as the last update left them, trimming and
+    // all, without needing a copy of its own
+
+    trail_bob1: VecDeque<Option<Point2>>,
+    trail_bob2: VecDeque<Option<Point2>>,
+    max_path_length: usize,
+
+    // how far along bob 2's trace has been drawn since it was last cleared, in metres, old
+    // points trimmed off the end included, and with --trace-budget how long to let it get before
+    // pausing
+
+    trace_length: f64,
+    trace_budget: Option<f64>,
+
+    // with --reference, bob 2's path from an earlier run, in the same metres from the pivot,
+    // drawn faintly behind the trace to compare against. r shows / hides it
+
+    reference: Vec<Point2>,
+    show_reference: bool,
+
+    comparison: Option<Comparison>,
+    rival: Option<Rival>,
+    cart: Option<CartRun>,
+
+    // how far (in metres) a bob has to move from the last point stored for it before another
+    // is, so the points are spread evenly along the path rather than in time. slow stretches
+    // then take up few points and fast ones many
+
+    min_trail_step: f32,
+
+    // with Some, the traces are sampled at every multiple of this many simulated seconds rather
+    // than once a frame, with the pendulum's position in between frames interpolated. the
+    // points then don't depend on how fast frames are drawn, so a trace comes out the same on
+    // any machine
+
+    trail_sample_dt: Option<f64>,
+
+    // nothing is traced for the first trace_start_delay simulated seconds, so the transient from
+    // a dramatic start doesn't clutter the picture. trace_from is the time it ends at
+
+    trace_start_delay: f64,
+    trace_from: f64,
+
+    // with strobe on, the whole pendulum is also drawn at strobe_count past states, one every
+    // strobe_stride frames, fading into the past like a multiple exposure photo. with ghost rods
+    // on, the rods alone are drawn at each of the last ghost_frames states as a smear behind
+    // them, like motion blur. states_seen holds just enough states for both
+
+    strobe: bool,
+    strobe_stride: usize,
+    strobe_count: usize,
+    ghost_rods: bool,
+    ghost_frames: usize,
+    states_seen: VecDeque<State>,
+
+    clip: Clip, // the last few seconds, for y to save as a gif
+
+    // with a canvas, each new stretch of the traces is drawn onto it as it comes in and it's
+    // the canvas that's shown, so the traces never fade (and are a solid colour). the trails
+    // above are still kept, just for their most recent points
+
+    canvas: Option<Canvas>,
+
+    // when frozen the pendulum keeps moving but nothing is added to the trace
+
+    trail_frozen: bool,
+    trace_frame: TraceFrame,
+    mirror: Mirror,
+    fade: Option<FadeCurve>, // how the trails fade out with age, or None to keep them solid
+
+    // where the top of the first arm is fixed, in window coordinates (0, 0 is the centre)
+
+    pivot: Point2,
+
+    // the scroll wheel zooms in and out about the cursor, scaling everything in the scene
+    // (though not the overlays) by zoom. dragging with the right button or holding the arrow
+    // keys pans it, by camera_offset metres, and panning is where the right button was last
+    // seen during a drag
+
+    zoom: f32,
+    camera_offset: Vec2,
+    panning: Option<Point2>,
+
+    // histogram of where bob 2 has spent its time, drawn as a heatmap behind the trace
+
+    density: Density,
+    show_density: bool,
+
+    // one bob coordinate against another, drawn in the top right corner when enabled. only the
+    // chosen pair is kept, so switching axes starts the figure afresh
+
+    lissajous_axes: Option<PlotAxes>,
+    lissajous: VecDeque<Point2>,
+
+    // (sim_time, energy) over the last ENERGY_HISTORY seconds, charted in the bottom right
+    // corner when enabled. always recorded, so the chart is full as soon as it's shown
+
+    energy_history: VecDeque<(f64, f64)>,
+    show_energy: bool,
+
+    // (sim_time, gravity's torque about the pivot) over the same window, charted above it
+
+    torque_history: VecDeque<(f64, f64)>,
+
+    show_monitor: bool, // the conserved quantities and their drift, above the integrator's name
+
+    // the smallest box the second bob has stayed inside since the traces were last cleared, in
+    // metres from the pivot, measured off by rulers along its sides when they're shown
+
+    envelope: Option<Rect>,
+    show_rulers: bool,
+
+    audio: Option<Audio>, // with --audio, unless there was no way to play it
+    flip_sounds: [Sound; 2],
+
+    // (sim_time, difference between the arms' phases) over the last SYNC_WINDOW seconds, which
+    // the synchronization shown in the readout is worked out from
+
+    phase_history: VecDeque<(f64, f64)>,
+
+    // a running estimate of how fast nearby runs pull away from this one, and the regime it
+    // makes the motion out to be, for the badge at the top of the window. the estimate is only
+    // kept up while the pendulum is being simulated in update
+
+    lyapunov: LyapunovEstimate,
+    regime: Option<Regime>,
+    regime_thresholds: RegimeThresholds,
+
+    // watches for the run coming back to a state it's been in before. each time it does, the
+    // period it took is shown in the readout and loop_flash counts down the real seconds the
+    // second bob's trace is lit up for
+
+    loops: LoopDetector,
+    loop_period: Option<f64>,
+    loop_flash: f32,
+
+    velocity_display: VelocityDisplay,
+    show_acceleration: bool, // colour the bobs by how hard they're accelerating
+    show_rod_forces: bool, // draw the rods by how hard they're pulling or pushing
+    show_dials: bool, // each arm's angle as a clock hand in the top left
+
+    // o steps through the controllers on the first joint (see ControlMode). holding, it's a pd
+    // controller pushing it towards control.target with control's gains, which , and . turn by
+    // CONTROL_TARGET_STEP. swinging up, balance_target is the upright it's caught at and is
+    // being held at, if it has been. the pd part is handed to the physics through
+    // params.control, and pump_torque goes on top of params.torque for each of a frame's steps.
+    // control_torque is the two together, for the readout
+
+    control: JointControl,
+    control_mode: Option<ControlMode>,
+    swing_up: SwingUp,
+    balance_target: Option<f64>,
+    pump_torque: f64,
+    control_torque: f64,
+    show_mass_labels: bool, // write each bob's mass beside it
+
+    theme: Theme,
+    background_image: Option<wgpu::Texture>, // loaded from the theme's Background::Image
+}
+
+// a coarse square grid over everywhere bob 2 can reach, each cell holding how long the bob has
+// spent in it. quasiperiodic motion keeps retracing the same regions, so their cells brighten.
+//
+// how long in a cell it takes to be drawn at full strength is the exposure. left to itself it
+// follows the most visited cell, so that one is always at full strength and the map neither
+// washes out over a long run nor stays faint at the start. it eases towards it over `smoothing`
+// seconds (0 to follow it exactly), so a cell suddenly taking the lead doesn't make the rest
+// flicker
+
+struct Density {
+    resolution: usize, // cells along each side
+    decay: f32, // fraction of each cell's value kept per second, so old visits fade
+    exposure: Option<f32>, // fixed seconds in a cell for full strength, instead of following the brightest
+    smoothing: f32,
+    saturation: f32, // seconds in a cell for full strength at the moment
+    cells: Vec<f32>,
+}
+
+impl Density {
+
+    fn new(resolution: usize, decay: f32, exposure: Option<f32>, smoothing: f32) -> Self {
+        Self { resolution, decay, exposure, smoothing, saturation: exposure.unwrap_or(0.0), cells: vec![0.0; resolution * resolution] }
+    }
+
+    fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = 0.0);
+        self.saturation = self.exposure.unwrap_or(0.0);
+    }
+
+    // how strongly to draw a cell, from nothing to 1
+
+    fn strength(&self, value: f32) -> f32 {
+        (value / self.saturation.max(1e-6)).min(1.0)
+    }
+
+    // the grid spans [-extent, extent] in both directions around the pivot
+
+    fn record(&mut self, position: Point2, extent: f32, dt: f32) {
+
+        let fade = self.decay.powf(dt);
+
+        for cell in self.cells.iter_mut() {
+            *cell *= fade;
+        }
+
+        let index = |v: f32| {
+            let i = ((v + extent) / (2.0 * extent) * self.resolution as f32).floor();
+            i.clamp(0.0, self.resolution as f32 - 1.0) as usize
+        };
+
+        let (col, row) = (index(position.x), index(position.y));
+        self.cells[row * self.resolution + col] += dt;
+
+        if self.exposure.is_none() {
+            let brightest = self.cells.iter().fold(0.0, |max: f32, cell| max.max(*cell));
+            let ease = if self.smoothing > 0.0 { 1.0 - (-dt / self.smoothing).exp() } else { 1.0 };
+
+            self.saturation += (brightest - self.saturation) * ease;
+        }
+
+    }
+
+}
+
+// with --compare, a second pendulum with some of its parameters changed, started from the same
+// state and stepped through the same frames by the same integrator, drawn in the right half of
+// the window. it keeps its parameters as they were at the start: the keys, controllers, drive
+// and energy setpoint only act on the main run. whenever the main run is restarted, moved by
+// hand or kicked, it's put back alongside it
+
+struct Comparison {
+    params: Params,
+    state: State,
+    adaptive: Adaptive, // for when the integrator is rkf45
+    trail: VecDeque<Option<Point2>>, // bob 2's, as for the main run
+    label: String, // what's been changed
+}
+
+// with --rival, the same pendulum stepped from the same state through the same fixed steps with a
+// different integrator, for how much the choice of method alone changes the trajectory. it's
+// never drawn, only how far it's got from the main run in phase space, which in the chaotic
+// regime grows exponentially from rounding level. it follows the same torques as the ensemble
+// does, working out the energy setpoint and the drive from its own state
+
+struct Rival {
+    integrator: Integrator,
+    state: State,
+    adaptive: Adaptive, // for when it's rkf45
+    separation: VecDeque<(f64, f64)>, // (sim_time, phase_distance) over the last SEPARATION_HISTORY seconds
+}
+
+const SEPARATION_HISTORY: f64 = 30.0;
+
+// with --cart, the pendulum hangs from a cart running along a horizontal track (see cart.rs),
+// pushed by a force given as an expression in the simulated time, like --drive-torque. the
+// pendulum's own state stays in Model::state, so everything else goes on as usual, and the
+// cart's position and speed are kept here. it's always stepped with rk4
+
+struct CartRun {
+    mass: f64,
+    force: Option<Expression>,
+    force_now: f64, // newtons, this frame
+    x: f64, // metres from the pivot
+    dot_x: f64,
+}
+
+const CART_SIZE: (f32, f32) = (60.0, 24.0); // pixels
+
+// in metres from the pivot, where the first arm hangs from
+
+fn cart_offset(model: &Model) -> Vec2 {
+    vec2(model.cart.as_ref().map_or(0.0, |cart| cart.x) as f32, 0.0)
+}
+
+// the pendulum's been put somewhere by hand, so the stepping carries on from there, whether
+// that's the main run's simulation or the physics thread
+
+fn hand_over(model: &mut Model) {
+
+    model.sim.place(model.state);
+
+    if let Some(sim_thread) = &model.sim_thread {
+        sim_thread.hold(&model.state);
+    }
+
+}
+
+// start the comparison and the rival again from the main run's state
+
+fn sync_comparison(model: &mut Model) {
+
+    if let Some(comparison) = &mut model.comparison {
+        comparison.state = model.state;
+        comparison.adaptive = Adaptive::new(model.sim.adaptive.tolerance);
+        break_trail(&mut comparison.trail);
+    }
+
+    if let Some(rival) = &mut model.rival {
+        rival.state = model.state;
+        rival.adaptive = Adaptive::new(model.sim.adaptive.tolerance);
+        rival.separation.clear();
+    }
+
+}
+
+// an external harmonic oscillator, which is nothing but a phase going round at a steady rate,
+// and how strongly the first arm is pulled towards it (see drive_torque)
+
+struct Drive {
+    frequency: f64, // rad/s
+    coupling: f64, // newton metres
+    phase: f64,
+}
+
+impl Drive {
+
+    // params with the drive's torque on the first arm added, for the given state
+
+    fn apply(&self, state: &State, params: &Params) -> Params {
+        let torque = drive_torque(state, self.phase, self.frequency, self.coupling) as f32;
+        Params { torque: (params.torque.0 + torque, params.torque.1), ..*params }
+    }
+
+}
+
+// a recorded trajectory being played back one row per frame
+
+struct Replay {
+    states: Vec<State>,
+    index: usize,
+    looping: bool,
+}
+
+fn load_input_log(file: &str) -> VecDeque<(f64, Key)> {
+
+    let log = std::fs::read_to_string(file)
+        .unwrap_or_else(|e| exit_with(&format!("couldn't read {}: {}", file, e)));
+
+    // one "<simulated time> <key>" per line
+
+    log.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let (time, key) = line.trim().split_once(' ')
+                .unwrap_or_else(|| exit_with(&format!("{} line {}: expected \"<time> <key>\"", file, i + 1)));
+
+            let time = time.parse()
+                .unwrap_or_else(|_| exit_with(&format!("{} line {}: {} is not a time", file, i + 1, time)));
+
+            let key = BOUND_KEYS.iter()
+                .find(|bound| format!("{:?}", bound) == key)
+                .unwrap_or_else(|| exit_with(&format!("{} line {}: {} isn't a key the app uses", file, i + 1, key)));
+
+            (time, *key)
+        })
+        .collect()
+
+}
+
+fn load_replay(file: &str, looping: bool) -> Replay {
+
+    let csv = std::fs::read_to_string(file)
+        .unwrap_or_else(|e| exit_with(&format!("couldn't read {}: {}", file, e)));
+
+    let states = trajectory::read_states(&csv)
+        .unwrap_or_else(|e| exit_with(&format!("couldn't load {}: {}", file, e)));
+
+    Replay { states, index: 0, looping }
+
+}
+
+// bob 2's positions through a recorded run, worked out with the given arm lengths since the
+// file only has the angles
+
+fn load_reference(file: &str, params: &Params) -> Vec<Point2> {
+
+    let csv = std::fs::read_to_string(file)
+        .unwrap_or_else(|e| exit_with(&format!("couldn't read {}: {}", file, e)));
+
+    let states = trajectory::read_states(&csv)
+        .unwrap_or_else(|e| exit_with(&format!("couldn't load {}: {}", file, e)));
+
+    states.iter()
+        .map(|state| {
+            let p1 = to_cartesian(params.l1, state.theta_1);
+            let p2 = to_cartesian(params.l2, state.theta_2);
+            pt2(p1.x + p2.x, p1.y + p2.y)
+        })
+        .collect()
+
+}
+
+struct RunStats {
+    flips_1: u64,
+    flips_2: u64,
+    min_energy: f64,
+    max_energy: f64,
+    max_speed_1: f64,
+    max_speed_2: f64,
+    min_denominator: f64, // lowest value of the denominator in the equations of motion
+}
+
+impl RunStats {
+
+    fn new() -> Self {
+        Self {
+            flips_1: 0,
+            flips_2: 0,
+            min_energy: f64::INFINITY,
+            max_energy: f64::NEG_INFINITY,
+            max_speed_1: 0.0,
+            max_speed_2: 0.0,
+            min_denominator: f64::INFINITY,
+        }
+    }
+
+    // fold in one update's step from previous to current
+
+    fn record(&mut self, previous: &State, current: &State, params: &Params) {
+
+        self.flips_1 += turns(current.theta_1).abs_diff(turns(previous.theta_1));
+        self.flips_2 += turns(current.theta_2).abs_diff(turns(previous.theta_2));
+
+        let e = energy(current, params);
+        self.min_energy = self.min_energy.min(e);
+        self.max_energy = self.max_energy.max(e);
+
+        let (v1, v2) = bob_speeds(current, params);
+        self.max_speed_1 = self.max_speed_1.max(v1);
+        self.max_speed_2 = self.max_speed_2.max(v2);
+
+        // warn the first time the equations come close to dividing by zero
+
+        let denominator = mass_denominator(current, params);
+
+        if denominator < DENOMINATOR_WARNING && self.min_denominator >= DENOMINATOR_WARNING {
+            eprintln!("the denominator in the equations of motion has dropped to {:.2e}, so the accelerations are no longer reliable", denominator);
+        }
+
+        self.min_denominator = self.min_denominator.min(denominator);
+
+    }
+
+}
+
+// how much of each bob's radial / tangential velocity to show: nothing, the numbers under the
+// parameter readout, or those along with arrows on the bobs
+
+#[derive(Clone, Copy, PartialEq)]
+enum VelocityDisplay {
+    Hidden,
+    Numbers,
+    Arrows,
+}
+
+// how the trace fades out with age, if at all. each gives the opacity of a point some fraction
+// of the way back along the trail (0 at the newest point, 1 at the oldest): linear fades at an
+// even rate, exponential drops off quickly and then lingers as a faint tail, and logarithmic
+// goes by the log of the age, so faint traces of the oldest points stay visible however long
+// the trail is
+
+#[derive(Clone, Copy)]
+enum FadeCurve {
+    Linear,
+    Exponential,
+    Logarithmic,
+}
+
+impl FadeCurve {
+
+    // the next curve along, or None after the last one to stop fading
+
+    fn next(self) -> Option<Self> {
+        match self {
+            FadeCurve::Linear => Some(FadeCurve::Exponential),
+            FadeCurve::Exponential => Some(FadeCurve::Logarithmic),
+            FadeCurve::Logarithmic => None,
+        }
+    }
+
+    // age is in points back from the newest, out of length
+
+    fn opacity(self, age: f32, length: f32) -> f32 {
+        match self {
+            FadeCurve::Linear => 1.0 - age / length,
+            FadeCurve::Exponential => (-5.0 * age / length).exp(),
+            FadeCurve::Logarithmic => 1.0 - (1.0 + age).ln() / (1.0 + length).ln(),
+        }
+    }
+
+}
+
+// what the second bob's trace is measured from: the pivot, as it's seen, or the first bob, which
+// leaves just the second arm swinging about it with the first arm's motion taken out. either way
+// the points are drawn out from the pivot, so the relative trace is centred there, on where the
+// first bob sits in its own frame, and reaches no further than l2
+
+#[derive(Clone, Copy)]
+enum TraceFrame {
+    Lab,
+    RelativeToBob1,
+}
+
+impl TraceFrame {
+
+    fn next(self) -> Self {
+        match self {
+            TraceFrame::Lab => TraceFrame::RelativeToBob1,
+            TraceFrame::RelativeToBob1 => TraceFrame::Lab,
+        }
+    }
+
+    fn point(self, bob_1: Vec2, bob_2: Vec2) -> Vec2 {
+        match self {
+            TraceFrame::Lab => bob_2,
+            TraceFrame::RelativeToBob1 => bob_2 - bob_1,
+        }
+    }
+
+    // the first bob doesn't move in its own frame, so it has no trace there
+
+    fn traces_bob1(self) -> bool {
+        matches!(self, TraceFrame::Lab)
+    }
+
+}
+
+// copies of the trace drawn reflected or rotated about the pivot, for kaleidoscope patterns:
+// none, mirrored left to right, mirrored across both axes, or turned through each quarter turn
+
+#[derive(Clone, Copy)]
+enum Mirror {
+    None,
+    Vertical,
+    Both,
+    FourFold,
+}
+
+impl Mirror {
+
+    fn next(self) -> Self {
+        match self {
+            Mirror::None => Mirror::Vertical,
+            Mirror::Vertical => Mirror::Both,
+            Mirror::Both => Mirror::FourFold,
+            Mirror::FourFold => Mirror::None,
+        }
+    }
+
+    // every image of the trace to draw, as maps of points measured from the pivot. the trace
+    // itself comes first
+
+    fn images(self) -> &'static [fn(Vec2) -> Vec2] {
+        match self {
+            Mirror::None => &[|p| p],
+            Mirror::Vertical => &[|p| p, |p| vec2(-p.x, p.y)],
+            Mirror::Both => &[|p| p, |p| vec2(-p.x, p.y), |p| vec2(p.x, -p.y), |p| -p],
+            Mirror::FourFold => &[|p| p, |p| vec2(-p.y, p.x), |p| -p, |p| vec2(p.y, -p.x)],
+        }
+    }
+
+}
+
+// which position of which bob goes along each axis of the parametric plot, as (horizontal,
+// vertical), both measured from the pivot
+
+#[derive(Clone, Copy)]
+enum PlotAxes {
+    X1Y2,
+    X1X2,
+    Y1Y2,
+    Y1X2,
+}
+
+impl PlotAxes {
+
+    // the next pairing along, or None after the last one to hide the plot
+
+    fn next(self) -> Option<Self> {
+        match self {
+            PlotAxes::X1Y2 => Some(PlotAxes::X1X2),
+            PlotAxes::X1X2 => Some(PlotAxes::Y1Y2),
+            PlotAxes::Y1Y2 => Some(PlotAxes::Y1X2),
+            PlotAxes::Y1X2 => None,
+        }
+    }
+
+    fn point(self, bob_1: Point2, bob_2: Point2) -> Point2 {
+        match self {
+            PlotAxes::X1Y2 => pt2(bob_1.x, bob_2.y),
+            PlotAxes::X1X2 => pt2(bob_1.x, bob_2.x),
+            PlotAxes::Y1Y2 => pt2(bob_1.y, bob_2.y),
+            PlotAxes::Y1X2 => pt2(bob_1.y, bob_2.x),
+        }
+    }
+
+    fn labels(self) -> (&'static str, &'static str) {
+        match self {
+            PlotAxes::X1Y2 => ("x1", "y2"),
+            PlotAxes::X1X2 => ("x1", "x2"),
+            PlotAxes::Y1Y2 => ("y1", "y2"),
+            PlotAxes::Y1X2 => ("y1", "x2"),
+        }
+    }
+
+}
+
+// what's drawn behind everything else: a flat colour, a gradient from the top of the window to
+// the bottom, or an image (the path to one) stretched to fill the window. the default theme uses
+// a solid colour, and --background picks any of them
+
+enum Background {
+    Solid(Srgb<u8>),
+    Gradient(Srgb<u8>, Srgb<u8>),
+    Image(String),
+}
+
+impl Background {
+
+    // a colour as #rrggbb, two of them as top,bottom for a gradient, or anything else as the
+    // path to an image
+
+    fn parse(text: &str) -> Result<Self, String> {
+
+        let color = |hex: &str| {
+            let digits = hex.strip_prefix('#').filter(|digits| digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()));
+            let channel = |digits: &str, i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+
+            digits.map(|digits| Srgb::new(channel(digits, 0), channel(digits, 2), channel(digits, 4)))
+                .ok_or_else(|| format!("{} isn't a colour, expected #rrggbb", hex))
+        };
+
+        if !text.starts_with('#') {
+            return Ok(Background::Image(text.to_string()));
+        }
+
+        match text.split_once(',') {
+            Some((top, bottom)) => Ok(Background::Gradient(color(top.trim())?, color(bottom.trim())?)),
+            None => Ok(Background::Solid(color(text)?)),
+        }
+
+    }
+
+}
+
+// what the labels measure each quantity in: si units, or with --dimensionless the natural
+// ones, in which l1, m1 and g are all 1 and time is counted in tau = sqrt(l1/g)
+
+struct Units {
+    time: &'static str,
+    length: &'static str,
+    mass: &'static str,
+    acceleration: &'static str,
+    rate: &'static str, // of an angle
+    energy: &'static str,
+    torque: &'static str,
+    angular_momentum: &'static str,
+}
+
+const SI_UNITS: Units = Units {
+    time: "s",
+    length: "m",
+    mass: "kg",
+    acceleration: "m/s²",
+    rate: "rad/s",
+    energy: "J",
+    torque: "N m",
+    angular_momentum: "kg m²/s",
+};
+
+const NATURAL_UNITS: Units = Units {
+    time: "τ",
+    length: "l1",
+    mass: "m1",
+    acceleration: "g",
+    rate: "rad/τ",
+    energy: "m1 g l1",
+    torque: "m1 g l1",
+    angular_momentum: "m1 l1²/τ",
+};
+
+fn units(model: &Model) -> &'static Units {
+    if model.tau.is_some() { &NATURAL_UNITS } else { &SI_UNITS }
+}
+
+// colours and sizes used when drawing, kept together so the look can be changed in one place
+
+struct Theme {
+    background: Background,
+    mechanism: Srgb<u8>,
+
+    // the trace of bob 2 fades from trace_start at its oldest point to trace_end at its newest,
+    // and the same for bob 1
+
+    trace_start: Srgb<u8>,
+    trace_end: Srgb<u8>,
+    trace_bob1_start: Srgb<u8>,
+    trace_bob1_end: Srgb<u8>,
+
+    text: Srgb<u8>,
+
+    // velocity arrows along and across the rods
+
+    radial: Srgb<u8>,
+    tangential: Srgb<u8>,
+
+    // ensemble members run through these from near the pendulum to far from it
+
+    ensemble_colors: [Srgb<u8>; 3],
+    regime_colors: [Srgb<u8>; 3], // the badge for periodic, quasiperiodic and chaotic motion
+
+    grabbed: Srgb<u8>, // for the bob held by the mouse, and the crosshair at the cursor
+
+    // with acceleration colouring on, the bobs go from calm to hot as their acceleration goes
+    // from zero up to max_acceleration (in m/s^2), and stay hot beyond it
+
+    acceleration_calm: Srgb<u8>,
+    acceleration_hot: Srgb<u8>,
+    max_acceleration: f32,
+
+    // with rod forces on, each rod goes from mechanism towards tension, thicker, the harder it
+    // pulls, and towards compression, thinner, the harder it pushes. either is at its strongest
+    // at max_rod_load times the rod's load hanging still
+
+    tension: Srgb<u8>,
+    compression: Srgb<u8>,
+    max_rod_load: f32,
+    rejection: Srgb<u8>, // the indicator that lights up when rkf45 rejects a step
+    kick: Srgb<u8>, // the ring that flashes round a bob when it's kicked
+    density: Srgb<u8>, // heatmap colour, drawn more opaque the longer a cell has been visited
+    reference: Srgb<u8>, // the --reference trace, which is drawn translucent
+    loop_closure: Srgb<u8>, // the second bob's trace as it flashes on closing a loop
+    ruler: Srgb<u8>, // the rulers measuring how far the second bob has reached
+
+    // sizes in points. nannou already maps points to physical pixels, but the defaults look
+    // thin on high-dpi displays, so with scale_with_dpi they're also multiplied by the window's
+    // scale factor to keep the same visual weight there
+
+    trace_weight: f32,
+    rod_weight: f32,
+    bob_radius: f32,
+    scale_with_dpi: bool,
+
+    // draw each rod as a tapering bar, rod_weight across where it hangs from and this fraction
+    // of that at its bob, rather than a line of even weight. None for plain lines
+
+    rod_taper: Option<f32>,
+
+    // draw a wide faint copy under the trace so it seems to glow
+
+    trace_glow: bool,
+}
+
+// draw a trail, colouring each vertex by its position along it (from colors.0 at the oldest
+// to colors.1 at the newest) so the direction of time is visible. each unbroken stretch is its
+// own polyline
+
+#[allow(clippy::too_many_arguments)]
+fn draw_trail(
+    draw: &Draw,
+    trail: &VecDeque<Option<Point2>>,
+    colors: (Srgb<u8>, Srgb<u8>),
+    pivot: Point2,
+    scale: f32,
+    weight: f32,
+    glow: bool,
+    fade: Option<FadeCurve>,
+) {
+
+    let last = trail.len().saturating_sub(1).max(1) as f32;
+    let mut segment = Vec::new();
+
+    let draw_segment = |segment: &mut Vec<(Point2, Srgba)>| {
+
+        if glow {
+            draw.polyline()
+                .stroke_weight(4.0 * weight)
+                .points_colored(segment.iter().map(|(p, c)| (*p, srgba(c.red, c.green, c.blue, 0.15 * c.alpha))));
+        }
+
+        draw.polyline()
+            .stroke_weight(weight)
+            .points_colored(segment.drain(..));
+    };
+
+    for (i, point) in trail.iter().enumerate() {
+        match point {
+            Some(p) => {
+                let color = lerp_color(colors.0, colors.1, i as f32 / last);
+                let alpha = fade.map_or(1.0, |fade| fade.opacity(last - i as f32, last));
+
+                segment.push((pivot + *p * scale, srgba(color.red, color.green, color.blue, alpha)));
+            }
+            None => draw_segment(&mut segment),
+        }
+    }
+
+    draw_segment(&mut segment);
+
+}
+
+// linear interpolation between two colours, t = 0.0 gives a and t = 1.0 gives b
+
+fn lerp_color(a: Srgb<u8>, b: Srgb<u8>, t: f32) -> Srgb {
+
+    let a = a.into_format::<f32>();
+    let b = b.into_format::<f32>();
+
+    srgb(
+        a.red + (b.red - a.red) * t,
+        a.green + (b.green - a.green) * t,
+        a.blue + (b.blue - a.blue) * t,
+    )
+
+}
+
+fn model(app: &App) -> Model {
+
+    let options = OPTIONS.get().unwrap();
+
+    let replay = options.replay.as_deref().map(|file| load_replay(file, options.replay_loop));
+    let scripted_keys = options.replay_input.as_deref().map(load_input_log).unwrap_or_default();
+
+    let input_log = options.record_input.as_deref().map(|file| {
+        let log = std::fs::File::create(file)
+            .unwrap_or_else(|e| exit_with(&format!("couldn't create {}: {}", file, e)));
+        std::io::BufWriter::new(log)
+    });
+
+    let mut window = app.new_window()
+        .view(view)
+        .key_pressed(key_pressed)
+        .key_released(key_released)
+        .unfocused(unfocused)
+        .mouse_pressed(mouse_pressed)
+        .mouse_moved(mouse_moved)
+        .mouse_released(mouse_released)
+        .mouse_wheel(mouse_wheel);
+
+    // video frames are read straight from the frame texture, which can't be multisampled, and
+    // have to stay the same size
+
+    if options.video.is_some() || options.timelapse.is_some() {
+        window = window.msaa_samples(1).resizable(false);
+    }
+
+    let window = window.build().unwrap();
+
+    // set on winit directly, since nannou 0.19's always_on_top puts the window underneath
+    // everything instead
+
+    if options.always_on_top {
+        app.window(window).unwrap().winit_window().set_window_level(nannou::winit::window::WindowLevel::AlwaysOnTop);
+    }
+
+    let video = options.video.as_deref().or(options.timelapse.as_deref()).map(|file| {
+        let size = app.window(window).unwrap().inner_size_pixels();
+        Video::start(file, options.fps, size).unwrap_or_else(|e| exit_with(&e))
+    });
+
+    let mut model = Model {
+
+        state: State {
+
+            // initial displacements
+
+            theta_1: 0.0,
+            theta_2: 2.0,
+
+            // initial velocities ('kick')
+
+            dot_theta_1: 0.0,
+            dot_theta_2: 0.0,
+        },
+
+        // worth noting that smaller values make the physics feel more realistic (1m is more
+        // familiar than 100...) but whenever these are drawn we multiply by 100 to make them
+        // visible without changing the physics
+
+        initial_state: State::new(0.0, 0.0, 0.0, 0.0), // set below, once the start is settled
+
+        params: Params {
+            l1: 1.0,
+            l2: 1.0,
+
+            m1: 1.0,
+            m2: 1.0,
+
+            gravity: 10.0,
+            gravity_scale_1: 1.0, // how much of it pulls on each bob
+            gravity_scale_2: 1.0,
+
+            torque: (0.0, 0.0),
+            control: JointControl::OFF,
+        },
+
+        sim_time: 0.0,
+        duration: options.duration,
+        frame_dt: options.frame_dt,
+        paused: options.paused,
+        time_scale: 1.0,
+        max_step: 0.02, // seconds
+        sim_thread: None,
+        min_frame: options.max_fps.map(|fps| std::time::Duration::from_secs_f32(1.0 / fps)),
+        last_frame: std::time::Instant::now(),
+
+        sim: Simulation::new(State::new(0.0, 0.0, 0.0, 0.0), presets::DEFAULT_PARAMS).unwrap(), // placed below, once the start is settled
+        stats_time: 0.0,
+        dragging_tolerance: false,
+        rejections: 0,
+        rejection_flash: 0.0,
+
+        held_keys: Vec::new(),
+        repeat_delay: 0.4,
+        repeat_rate: 10.0,
+        grabbed_bob: None,
+        click_to_place: true,
+        kick: 2.0,
+        kick_flash: [0.0; 2],
+
+        conserve_energy: false,
+        target_energy: 0.0,
+        target_momentum: 0.0,
+
+        energy_setpoint: options.energy_setpoint,
+        energy_gain: options.energy_gain,
+        gravity_schedule: None, // e.g. Some(GravitySchedule::Ramp { from: 0.0, to: 10.0, duration: 5.0 })
+        tau: None,
+        drive: options.drive.map(|frequency| Drive { frequency, coupling: options.drive_coupling, phase: 0.0 }),
+        drive_torque: options.drive_torque.clone(),
+        drive_torque_now: 0.0,
+
+        replay: None,
+
+        input_log,
+        scripted_keys,
+        phase_export: None,
+        portrait: Portrait::new(options.portrait_axes),
+        portrait_file: options.portrait.clone(),
+        library: Vec::new(),
+        library_file: options.library.clone(),
+        library_index: None,
+        save_named: options.save_named.clone(),
+
+        window,
+        video,
+        timelapse: None,
+
+        ensemble: Vec::new(),
+        ensemble_size: options.ensemble,
+        ensemble_spread: options.ensemble_spread,
+
+        stats: RunStats::new(),
+
+        trail_bob1: VecDeque::new(),
+        trail_bob2: VecDeque::new(),
+        max_path_length: 500, // determines trace decay
+        trace_length: 0.0,
+        trace_budget: options.trace_budget,
+        reference: Vec::new(),
+        show_reference: false,
+        comparison: None,
+        rival: None,
+        cart: options.cart.map(|mass| CartRun { mass, force: options.cart_force.clone(), force_now: 0.0, x: 0.0, dot_x: 0.0 }),
+        min_trail_step: 0.01,
+        trail_sample_dt: None, // once a frame
+        trace_start_delay: 0.0,
+        trace_from: 0.0,
+
+        strobe: false,
+        strobe_stride: 6,
+        strobe_count: 8,
+        ghost_rods: false,
+        ghost_frames: 6,
+        states_seen: VecDeque::new(),
+        clip: Clip::new(options.clip_seconds, options.clip_fps),
+        canvas: options.canvas.then(|| Canvas::new(&app.window(window).unwrap())),
+        trail_frozen: false,
+        trace_frame: TraceFrame::Lab,
+        mirror: Mirror::None,
+        fade: None,
+
+        pivot: pt2(0.0, 0.0),
+        zoom: 1.0,
+        camera_offset: vec2(0.0, 0.0),
+        panning: None,
+
+        density: Density::new(64, 0.95, None, 0.5),
+        show_density: false,
+
+        lissajous_axes: None,
+        lissajous: VecDeque::new(),
+
+        energy_history: VecDeque::new(),
+        show_energy: false,
+        torque_history: VecDeque::new(),
+        show_monitor: false,
+        envelope: None,
+        show_rulers: false,
+        audio: options.audio.then(Audio::start).flatten(),
+        flip_sounds: options.flip_sounds,
+
+        phase_history: VecDeque::new(),
+        lyapunov: LyapunovEstimate::new(20.0), // simulated seconds averaged over
+        regime: None,
+        regime_thresholds: RegimeThresholds { quasiperiodic: 0.1, chaotic: 0.5, hysteresis: 0.05 }, // per second
+        loops: LoopDetector::new(0.05, 0.02, 30.0), // threshold, then seconds between states kept and back
+        loop_period: None,
+        loop_flash: 0.0,
+
+        velocity_display: VelocityDisplay::Hidden,
+        show_acceleration: false,
+        show_rod_forces: false,
+        show_dials: false,
+        control: JointControl { target: 1.0, kp: 100.0, kd: 15.0 }, // radians, N m/rad, N m s/rad
+        control_mode: None,
+        swing_up: SwingUp { gain: 2.0, max_torque: 10.0, capture: 0.3, release: 0.8, kp: 300.0, kd: 40.0 },
+        balance_target: None,
+        pump_torque: 0.0,
+        control_torque: 0.0,
+        show_mass_labels: false,
+
+        theme: Theme {
+            background: Background::Solid(WHITESMOKE),
+            mechanism: GRAY,
+            trace_start: CADETBLUE,
+            trace_end: MEDIUMORCHID,
+            trace_bob1_start: PALEGOLDENROD,
+            trace_bob1_end: DARKGOLDENROD,
+            text: DIMGRAY,
+            radial: TOMATO,
+            tangential: SEAGREEN,
+            ensemble_colors: [CADETBLUE, GOLD, CRIMSON],
+            regime_colors: [SEAGREEN, GOLDENROD, CRIMSON],
+            grabbed: ORANGERED,
+
+            acceleration_calm: STEELBLUE,
+            acceleration_hot: RED,
+            max_acceleration: 150.0,
+            tension: RED,
+            compression: ROYALBLUE,
+            max_rod_load: 5.0,
+            rejection: ORANGE,
+            kick: GOLD,
+            density: DARKORANGE,
+            reference: OLIVEDRAB,
+            loop_closure: DEEPSKYBLUE,
+            ruler: SLATEGRAY,
+
+            trace_weight: 2.0,
+            rod_weight: 4.0,
+            bob_radius: 7.0,
+            scale_with_dpi: true,
+            rod_taper: None,
+
+            trace_glow: false,
+        },
+        background_image: None,
+    };
+
+    if let Err(e) = model.params.validate() {
+        exit_with(&format!("bad parameters in model(): {}", e));
+    }
+
+    if let Some(name) = &options.preset {
+        let preset = presets::find(name).unwrap_or_else(|e| exit_with(&e));
+
+        model.state = preset.state;
+        model.params = preset.params;
+    }
+
+    // carry on from where the last run stopped, or from the usual start if that can't be read
+
+    if options.continue_run {
+        match persist::load() {
+            Ok((sim_time, state, params)) => {
+                model.sim_time = sim_time;
+                model.state = state;
+                model.params = params;
+            }
+            Err(e) => eprintln!("{}, starting afresh", e),
+        }
+    }
+
+    // read the library if it's there. a broken one only stops the run if it was asked for,
+    // otherwise n just has nothing to step through
+
+    let library_wanted = options.load_named.is_some() || options.save_named.is_some();
+
+    if options.load_named.is_some() || std::path::Path::new(&options.library).exists() {
+        match library::load(&options.library) {
+            Ok(configs) => model.library = configs,
+            Err(e) if library_wanted => exit_with(&e),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    if let Some(name) = &options.load_named {
+        let index = library::find(&model.library, name, &options.library).unwrap_or_else(|e| exit_with(&e));
+
+        model.state = model.library[index].state;
+        model.params = model.library[index].params;
+        model.library_index = Some(index);
+    }
+
+    // the command line's changes on top of whichever start that was
+
+    for (value, length) in options.lengths.iter().zip([&mut model.params.l1, &mut model.params.l2]) {
+        *length = value.unwrap_or(*length);
+    }
+
+    for (value, mass) in options.masses.iter().zip([&mut model.params.m1, &mut model.params.m2]) {
+        *mass = value.unwrap_or(*mass);
+    }
+
+    model.params.gravity = options.gravity.unwrap_or(model.params.gravity);
+
+    let start = &mut model.state;
+
+    for (value, variable) in options.angles.iter().chain(&options.kicks).zip([&mut start.theta_1, &mut start.theta_2, &mut start.dot_theta_1, &mut start.dot_theta_2]) {
+        *variable = value.unwrap_or(*variable);
+    }
+
+    if let Err(e) = model.params.validate() {
+        exit_with(&format!("bad parameters on the command line: {}", e));
+    }
+
+    model.max_path_length = options.trace_len.unwrap_or(model.max_path_length);
+
+    if let Some(name) = &options.save_named {
+        if model.library.iter().any(|config| config.name == *name) {
+            exit_with(&format!("{} already has a setup called {}, pick another name for --save-named", options.library, name));
+        }
+    }
+
+    // from here on it's all in natural units, including the simulated time carried over by
+    // --continue. a gravity schedule would set gravity back in m/s² every frame
+
+    if options.dimensionless {
+        if model.gravity_schedule.is_some() {
+            exit_with("--dimensionless can't be used with a gravity_schedule");
+        }
+
+        let (state, params, tau) = natural_units(&model.state, &model.params).unwrap_or_else(|e| exit_with(&format!("--dimensionless: {}", e)));
+
+        model.state = state;
+        model.params = params;
+        model.sim_time /= tau;
+        model.tau = Some(tau);
+    }
+
+    // a replay starts from its first recorded state
+
+    if let Some(replay) = replay {
+        model.state = replay.states[0];
+        model.replay = Some(replay);
+    }
+
+    model.sim.place(model.state);
+    model.sim.time = model.sim_time;
+    model.sim.max_step = model.max_step;
+
+    // jump ahead to --start-at before anything is drawn. it takes the same fixed steps update
+    // would have by then, so it lands on exactly the state a run from the start reaches
+
+    if let Some(time) = options.start_at {
+        let steps = model.sim.due(time);
+        take_steps(&mut model, steps);
+
+        model.sim_time = model.sim.time;
+        model.state = model.sim.state;
+    }
+
+    // the reference is laid out with the arm lengths this run starts with
+
+    if let Some(file) = &options.reference {
+        model.reference = load_reference(file, &model.params);
+        model.show_reference = true;
+    }
+
+    model.initial_state = model.state;
+    model.trace_from = model.sim_time + model.trace_start_delay;
+
+    if let Some(changes) = &options.compare {
+        let params = changes.iter().fold(model.params, |params, (parameter, value)| parameter.apply(&params, *value));
+
+        if let Err(e) = params.validate() {
+            exit_with(&format!("--compare: {}", e));
+        }
+
+        let label = changes.iter()
+            .map(|(parameter, value)| format!("{} = {}", parameter.column(), value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        model.comparison = Some(Comparison {
+            params,
+            state: model.state,
+            adaptive: Adaptive::new(model.sim.adaptive.tolerance),
+            trail: VecDeque::new(),
+            label,
+        });
+    }
+
+    if let Some(name) = &options.rival {
+        let integrator = Integrator::parse(name).unwrap_or_else(|e| exit_with(&format!("--rival: {}", e)));
+
+        model.rival = Some(Rival { integrator, state: model.state, adaptive: Adaptive::new(model.sim.adaptive.tolerance), separation: VecDeque::new() });
+    }
+
+    rebaseline(&mut model);
+    model.ensemble = ensemble(&model.state, model.ensemble_size, model.ensemble_spread);
+
+    // samples start from wherever the run does, which --continue may have moved on
+
+    if let Some(file) = &options.export_phase {
+        let export = PhaseExport::create(file, options.phase_interval, model.sim_time);
+        model.phase_export = Some(export.unwrap_or_else(|e| exit_with(&e)));
+    }
+
+    if let Some(text) = &options.background {
+        model.theme.background = Background::parse(text).unwrap_or_else(|e| exit_with(&format!("--background: {}", e)));
+    }
+
+    if let Background::Image(path) = &model.theme.background {
+        let texture = wgpu::Texture::from_path(app, path)
+            .unwrap_or_else(|e| exit_with(&format!("couldn't load the background {}: {}", path, e)));
+
+        model.background_image = Some(texture);
+    }
+
+    // the time-lapse starts from whatever time the run does, after any --start-at
+
+    if options.timelapse.is_some() {
+        let frame_dt = (model.time_scale * model.frame_dt.unwrap_or(1.0 / 60.0)) as f64;
+        let timelapse = TimeLapse::new(model.sim_time, options.timelapse_duration, options.timelapse_frames, frame_dt);
+
+        model.timelapse = Some(timelapse.unwrap_or_else(|e| exit_with(&format!("--timelapse: {}", e))));
+    }
+
+    if let Some(rate) = options.physics_rate {
+        model.sim_thread = Some(SimThread::start(
+            rate,
+            model.sim_time,
+            model.state,
+            model.params,
+            model.energy_setpoint,
+            model.energy_gain,
+        ));
+    }
+
+    model
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+
+    // the last frame has been submitted by now, so can be sent off to the video
+
+    if let Some(video) = &mut model.video {
+        video.write_pending(app.main_window().device());
+    }
+
+    // hold the frame back until it's taken at least min_frame. the wait lands in the time
+    // between this update and the next, so the next dt covers it and simulated time still keeps
+    // up with the clock
+
+    if let Some(min_frame) = model.min_frame {
+        if let Some(wait) = min_frame.checked_sub(model.last_frame.elapsed()) {
+            std::thread::sleep(wait);
+        }
+
+        model.last_frame = std::time::Instant::now();
+    }
+
+    // stop once the time limit is up. exit still runs, so the video and input log are finished
+    // off properly, and the frame just written was the last one drawn
+
+    if model.duration.is_some_and(|duration| model.sim_time >= duration) || model.timelapse.as_ref().is_some_and(TimeLapse::finished) {
+        app.quit();
+        return;
+    }
+
+    // scalar on dt for visualisation speed
+
+    let dt = model.time_scale * model.frame_dt.unwrap_or(app.duration.since_prev_update.as_secs_f32());
+
+    // press any scripted keys that are due, before stepping just as a live keypress would be
+
+    while model.scripted_keys.front().is_some_and(|(time, _)| *time <= model.sim_time) {
+        let (_, key) = model.scripted_keys.pop_front().unwrap();
+        press(app, model, key);
+    }
+
+    // held parameter keys sweep their value smoothly, at a rate that doesn't depend on the
+    // frame rate
+
+    let wall_dt = app.duration.since_prev_update.as_secs_f32();
+    let mut repeating = Vec::new();
+
+    for (key, held) in &mut model.held_keys {
+        *held += wall_dt;
+
+        if *held > model.repeat_delay {
+            repeating.push(*key);
+        }
+    }
+
+    let presses = model.repeat_rate * wall_dt;
+
+    for key in repeating {
+        if let Some((value, factor, range)) = nudge_key(key, model) {
+            nudge(value, factor.powf(presses), range);
+            parameters_changed(app, model, key);
+        }
+    }
+
+    // held arrow keys pan the view, at a steady speed across the screen however far it's zoomed.
+    // each one brings more of the scene into view from its side
+
+    let direction: Vec2 = model.held_keys.iter()
+        .filter_map(|(key, _)| match key {
+            Key::Left => Some(vec2(1.0, 0.0)),
+            Key::Right => Some(vec2(-1.0, 0.0)),
+            Key::Up => Some(vec2(0.0, -1.0)),
+            Key::Down => Some(vec2(0.0, 1.0)),
+            _ => None,
+        })
+        .fold(Vec2::ZERO, |sum, direction| sum + direction);
+
+    if direction != Vec2::ZERO {
+        let speed = PAN_SPEED * (model.params.l1 + model.params.l2) / model.zoom;
+        pan(app, model, direction * speed * wall_dt);
+    }
+
+    model.rejection_flash = (model.rejection_flash - wall_dt).max(0.0);
+    model.kick_flash = model.kick_flash.map(|flash| (flash - wall_dt).max(0.0));
+    model.loop_flash = (model.loop_flash - wall_dt).max(0.0);
+
+    // nothing moves while a bob is held, or while paused
+
+    if model.grabbed_bob.is_some() || model.paused {
+        if let Some(sim_thread) = &model.sim_thread {
+            sim_thread.hold(&model.state);
+        }
+
+        return;
+    }
+
+    // gravity comes from the schedule at the start of the frame, for the physics thread, which
+    // is handed the parameters below. update's own steps each take it at their own start
+
+    if let Some(schedule) = &model.gravity_schedule {
+        model.params.gravity = schedule.at(model.sim_time);
+    }
+
+    // the same for the controller's torques, apart from the pd part, which follows the state
+    // through each step
+
+    if model.replay.is_none() {
+        steer(model);
+    }
+
+    let previous_time = model.sim_time;
+
+    let mut previous = model.state;
+    let previous_comparison = model.comparison.as_ref().map(|comparison| comparison.state);
+    let previous_offset = cart_offset(model);
+
+    // perform state update, take the newest state from the physics thread, or take the next
+    // recorded state when replaying
+
+    if let Some(sim_thread) = &model.sim_thread {
+        (model.sim_time, model.state) = sim_thread.latest(&model.params, model.pump_torque);
+    } else if let Some(replay) = &mut model.replay {
+
+        if replay.index + 1 < replay.states.len() {
+            replay.index += 1;
+        } else if replay.looping {
+            replay.index = 0;
+
+            // don't join the end of the recording to its start, or count the jump as flips
+
+            break_trail(&mut model.trail_bob1);
+            break_trail(&mut model.trail_bob2);
+            model.portrait.break_line();
+            previous = replay.states[0];
+        }
+
+        model.state = replay.states[replay.index];
+        model.sim_time += dt as f64;
+    } else {
+
+        // the main run and everything stepped alongside it take as many fixed steps as this
+        // frame brings due, each at the parameters of the moment it starts, so where they get to
+        // depends only on how much simulated time has passed and not on how it was split into
+        // frames. the controllers are the one thing that only acts once a frame, from steer
+
+        let steps = model.sim.due(dt as f64);
+        let rejected = model.sim.adaptive.stats.rejected;
+
+        take_steps(model, steps);
+
+        if model.sim.adaptive.stats.rejected > rejected {
+            model.rejections += model.sim.adaptive.stats.rejected - rejected;
+            model.rejection_flash = REJECTION_FLASH;
+        }
+
+        // the rival's taken the same steps, so it's measured against the newest state rather
+        // than the one drawn
+
+        if let Some(rival) = &mut model.rival {
+            rival.separation.push_back((model.sim.time, phase_distance(&model.sim.state, &rival.state)));
+
+            while rival.separation.front().is_some_and(|(time, _)| *time < model.sim.time - SEPARATION_HISTORY) {
+                rival.separation.pop_front();
+            }
+        }
+
+        // a frame rarely lands on a step, so as on the physics thread it shows the pendulum
+        // between the last two
+
+        (model.sim_time, model.state) = model.sim.drawn();
+    }
+
+    model.stats.record(&previous, &model.state, &model.params);
+
+    if let Some(audio) = &model.audio {
+        let flipped = [(previous.theta_1, model.state.theta_1), (previous.theta_2, model.state.theta_2)];
+
+        for ((before, after), sound) in flipped.into_iter().zip(model.flip_sounds) {
+            if turns(before) != turns(after) {
+                audio.play(sound);
+            }
+        }
+    }
+
+    if let Some(export) = &mut model.phase_export {
+        export.record(previous_time, &previous, model.sim_time, &model.state);
+    }
+
+    // while a bob is held the pendulum is only being posed, so that isn't part of the trajectory
+
+    if model.grabbed_bob.is_none() {
+        model.portrait.record(&model.state);
+
+        if let Some(period) = model.loops.record(model.sim_time, &model.state) {
+            model.loop_period = Some(period);
+            model.loop_flash = LOOP_FLASH;
+        }
+    }
+
+    model.states_seen.push_back(model.state);
+
+    while model.states_seen.len() > (model.strobe_stride * model.strobe_count).max(model.ghost_frames) + 1 {
+        model.states_seen.pop_front();
+    }
+
+    model.clip.record(model.sim_time, &model.state, &model.params);
+
+    model.energy_history.push_back((model.sim_time, energy(&model.state, &model.params)));
+
+    while model.energy_history.front().is_some_and(|(time, _)| *time < model.sim_time - ENERGY_HISTORY) {
+        model.energy_history.pop_front();
+    }
+
+    model.torque_history.push_back((model.sim_time, gravity_torque(&model.state, &model.params)));
+
+    while model.torque_history.front().is_some_and(|(time, _)| *time < model.sim_time - ENERGY_HISTORY) {
+        model.torque_history.pop_front();
+    }
+
+    // each arm's phase is measured against the natural frequency of the top arm on its own, which
+    // is in the right range for both normal modes
+
+    let frequency = (model.params.gravity / model.params.l1).sqrt() as f64;
+    let difference = oscillation_phase(model.state.theta_1, model.state.dot_theta_1, frequency)
+        - oscillation_phase(model.state.theta_2, model.state.dot_theta_2, frequency);
+
+    model.phase_history.push_back((model.sim_time, difference));
+
+    while model.phase_history.front().is_some_and(|(time, _)| *time < model.sim_time - SYNC_WINDOW) {
+        model.phase_history.pop_front();
+    }
+
+    if let Some(rate) = model.lyapunov.exponent() {
+        model.regime = Some(Regime::classify(rate, model.regime, &model.regime_thresholds));
+    }
+
+    // summarise how the adaptive integrator has been getting on every simulated second. a
+    // collapsing min step or lots of rejections means the tolerance is fighting the motion.
+    // it goes to stderr, keeping stdout for the summary printed at the end
+
+    if let Integrator::Rkf45 = model.sim.integrator {
+
+        model.stats_time += dt;
+
+        if model.stats_time >= 1.0 {
+
+            let stats = model.sim.adaptive.stats;
+
+            eprintln!(
+                "rkf45: {} accepted, {} rejected, mean step {:.2e}s, min step {:.2e}s",
+                stats.accepted,
+                stats.rejected,
+                stats.mean_step(),
+                stats.min_step,
+            );
+
+            model.sim.adaptive.stats = StepStats::new();
+            model.stats_time = 0.0;
+        }
+    }
+
+    // bob positions relative to the pivot, in metres
+
+    let p1 = to_cartesian(model.params.l1, model.state.theta_1);
+    let p2 = to_cartesian(model.params.l2, model.state.theta_2);
+    let bob_1 = vec2(p1.x, p1.y);
+    let bob_2 = bob_1 + vec2(p2.x, p2.y);
+
+    let extent = model.params.l1 + model.params.l2;
+    model.density.record(bob_2, extent, dt);
+
+    if let Some(axes) = model.lissajous_axes {
+
+        model.lissajous.push_back(axes.point(bob_1, bob_2));
+
+        while model.lissajous.len() > model.max_path_length {
+            model.lissajous.pop_front();
+        }
+    }
+
+    if model.trail_frozen || model.sim_time < model.trace_from {
+        return;
+    }
+
+    // store new points in the path traces: this frame's, or with trail_sample_dt one for each
+    // sample time since the last frame, found that far between the two frames' states. there
+    // can't be more of those worth keeping than the traces hold
+
+    let current_comparison = model.comparison.as_ref().map(|comparison| comparison.state);
+    let current_offset = cart_offset(model);
+
+    let samples: Vec<(State, Option<State>, Vec2)> = match model.trail_sample_dt {
+        None => vec![(model.state, current_comparison, current_offset)],
+        Some(interval) => {
+            let first = (previous_time / interval).floor() as i64 + 1;
+            let last = (model.sim_time / interval).floor() as i64;
+            let span = model.sim_time - previous_time;
+
+            (first.max(last - model.max_path_length as i64 + 1)..=last)
+                .map(|k| {
+                    let t = if span > 0.0 { (k as f64 * interval - previous_time) / span } else { 1.0 };
+                    let comparison = previous_comparison.zip(current_comparison).map(|(from, to)| interpolate(&from, &to, t));
+
+                    (interpolate(&previous, &model.state, t), comparison, previous_offset.lerp(current_offset, t as f32))
+                })
+                .collect()
+        }
+    };
+
+    let frame = model.trace_frame;
+
+    // with a cart the traces are of where the bobs went in the room, not relative to the cart
+
+    for (state, comparison_state, offset) in samples {
+
+        let (bob_1, bob_2) = bob_offsets(&state, &model.params);
+        let (bob_1, bob_2) = (bob_1 + offset, bob_2 + offset);
+
+        model.envelope = Some(match model.envelope {
+            Some(envelope) => envelope.stretch_to_point(bob_2.into()),
+            None => Rect::from_xy_wh(bob_2, Vec2::ZERO),
+        });
+
+        let moved_1 = frame.traces_bob1() && push_trail(&mut model.trail_bob1, bob_1, model.max_path_length, model.min_trail_step);
+        let last_2 = model.trail_bob2.back().copied().flatten();
+        let moved_2 = push_trail(&mut model.trail_bob2, frame.point(bob_1, bob_2), model.max_path_length, model.min_trail_step);
+
+        if let (true, Some(last), Some(Some(point))) = (moved_2, last_2, model.trail_bob2.back()) {
+            let before = model.trace_length;
+            model.trace_length += last.distance(*point) as f64;
+
+            if model.trace_budget.is_some_and(|budget| before < budget && model.trace_length >= budget) {
+                println!("trace budget of {:.2} m reached at t = {:.2} s, pausing", model.trace_length, model.sim_time);
+                model.paused = true;
+            }
+        }
+
+        if let (Some(comparison), Some(state)) = (&mut model.comparison, comparison_state) {
+            let (end_1, end_2) = bob_offsets(&state, &comparison.params);
+
+            push_trail(&mut comparison.trail, frame.point(end_1, end_2), model.max_path_length, model.min_trail_step);
+        }
+
+        // the canvas only takes on the newest stretch, so it's drawn after each one
+
+        if model.canvas.is_some() && (moved_1 || moved_2) {
+            extend_canvas(app, model);
+        }
+    }
+
+}
+
+// the fixed steps a frame has brought due. rkf45 picks its own steps, so rather than having
+// them capped at the clock's it's handed all of that time in one go. its steps then depend on
+// the frame rate as they always have, since that's where its error control puts them
+
+fn take_steps(model: &mut Model, steps: u64) {
+
+    let h = model.sim.clock.dt as f32;
+
+    if model.sim.integrator == Integrator::Rkf45 {
+        if steps > 0 {
+            step_alongside(model, steps as f32 * h);
+        }
+    } else {
+        for _ in 0..steps {
+            step_alongside(model, h);
+        }
+    }
+
+}
+
+// one step of h for the main run, its cart if it has one, and whatever's stepped alongside it
+// (the ensemble, --compare and --rival), each cut into substeps no longer than sim.max_step.
+// gravity, the energy setpoint, the drive and the torque and force expressions are all taken
+// at the time the step starts and held through it. the drive's oscillator goes round with it
+
+fn step_alongside(model: &mut Model, h: f32) {
+
+    let time = model.sim.time;
+
+    if let Some(schedule) = &model.gravity_schedule {
+        model.params.gravity = schedule.at(time);
+    }
+
+    // the torques are held through the step, so follow the energy error from step to step
+
+    if let Some(setpoint) = model.energy_setpoint {
+        model.params.torque = energy_pump_torque(&model.sim.state, &model.params, setpoint, model.energy_gain);
+    }
+
+    // as is the drive's, which goes on top just for this step
+
+    let mut params = match &model.drive {
+        Some(drive) => drive.apply(&model.sim.state, &model.params),
+        None => model.params,
+    };
+
+    params.torque.0 += model.pump_torque as f32;
+
+    // anywhere the expression isn't a number (dividing by zero, say) it gives no torque at all
+
+    if let Some(expression) = &model.drive_torque {
+        let torque = expression.evaluate(time);
+        model.drive_torque_now = if torque.is_finite() { torque } else { 0.0 };
+        params.torque.0 += model.drive_torque_now as f32;
+    }
+
+    model.lyapunov.advance(&model.sim.state, &params, h);
+
+    match &mut model.cart {
+        Some(cart) => {
+            cart.force_now = cart.force.as_ref().map_or(0.0, |force| force.evaluate(time));
+
+            if !cart.force_now.is_finite() {
+                cart.force_now = 0.0;
+            }
+
+            let count = (h / model.sim.max_step).ceil().max(1.0) as usize;
+            let forced = Cart { mass: cart.mass, force: cart.force_now };
+            let mut state = CartState { x: cart.x, dot_x: cart.dot_x, pendulum: model.sim.state };
+
+            for _ in 0..count {
+                state = cart_rk4(&state, &params, &forced, h / count as f32);
+            }
+
+            (cart.x, cart.dot_x) = (state.x, state.dot_x);
+            model.sim.stepped_to(state.pendulum, h as f64);
+        }
+        None => model.sim.step_with(&params, h as f64),
+    }
+
+    // the pendulum's energy alone isn't what's conserved on a cart
+
+    if model.conserve_energy && model.cart.is_none() {
+        model.sim.state = project_energy(&model.sim.state, &model.params, model.target_energy);
+    }
+
+    for member in &mut model.ensemble {
+
+        let mut params = model.params;
+
+        if let Some(setpoint) = model.energy_setpoint {
+            params.torque = energy_pump_torque(member, &params, setpoint, model.energy_gain);
+        }
+
+        if let Some(drive) = &model.drive {
+            params = drive.apply(member, &params);
+        }
+
+        params.torque.0 += model.drive_torque_now as f32;
+
+        *member = substep(rk4, member, &params, h, model.sim.max_step);
+
+        if model.conserve_energy {
+            *member = project_energy(member, &params, model.target_energy);
+        }
+    }
+
+    if let Some(comparison) = &mut model.comparison {
+        comparison.state = match model.sim.integrator.step() {
+            Some(step) => substep(step, &comparison.state, &comparison.params, h, model.sim.max_step),
+            None => comparison.adaptive.advance(&comparison.state, &comparison.params, h),
+        };
+    }
+
+    if let Some(rival) = &mut model.rival {
+
+        let mut params = model.params;
+
+        if let Some(setpoint) = model.energy_setpoint {
+            params.torque = energy_pump_torque(&rival.state, &params, setpoint, model.energy_gain);
+        }
+
+        if let Some(drive) = &model.drive {
+            params = drive.apply(&rival.state, &params);
+        }
+
+        params.torque.0 += model.drive_torque_now as f32;
+
+        rival.state = match rival.integrator.step() {
+            Some(step) => substep(step, &rival.state, &params, h, model.sim.max_step),
+            None => rival.adaptive.advance(&rival.state, &params, h),
+        };
+
+        if model.conserve_energy {
+            rival.state = project_energy(&rival.state, &params, model.target_energy);
+        }
+    }
+
+    if let Some(drive) = &mut model.drive {
+        drive.phase = (drive.phase + drive.frequency * h as f64).rem_euclid(std::f64::consts::TAU);
+    }
+
+}
+
+// where the bobs are, in metres from the pivot
+
+fn bob_offsets(state: &State, params: &Params) -> (Vec2, Vec2) {
+
+    let p1 = to_cartesian(params.l1, state.theta_1);
+    let p2 = to_cartesian(params.l2, state.theta_2);
+
+    (vec2(p1.x, p1.y), vec2(p1.x + p2.x, p1.y + p2.y))
+
+}
+
+// draw the newest stretch of each trail onto the canvas, in the newest of its trace colours
+
+fn extend_canvas(app: &App, model: &mut Model) {
+
+    let Some(window) = app.window(model.window) else {
+        return;
+    };
+
+    let scale = pixel_scale(window.rect(), model);
+    let pivot = screen_pivot(window.rect(), model);
+    let theme = &model.theme;
+    let dpi = if theme.scale_with_dpi { window.scale_factor() } else { 1.0 };
+
+    let newest = |trail: &VecDeque<Option<Point2>>| {
+        let n = trail.len();
+
+        match (n >= 2).then(|| (trail[n - 2], trail[n - 1])) {
+            Some((Some(from), Some(to))) => Some((pivot + from * scale, pivot + to * scale)),
+            _ => None,
+        }
+    };
+
+    let image = |transform: fn(Vec2) -> Vec2, point: Point2| pivot + transform(point - pivot);
+
+    let segments: Vec<(Point2, Point2, Srgb<u8>)> = [
+        (newest(&model.trail_bob1), theme.trace_bob1_end),
+        (newest(&model.trail_bob2), theme.trace_end),
+    ]
+        .into_iter()
+        .filter_map(|(segment, colour)| segment.map(|(from, to)| (from, to, colour)))
+        .flat_map(|(from, to, colour)| {
+            model.mirror.images().iter().map(move |transform| (image(*transform, from), image(*transform, to), colour))
+        })
+        .collect();
+
+    let weight = theme.trace_weight * dpi;
+    let canvas = model.canvas.as_mut().unwrap();
+
+    // a resized window starts the canvas afresh, so there's nothing to join on to
+
+    if !canvas.fit(&window) {
+        canvas.draw_segments(&window, &segments, weight);
+    }
+
+}
+
+// go back to the starting state with a clean slate: no traces or recent history, and the
+// adaptive integrator's step and statistics as they were when it began
+
+fn restart(app: &App, model: &mut Model) {
+
+    model.state = model.initial_state;
+    hand_over(model);
+    sync_comparison(model);
+    model.trace_from = model.sim_time + model.trace_start_delay;
+
+    if let Some(cart) = &mut model.cart {
+        (cart.x, cart.dot_x) = (0.0, 0.0);
+    }
+
+    if let Some(drive) = &mut model.drive {
+        drive.phase = 0.0;
+    }
+
+    rebaseline(model);
+    model.ensemble = ensemble(&model.state, model.ensemble_size, model.ensemble_spread);
+
+    model.sim.adaptive = Adaptive::new(model.sim.adaptive.tolerance);
+    model.stats_time = 0.0;
+    model.rejections = 0;
+    model.rejection_flash = 0.0;
+
+    model.energy_history.clear();
+    model.torque_history.clear();
+    model.phase_history.clear();
+    model.lyapunov.clear();
+    model.regime = None;
+    model.loops.clear();
+    model.loop_period = None;
+    model.lissajous.clear();
+    model.portrait.clear();
+    clear_trace(app, model);
+
+}
+
+// switch to the next setup in the library, going back to the first after the last, and start
+// it afresh. a replay's states come from its file, so there's nothing to switch then
+
+fn load_next_config(app: &App, model: &mut Model) {
+
+    if model.replay.is_some() {
+        return;
+    }
+
+    if model.library.is_empty() {
+        println!("no setups in {} to step through", model.library_file);
+        return;
+    }
+
+    let index = model.library_index.map_or(0, |index| (index + 1) % model.library.len());
+    let config = &model.library[index];
+
+    println!("loaded {} from {}", config.name, model.library_file);
+
+    model.initial_state = config.state;
+    model.params = config.params;
+    model.library_index = Some(index);
+    restart(app, model);
+
+}
+
+// empty the traces, including anything drawn on the canvas
+
+fn clear_trace(app: &App, model: &mut Model) {
+
+    model.trail_bob1.clear();
+    model.trail_bob2.clear();
+    model.envelope = None;
+    model.trace_length = 0.0;
+
+    if let Some(comparison) = &mut model.comparison {
+        comparison.trail.clear();
+    }
+    model.states_seen.clear();
+
+    if let (Some(canvas), Some(window)) = (&mut model.canvas, app.window(model.window)) {
+        canvas.clear(&window);
+    }
+
+}
+
+// returns whether the point was far enough from the last one to be stored
+
+fn push_trail(trail: &mut VecDeque<Option<Point2>>, point: Point2, max_length: usize, min_step: f32) -> bool {
+
+    if let Some(Some(last)) = trail.back() {
+        if last.distance(point) < min_step {
+            return false;
+        }
+    }
+
+    trail.push_back(Some(point));
+
+    // remove values over the length cap
+
+    while trail.len() > max_length {
+        trail.pop_front();
+    }
+
+    true
+
+}
+
+// end the current stretch of a trail, so the next point isn't joined on to the last one
+
+fn break_trail(trail: &mut VecDeque<Option<Point2>>) {
+    if matches!(trail.back(), Some(Some(_))) {
+        trail.push_back(None);
+    }
+}
+
+// the rkf45 tolerance slider sits in the bottom left corner while rkf45 is in use. it runs over
+// a log scale, since useful tolerances span several orders of magnitude
+
+const TOLERANCE_RANGE: (f64, f64) = (1e-10, 1e-2);
+
+// how long, in real seconds, the rejection light stays on (fading out) after a rejected step
+
+const REJECTION_FLASH: f32 = 0.3;
+
+// and the ring round a kicked bob, and the trace on closing a loop
+
+const KICK_FLASH: f32 = 0.4;
+const LOOP_FLASH: f32 = 0.5;
+
+// the error budget bar under the slider reaches this many times the tolerance
+
+const ERROR_BUDGET_SPAN: f32 = 1.25;
+
+fn tolerance_slider(window: Rect) -> Rect {
+    Rect::from_x_y_w_h(window.left() + 120.0, window.bottom() + 30.0, 200.0, 16.0)
+}
+
+fn set_tolerance(model: &mut Model, window: Rect, x: f32) {
+
+    let slider = tolerance_slider(window);
+    let t = ((x - slider.left()) / slider.w()).clamp(0.0, 1.0) as f64;
+    let (min, max) = (TOLERANCE_RANGE.0.log10(), TOLERANCE_RANGE.1.log10());
+
+    model.sim.adaptive.tolerance = 10.0f64.powf(min + t * (max - min));
+
+}
+
+// how many simulated seconds the energy chart covers
+
+const ENERGY_HISTORY: f64 = 10.0;
+
+// how many simulated seconds of phase differences go into the synchronization. long enough to
+// take in a few swings, short enough to follow the motion as it changes
+
+const SYNC_WINDOW: f64 = 5.0;
+
+// where the first arm hangs from in window coordinates: the pivot, or with a cart wherever it's
+// got to along its track
+
+fn hanging_point(window: Rect, model: &Model) -> Point2 {
+    screen_pivot(window, model) + cart_offset(model) * pixel_scale(window, model)
+}
+
+// bob positions in window coordinates, matching where view draws them
+
+fn bob_positions(window: Rect, model: &Model) -> (Point2, Point2) {
+
+    let scale = pixel_scale(window, model);
+
+    let p1 = to_cartesian(scale * model.params.l1, model.state.theta_1);
+    let p2 = to_cartesian(scale * model.params.l2, model.state.theta_2);
+
+    let bob_1 = hanging_point(window, model) + vec2(p1.x, p1.y);
+    let bob_2 = bob_1 + vec2(p2.x, p2.y);
+
+    (bob_1, bob_2)
+
+}
+
+// point the held bob's arm at the cursor, leaving the other arm where it is
+
+fn drag_bob(model: &mut Model, window: Rect, position: Point2) {
+
+    let (bob_1, _) = bob_positions(window, model);
+
+    let (top, theta) = match model.grabbed_bob {
+        Some(1) => (hanging_point(window, model), &mut model.state.theta_1),
+        Some(2) => (bob_1, &mut model.state.theta_2),
+        _ => return,
+    };
+
+    // angles are measured from hanging straight down. keep the number of turns the arm has
+    // made, so letting go doesn't show up as flips in the summary
+
+    let arm = position - top;
+    let angle = (arm.x as f64).atan2(-arm.y as f64);
+    let turns = ((*theta - angle) / std::f64::consts::TAU).round();
+
+    *theta = angle + turns * std::f64::consts::TAU;
+
+    model.state.dot_theta_1 = 0.0;
+    model.state.dot_theta_2 = 0.0;
+
+}
+
+fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+
+    let window = app.window_rect();
+    let mouse = app.mouse.position();
+
+    if button == MouseButton::Right {
+        model.panning = Some(mouse);
+        return;
+    }
+
+    if button != MouseButton::Left {
+        return;
+    }
+
+    if matches!(model.sim.integrator, Integrator::Rkf45) && tolerance_slider(window).contains(mouse) {
+        model.dragging_tolerance = true;
+        set_tolerance(model, window, app.mouse.x);
+        return;
+    }
+
+    // a replay's states come from the file, so can't be dragged about
+
+    if model.replay.is_some() {
+        return;
+    }
+
+    // grab whichever bob is nearest, as long as the cursor is reasonably close to it
+
+    let (bob_1, bob_2) = bob_positions(window, model);
+    let reach = 3.0 * model.theme.bob_radius;
+
+    let (bob, distance) = if mouse.distance(bob_1) < mouse.distance(bob_2) {
+        (1, mouse.distance(bob_1))
+    } else {
+        (2, mouse.distance(bob_2))
+    };
+
+    if distance < reach {
+        model.grabbed_bob = Some(bob);
+        model.portrait.break_line();
+        drag_bob(model, window, mouse);
+    } else if model.click_to_place {
+        place_pendulum(model, window, mouse);
+    }
+
+}
+
+// start the pendulum again from rest with both arms pointing straight at the cursor. right on
+// the pivot there's no direction to point in, so that's ignored
+
+fn place_pendulum(model: &mut Model, window: Rect, mouse: Point2) {
+
+    let offset = mouse - hanging_point(window, model);
+
+    if offset.length() < model.theme.bob_radius {
+        return;
+    }
+
+    let theta = (offset.x as f64).atan2(-offset.y as f64);
+
+    model.state = State { theta_1: theta, theta_2: theta, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+    rebaseline(model);
+    model.ensemble = ensemble(&model.state, model.ensemble_size, model.ensemble_spread);
+    model.phase_history.clear();
+    model.lyapunov.clear();
+    model.regime = None;
+    model.loops.clear();
+    model.loop_period = None;
+
+    break_trail(&mut model.trail_bob1);
+    break_trail(&mut model.trail_bob2);
+    model.portrait.break_line();
+    sync_comparison(model);
+
+    hand_over(model);
+
+}
+
+fn mouse_moved(app: &App, model: &mut Model, position: Point2) {
+
+    if model.dragging_tolerance {
+        set_tolerance(model, app.window_rect(), position.x);
+    }
+
+    if model.grabbed_bob.is_some() {
+        drag_bob(model, app.window_rect(), position);
+    }
+
+    if let Some(last) = model.panning.replace(position) {
+        let scale = pixel_scale(app.window_rect(), model);
+        pan(app, model, (position - last) / scale);
+    }
+
+}
+
+fn mouse_released(_app: &App, model: &mut Model, button: MouseButton) {
+
+    if button == MouseButton::Right {
+        model.panning = None;
+    }
+
+    if button != MouseButton::Left {
+        return;
+    }
+
+    model.dragging_tolerance = false;
+
+    // the pendulum has been moved by hand, so there's a new energy to conserve
+
+    if model.grabbed_bob.take().is_some() {
+        hand_over(model);
+        sync_comparison(model);
+        rebaseline(model);
+        model.ensemble = ensemble(&model.state, model.ensemble_size, model.ensemble_spread);
+        model.phase_history.clear();
+        model.lyapunov.clear();
+        model.regime = None;
+        model.loops.clear();
+        model.loop_period = None;
+    }
+
+}
+
+// radians the target turns by for each press of , or .
+
+const CONTROL_TARGET_STEP: f64 = std::f64::consts::PI / 12.0;
+
+// what's driving the first joint. Hold is the pd controller on its own. SwingUp pumps energy in
+// with swing_up_torque until the first arm comes within capture radians of upright, then holds
+// it there with a stiffer pd controller, going back to pumping if it's knocked more than
+// release radians away
+
+#[derive(Clone, Copy, PartialEq)]
+enum ControlMode {
+    Hold,
+    SwingUp,
+}
+
+impl ControlMode {
+
+    // the next mode along, or None after the last one to switch the controller off
+
+    fn next(self) -> Option<Self> {
+        match self {
+            ControlMode::Hold => Some(ControlMode::SwingUp),
+            ControlMode::SwingUp => None,
+        }
+    }
+
+}
+
+// the swing-up's gains: gain (per second) and max_torque (N m) are swing_up_torque's, kp and kd
+// the balancing controller's
+
+struct SwingUp {
+    gain: f64,
+    max_torque: f64,
+    capture: f64,
+    release: f64,
+    kp: f64,
+    kd: f64,
+}
+
+// set the control torques for the frame about to be stepped, from the state it starts at
+
+fn steer(model: &mut Model) {
+
+    let theta_1 = model.state.theta_1;
+    let swing_up = &model.swing_up;
+
+    if model.control_mode != Some(ControlMode::SwingUp) {
+        model.balance_target = None;
+    }
+
+    match model.control_mode {
+        None => {
+            model.params.control = JointControl::OFF;
+            model.pump_torque = 0.0;
+        }
+
+        Some(ControlMode::Hold) => {
+            model.params.control = model.control;
+            model.pump_torque = 0.0;
+        }
+
+        Some(ControlMode::SwingUp) => {
+
+            // how far round from the nearest upright, which is an odd multiple of pi
+
+            let off = theta_1.rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI;
+
+            model.balance_target = match model.balance_target {
+                None if off.abs() < swing_up.capture => Some(theta_1 - off),
+                Some(target) if (theta_1 - target).abs() > swing_up.release => None,
+                target => target,
+            };
+
+            match model.balance_target {
+                Some(target) => {
+                    model.params.control = JointControl { target, kp: swing_up.kp, kd: swing_up.kd };
+                    model.pump_torque = 0.0;
+                }
+                None => {
+                    model.params.control = JointControl::OFF;
+                    model.pump_torque = swing_up_torque(&model.state, &model.params, swing_up.gain, swing_up.max_torque);
+                }
+            }
+        }
+    }
+
+    model.control_torque = model.pump_torque + model.params.control.torque(&model.state);
+
+}
+
+// a sudden push on one arm, adding model.kick to its angular velocity. it gives the pendulum
+// new energy, which is what's conserved from then on. the ensemble gets the same push so it
+// stays clustered round the pendulum. a replay can't be pushed
+
+fn kick(model: &mut Model, bob: usize) {
+
+    if model.replay.is_some() {
+        return;
+    }
+
+    let push = |state: &mut State| match bob {
+        1 => state.dot_theta_1 += model.kick,
+        _ => state.dot_theta_2 += model.kick,
+    };
+
+    // the newest step is kicked as well as the state drawn a little behind it, so the main run
+    // carries on in step with the ones alongside it
+
+    push(&mut model.state);
+    push(&mut model.sim.state);
+    model.ensemble.iter_mut().for_each(push);
+
+    if let Some(comparison) = &mut model.comparison {
+        push(&mut comparison.state);
+    }
+
+    if let Some(rival) = &mut model.rival {
+        push(&mut rival.state);
+    }
+
+    rebaseline(model);
+    model.kick_flash[bob - 1] = KICK_FLASH;
+
+    // the physics thread picks it up as the state to carry on from
+
+    if let Some(sim_thread) = &model.sim_thread {
+        sim_thread.hold(&model.state);
+    }
+
+}
+
+// each notch of the wheel zooms by ZOOM_STEP, within ZOOM_RANGE. trackpads scroll by pixels
+// rather than notches, so PIXELS_PER_NOTCH of those count as one. the view is panned to keep
+// whatever is under the cursor in place
+
+const ZOOM_STEP: f32 = 1.1;
+const ZOOM_RANGE: (f32, f32) = (0.25, 20.0);
+const PIXELS_PER_NOTCH: f32 = 50.0;
+
+fn mouse_wheel(app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: TouchPhase) {
+
+    let notches = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_NOTCH,
+    };
+
+    let zoom = (model.zoom * ZOOM_STEP.powf(notches)).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+
+    if zoom == model.zoom {
+        return;
+    }
+
+    // the cursor sits at zoom * (pivot + (offset + point) * unzoomed scale) for the point under
+    // it, so keeping that point fixed takes shifting the offset by this much
+
+    let window = app.window_rect();
+    let unzoomed = pixel_scale(window, model) / model.zoom;
+    let cursor = app.mouse.position() - viewport(window, model).xy();
+    let shift = cursor * (1.0 / zoom - 1.0 / model.zoom) / unzoomed;
+
+    model.zoom = zoom;
+    pan(app, model, shift);
+
+}
+
+// how fast the arrow keys pan, in lengths of the whole pendulum per second at normal zoom
+
+const PAN_SPEED: f32 = 1.0;
+
+// move the view by offset metres
+
+fn pan(app: &App, model: &mut Model, offset: Vec2) {
+
+    model.camera_offset += offset;
+
+    // the canvas can only be drawn on where it was drawn at
+
+    if model.canvas.is_some() {
+        clear_trace(app, model);
+    }
+
+}
+
+// print a summary of the run, one key=value per line so it's easy to pick apart in scripts
+
+fn exit(app: &App, mut model: Model) {
+
+    if let Some(sim_thread) = model.sim_thread.take() {
+        sim_thread.finish();
+    }
+
+    if let Some(video) = model.video.take() {
+        let window = app.window(model.window);
+        video.finish(window.as_ref().map(|window| window.device()));
+    }
+
+    if let Some(log) = &mut model.input_log {
+        log.flush().unwrap_or_else(|e| eprintln!("couldn't finish writing the input log: {}", e));
+    }
+
+    if let Some(export) = model.phase_export.take() {
+        export.finish();
+    }
+
+    if let Some(file) = &model.portrait_file {
+        save_portrait(&model.portrait, file);
+    }
+
+    // a replay has nothing of its own to carry on from
+
+    if model.replay.is_none() {
+        persist::save(model.sim_time, &model.state, &model.params).unwrap_or_else(|e| eprintln!("{}", e));
+
+        if let Some(name) = &model.save_named {
+            match library::append(&model.library_file, name, &model.state, &model.params) {
+                Ok(()) => println!("saved as {} in {}", name, model.library_file),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }
+
+    let stats = &model.stats;
+    let state = &model.state;
+
+    println!("sim_time={}", model.sim_time);
+    println!("flips_1={}", stats.flips_1);
+    println!("flips_2={}", stats.flips_2);
+    println!("min_energy={}", stats.min_energy);
+    println!("max_energy={}", stats.max_energy);
+    println!("max_speed_1={}", stats.max_speed_1);
+    println!("max_speed_2={}", stats.max_speed_2);
+    println!("min_denominator={}", stats.min_denominator);
+    println!("final_theta_1={}", state.theta_1);
+    println!("final_theta_2={}", state.theta_2);
+    println!("final_dot_theta_1={}", state.dot_theta_1);
+    println!("final_dot_theta_2={}", state.dot_theta_2);
+
+}
+
+// where p saves the phase portrait without --portrait
+
+const PORTRAIT_FILE: &str = "phase_portrait.png";
+
+fn save_portrait(portrait: &Portrait, file: &str) {
+    match portrait.save(file) {
+        Ok(()) => println!("wrote {}", file),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+// every key handled below, so logged keypresses can be read back in by name
+
+const BOUND_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z, Key::Q, Key::Back, Key::Space, Key::Comma, Key::Period, Key::Minus, Key::Equals, Key::LBracket, Key::RBracket,
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8,
+];
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+
+    // the system repeats keys that are held down, but holding is dealt with in update, so only
+    // the first press counts
+
+    if model.held_keys.iter().any(|(held, _)| *held == key) {
+        return;
+    }
+
+    model.held_keys.push((key, 0.0));
+    press(app, model, key);
+
+}
+
+fn key_released(_app: &App, model: &mut Model, key: Key) {
+    model.held_keys.retain(|(held, _)| *held != key);
+}
+
+// releases can go astray when the window loses focus, so don't keep nudging after that
+
+fn unfocused(_app: &App, model: &mut Model) {
+    model.held_keys.clear();
+}
+
+// act on a single keypress, whether from the keyboard or a replayed log
+
+fn press(app: &App, model: &mut Model, key: Key) {
+
+    if let Some(log) = &mut model.input_log {
+        if BOUND_KEYS.contains(&key) {
+            writeln!(log, "{} {:?}", model.sim_time, key)
+                .unwrap_or_else(|e| eprintln!("couldn't record keypress: {}", e));
+        }
+    }
+
+    match key {
+
+        // freeze / unfreeze the trace without pausing the simulation
+
+        Key::F => {
+            model.trail_frozen = !model.trail_frozen;
+
+            // break the trace on unfreezing so it isn't joined up to where it was frozen
+
+            if !model.trail_frozen {
+                break_trail(&mut model.trail_bob1);
+                break_trail(&mut model.trail_bob2);
+            }
+        }
+
+        // colour the bobs by their acceleration, or not
+
+        Key::A => model.show_acceleration = !model.show_acceleration,
+
+        // step through the controllers on the first joint, and turn the pd controller's target
+
+        Key::O => {
+            model.control_mode = match model.control_mode {
+                None => Some(ControlMode::Hold),
+                Some(mode) => mode.next(),
+            };
+        }
+
+        Key::Comma | Key::Period => {
+            let step = if key == Key::Comma { -CONTROL_TARGET_STEP } else { CONTROL_TARGET_STEP };
+            model.control.target += step;
+        }
+
+        // show / hide the angle dials
+
+        Key::D => model.show_dials = !model.show_dials,
+
+        // draw the rods by the forces in them, or plainly
+
+        Key::B => model.show_rod_forces = !model.show_rod_forces,
+
+        // wipe the traces so far
+
+        Key::C => clear_trace(app, model),
+
+        // show / hide the energy chart
+
+        Key::E => model.show_energy = !model.show_energy,
+
+        // show / hide the visit heatmap
+
+        Key::H => model.show_density = !model.show_density,
+
+        // move on to the next integrator, going back to the starting state so each one gets
+        // the same run. the physics thread only does rk4, so there's nothing to switch there
+
+        Key::I => {
+            if model.sim_thread.is_none() {
+                model.sim.integrator = model.sim.integrator.next();
+                restart(app, model);
+            }
+        }
+
+        // velocity components: off, then the numbers, then the numbers with arrows
+
+        Key::V => {
+            model.velocity_display = match model.velocity_display {
+                VelocityDisplay::Hidden => VelocityDisplay::Numbers,
+                VelocityDisplay::Numbers => VelocityDisplay::Arrows,
+                VelocityDisplay::Arrows => VelocityDisplay::Hidden,
+            };
+        }
+
+        // step through the parametric plot's axis pairings, then hide it
+
+        Key::L => {
+            model.lissajous_axes = match model.lissajous_axes {
+                None => Some(PlotAxes::X1Y2),
+                Some(axes) => axes.next(),
+            };
+
+            model.lissajous.clear();
+        }
+
+        // show / hide the strobe afterimages
+
+        Key::S => model.strobe = !model.strobe,
+
+        // show / hide the ghost rods
+
+        Key::G => model.ghost_rods = !model.ghost_rods,
+
+        // show / hide the mass labels on the bobs
+
+        Key::M => model.show_mass_labels = !model.show_mass_labels,
+
+        // step through the fade curves for the trails
+
+        Key::T => {
+            model.fade = match model.fade {
+                None => Some(FadeCurve::Linear),
+                Some(fade) => fade.next(),
+            };
+        }
+
+        // show / hide the reference trace
+
+        Key::R => model.show_reference = !model.show_reference,
+
+        // pause / resume
+
+        Key::Space => model.paused = !model.paused,
+
+        // kick the first / second arm on a bit faster
+
+        Key::J => kick(model, 1),
+        Key::U => kick(model, 2),
+
+        // save the phase portrait so far
+
+        Key::P => save_portrait(&model.portrait, model.portrait_file.as_deref().unwrap_or(PORTRAIT_FILE)),
+
+        // save the last few seconds as a gif
+
+        Key::Y => model.clip.save(),
+
+        // back to the pivot's place in the window, at the same zoom
+
+        Key::Key0 => pan(app, model, -model.camera_offset),
+
+        // step through the kaleidoscope mirrors
+
+        Key::K => model.mirror = model.mirror.next(),
+
+        // pull only one bob down, then only the other, then both again. the energy changes
+        // with it, so that's what's conserved from then on
+
+        Key::X => {
+            let scales = match (model.params.gravity_scale_1 != 0.0, model.params.gravity_scale_2 != 0.0) {
+                (true, true) => (1.0, 0.0),
+                (true, false) => (0.0, 1.0),
+                _ => (1.0, 1.0),
+            };
+
+            (model.params.gravity_scale_1, model.params.gravity_scale_2) = scales;
+            rebaseline(model);
+        }
+
+        // start again from the next setup in the library
+
+        Key::N => load_next_config(app, model),
+
+        // switch the frame the second bob is traced in. the old trace was measured from
+        // somewhere else, so it's dropped
+
+        Key::W => {
+            model.trace_frame = model.trace_frame.next();
+            clear_trace(app, model);
+        }
+
+        // show / hide the conserved quantity monitor
+
+        Key::Z => model.show_monitor = !model.show_monitor,
+
+        // show / hide the rulers round where the second bob has reached
+
+        Key::Q => model.show_rulers = !model.show_rulers,
+
+        // start the rulers' measurement over, leaving the traces as they are
+
+        Key::Back => model.envelope = None,
+
+        // masses, lengths and gravity, and the time scale
+
+        _ => {
+            if let Some((value, factor, range)) = nudge_key(key, model) {
+                nudge(value, factor, range);
+                parameters_changed(app, model, key);
+            }
+        }
+    }
+
+}
+
+// the pendulum has been set going afresh, so its energy and angular momentum from here on are
+// the ones to hold
+
+fn rebaseline(model: &mut Model) {
+    model.target_energy = energy(&model.state, &model.params);
+    model.target_momentum = angular_momentum(&model.state, &model.params);
+}
+
+// each pair of number keys scales one of the masses or lengths down / up by 10% a press, and
+// - / = do the same for gravity and [ / ] for the time scale
+
+fn nudge_key(key: Key, model: &mut Model) -> Option<(&mut f32, f32, (f32, f32))> {
+
+    let (down, up) = (1.0 / 1.1, 1.1);
+    let params = &mut model.params;
+
+    let nudge = match key {
+        Key::Key1 => (&mut params.m1, down, MASS_RANGE),
+        Key::Key2 => (&mut params.m1, up, MASS_RANGE),
+        Key::Key3 => (&mut params.m2, down, MASS_RANGE),
+        Key::Key4 => (&mut params.m2, up, MASS_RANGE),
+
+        Key::Key5 => (&mut params.l1, down, LENGTH_RANGE),
+        Key::Key6 => (&mut params.l1, up, LENGTH_RANGE),
+        Key::Key7 => (&mut params.l2, down, LENGTH_RANGE),
+        Key::Key8 => (&mut params.l2, up, LENGTH_RANGE),
+
+        Key::Minus => (&mut params.gravity, down, GRAVITY_RANGE),
+        Key::Equals => (&mut params.gravity, up, GRAVITY_RANGE),
+
+        Key::LBracket => (&mut model.time_scale, down, TIME_SCALE_RANGE),
+        Key::RBracket => (&mut model.time_scale, up, TIME_SCALE_RANGE),
+
+        _ => return None,
+    };
+
+    Some(nudge)
+
+}
+
+// tidy up after the key for a mass, length or gravity has changed it. the time scale only changes how
+// fast the run goes, so there's nothing to tidy after that
+
+fn parameters_changed(app: &App, model: &mut Model, key: Key) {
+
+    if matches!(key, Key::LBracket | Key::RBracket) {
+        return;
+    }
+
+    // the heatmap grid is sized by the total length, so what's in it no longer lines up
+
+    if matches!(key, Key::Key5 | Key::Key6 | Key::Key7 | Key::Key8) {
+        model.density.clear();
+    }
+
+    // the same for the canvas, which can only be drawn on at the scale it was drawn at
+
+    if matches!(key, Key::Key5 | Key::Key6 | Key::Key7 | Key::Key8) && model.canvas.is_some() {
+        clear_trace(app, model);
+    }
+
+    // changing the masses, lengths or gravity changes the energy, so conserve the new value from
+    // here on
+
+    rebaseline(model);
+
+}
+
+// limits for the runtime adjustments, which are multiplicative so can never reach zero anyway
+
+const MASS_RANGE: (f32, f32) = (0.1, 10.0);
+const LENGTH_RANGE: (f32, f32) = (0.1, 5.0);
+const GRAVITY_RANGE: (f32, f32) = (0.1, 100.0);
+const TIME_SCALE_RANGE: (f32, f32) = (0.05, 20.0);
+
+fn nudge(value: &mut f32, factor: f32, (min, max): (f32, f32)) {
+    *value = (*value * factor).clamp(min, max);
+}
+
+// distances from the pendulum where the ensemble colours start and end: the spread they start
+// with, up to fully decorrelated. they're spaced on a log scale since divergence is
+// exponential, so the colours sweep through at a steady rate while it's growing
+
+const ENSEMBLE_FAR: f64 = 10.0;
+
+fn ensemble_color(theme: &Theme, spread: f64, distance: f64) -> Srgb {
+
+    let (near, far) = (spread.log10(), ENSEMBLE_FAR.log10());
+    let t = ((distance.max(1e-300).log10() - near) / (far - near)).clamp(0.0, 1.0) as f32;
+
+    let [a, b, c] = theme.ensemble_colors;
+
+    if t < 0.5 {
+        lerp_color(a, b, 2.0 * t)
+    } else {
+        lerp_color(b, c, 2.0 * t - 1.0)
+    }
+
+}
+
+// legend for the ensemble colours down the right hand edge
+
+fn draw_colorbar(draw: &Draw, theme: &Theme, spread: f64, window: Rect) {
+
+    let bar = Rect::from_x_y_w_h(window.right() - 30.0, window.y(), 10.0, 200.0);
+    let (near, far) = (spread.log10(), ENSEMBLE_FAR.log10());
+    let bands = 32;
+
+    for i in 0..bands {
+
+        let t = (i as f64 + 0.5) / bands as f64;
+        let distance = 10.0f64.powf(near + t * (far - near));
+
+        draw.rect()
+            .x_y(bar.x(), bar.bottom() + t as f32 * bar.h())
+            .w_h(bar.w(), bar.h() / bands as f32)
+            .color(ensemble_color(theme, spread, distance));
+    }
+
+    for (distance, y) in [(spread, bar.bottom()), (ENSEMBLE_FAR, bar.top())] {
+        draw.text(&format!("{:.0e}", distance))
+            .x_y(bar.x() - 30.0, y)
+            .w_h(40.0, 20.0)
+            .right_justify()
+            .color(theme.text);
+    }
+
+    draw.text("distance")
+        .x_y(bar.x() - 10.0, bar.top() + 16.0)
+        .w_h(80.0, 20.0)
+        .color(theme.text);
+
+}
+
+// the external oscillator as a dial at the bottom of the window, with a hand going round at its
+// phase and a second, thinner one at the first arm's phase measured the same way. once the arm
+// is entrained the two hands turn together
+
+fn draw_drive(draw: &Draw, model: &Model, drive: &Drive, window: Rect, dpi: f32) {
+
+    let theme = &model.theme;
+    let centre = window.mid_bottom() + vec2(0.0, 100.0);
+    let radius = 30.0;
+
+    let hand = |phase: f64| centre + radius * vec2(phase.cos() as f32, phase.sin() as f32);
+    let arm = oscillation_phase(model.state.theta_1, model.state.dot_theta_1, drive.frequency);
+
+    draw.ellipse()
+        .xy(centre)
+        .radius(radius)
+        .no_fill()
+        .stroke(theme.mechanism)
+        .stroke_weight(1.0);
+
+    draw.line().start(centre).end(hand(drive.phase)).weight(2.0 * dpi).color(theme.trace_end);
+    draw.line().start(centre).end(hand(arm)).weight(dpi).color(theme.trace_start);
+
+    let units = units(model);
+
+    draw.text(&format!("drive {:.2} {}   coupling {:.2} {}", drive.frequency, units.rate, drive.coupling, units.torque))
+        .xy(centre - vec2(0.0, radius + 14.0))
+        .w_h(300.0, 20.0)
+        .color(theme.text);
+
+}
+
+// each arm's angle as a hand on a small dial under the readout, pointing the way the arm does
+// in the animation (by to_cartesian, so straight down at zero), with a tick where it hangs
+
+fn draw_dials(draw: &Draw, model: &Model, window: Rect, dpi: f32) {
+
+    let theme = &model.theme;
+    let radius = 28.0;
+
+    for (i, (theta, color)) in [(model.state.theta_1, theme.trace_bob1_end), (model.state.theta_2, theme.trace_end)].into_iter().enumerate() {
+
+        let centre = window.top_left() + vec2(50.0 + 80.0 * i as f32, -110.0);
+        let hand = to_cartesian(radius, theta);
+
+        draw.ellipse()
+            .xy(centre)
+            .radius(radius)
+            .no_fill()
+            .stroke(theme.mechanism)
+            .stroke_weight(1.0);
+
+        draw.line()
+            .start(centre - vec2(0.0, 0.8 * radius))
+            .end(centre - vec2(0.0, radius))
+            .weight(dpi)
+            .color(theme.mechanism);
+
+        draw.line()
+            .start(centre)
+            .end(centre + vec2(hand.x, hand.y))
+            .weight(2.0 * dpi)
+            .color(color);
+
+        let degrees = ((theta + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI).to_degrees();
+
+        draw.text(&format!("θ{} {:.0}°", i + 1, degrees))
+            .xy(centre - vec2(0.0, radius + 12.0))
+            .w_h(80.0, 20.0)
+            .color(theme.text);
+    }
+
+}
+
+// how far apart the main run and the rival have got, in the right of the window below where the
+// parametric plot goes. the separation starts around rounding error and grows exponentially
+// once the motion is chaotic, so it's on a log scale fitted to the range that's been seen, and
+// the steady slope it settles into is the lyapunov exponent
+
+fn draw_separation(draw: &Draw, model: &Model, rival: &Rival, window: Rect, dpi: f32) {
+
+    let theme = &model.theme;
+    let chart = Rect::from_x_y_w_h(window.right() - 170.0, window.top() - 320.0, 300.0, 120.0);
+    let now = model.sim_time;
+
+    let logs: Vec<(f64, f64)> = rival.separation.iter()
+        .map(|(time, separation)| (*time, separation.max(1e-16).log10()))
+        .collect();
+
+    let (low, high) = logs.iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (_, log)| (low.min(*log), high.max(*log)));
+    let (low, high) = if low <= high { (low, high.max(low + 1.0)) } else { (-16.0, 0.0) };
+
+    let to_chart = |time: f64, log: f64| {
+        let x = 1.0 - (now - time) / SEPARATION_HISTORY;
+        let y = (log - low) / (high - low);
+        pt2(chart.left() + x as f32 * chart.w(), chart.bottom() + y as f32 * chart.h())
+    };
+
+    draw.rect()
+        .xy(chart.xy())
+        .wh(chart.wh())
+        .no_fill()
+        .stroke(theme.mechanism)
+        .stroke_weight(1.0);
+
+    draw.polyline()
+        .weight(dpi)
+        .points(logs.iter().map(|(time, log)| to_chart(*time, *log)))
+        .color(theme.trace_end);
+
+    let current = rival.separation.back().map_or(0.0, |(_, separation)| *separation);
+    let label = format!("{} against {}: apart by {:.1e}", model.sim.integrator.name(), rival.integrator.name(), current);
+
+    draw.text(&label)
+        .xy(chart.mid_top() + vec2(0.0, 12.0))
+        .wh(vec2(chart.w(), 20.0))
+        .color(theme.text);
+
+    draw.text(&format!("10^{:.0}", high))
+        .xy(chart.top_left() - vec2(24.0, 0.0))
+        .w_h(40.0, 20.0)
+        .color(theme.text);
+
+    draw.text(&format!("10^{:.0}", low))
+        .xy(chart.bottom_left() - vec2(24.0, 0.0))
+        .w_h(40.0, 20.0)
+        .color(theme.text);
+
+}
+
+// a bracket under the second bob's envelope measuring its width, and one to the right of it
+// measuring its height, each with a tick where the pivot falls along it, so how far it's
+// reached to either side can be read off as well
+
+const RULER_GAP: f32 = 20.0; // pixels between the envelope and its rulers
+const RULER_TICK: f32 = 6.0;
+
+fn draw_rulers(draw: &Draw, model: &Model, pivot: Point2, scale: f32, dpi: f32) {
+
+    let Some(envelope) = model.envelope else {
+        return;
+    };
+
+    let theme = &model.theme;
+    let units = units(model);
+    let (gap, tick) = (RULER_GAP * dpi, RULER_TICK * dpi);
+
+    let corner = |point: Vec2| pivot + point * scale;
+    let (low, high) = (corner(envelope.bottom_left()), corner(envelope.top_right()));
+
+    // along the bottom, then down the right hand side, each as the line, its two end ticks, the
+    // pivot's tick and the label, which sits outside the bracket
+
+    let rulers = [
+        (pt2(low.x, low.y - gap), pt2(high.x, low.y - gap), vec2(0.0, 1.0), pt2(pivot.x, low.y - gap), envelope.w(), vec2(0.0, -12.0)),
+        (pt2(high.x + gap, low.y), pt2(high.x + gap, high.y), vec2(-1.0, 0.0), pt2(high.x + gap, pivot.y), envelope.h(), vec2(40.0, 0.0)),
+    ];
+
+    for (start, end, inwards, origin, length, label) in rulers {
+
+        draw.line().start(start).end(end).weight(dpi).color(theme.ruler);
+
+        for point in [start, end] {
+            draw.line().start(point).end(point + inwards * tick).weight(dpi).color(theme.ruler);
+        }
+
+        if origin.x >= start.x && origin.x <= end.x && origin.y >= start.y && origin.y <= end.y {
+            draw.line().start(origin - inwards * 0.5 * tick).end(origin + inwards * 0.5 * tick).weight(dpi).color(theme.ruler);
+        }
+
+        draw.text(&format!("{:.2} {}", length, units.length))
+            .xy((start + end) / 2.0 + label * dpi)
+            .color(theme.ruler)
+            .font_size(12);
+    }
+
 }
 
-// store defining degrees of freedom as a state object
+// the quantities the motion should conserve, each against its value when the pendulum was last
+// set going, to see how well the integrator and step are holding them. drift is given as a
+// percentage of that value, unless it's too near zero for one to mean anything (angular
+// momentum starting from rest, say). angular momentum is only conserved with gravity off, so
+// otherwise it's shown without its drift. any torque applied puts both off too
+
+fn draw_monitor(draw: &Draw, model: &Model, window: Rect) {
+
+    let theme = &model.theme;
+    let panel = Rect::from_x_y_w_h(window.left() + 170.0, window.bottom() + 150.0, 300.0, 70.0);
+
+    let drift = |now: f64, start: f64| {
+        if start.abs() > 1e-9 {
+            format!("drift {:+.4}%", 100.0 * (now - start) / start.abs())
+        } else {
+            format!("drift {:+.2e}", now - start)
+        }
+    };
+
+    let energy_now = energy(&model.state, &model.params);
+    let momentum_now = angular_momentum(&model.state, &model.params);
+
+    let momentum_drift = if model.params.bob_gravity() == (0.0, 0.0) {
+        drift(momentum_now, model.target_momentum)
+    } else {
+        "not conserved under gravity".to_string()
+    };
+
+    let units = units(model);
+
+    let text = format!(
+        "conserved quantities\nenergy {:.6} {}   {}\nangular momentum {:.6} {}   {}",
+        energy_now,
+        units.energy,
+        drift(energy_now, model.target_energy),
+        momentum_now,
+        units.angular_momentum,
+        momentum_drift,
+    );
+
+    draw.rect()
+        .xy(panel.xy())
+        .wh(panel.wh())
+        .no_fill()
+        .stroke(theme.mechanism)
+        .stroke_weight(1.0);
+
+    draw.text(&text)
+        .xy(panel.xy() + vec2(8.0, 0.0))
+        .wh(panel.wh())
+        .left_justify()
+        .color(theme.text);
 
-#[derive(Copy, Clone)]
-struct State {
-    theta_1: f32,
-    theta_2: f32,
-    dot_theta_1: f32,
-    dot_theta_2: f32,
 }
 
-// implement scalar vector multiplication, division, and vector addition on State for rk4 
+// the region of (theta_2, dot_theta_2) the ensemble and the pendulum cover between them, as a
+// translucent convex hull on the left. it starts as a dot and spreads out as they diverge.
+// angles are shifted by whole turns so the pendulum's own theta_2 stays in view, and the
+// members move with it so the region stays in one piece. anything further out is pinned to the
+// edges
 
-impl std::ops::Mul<f32> for State {
-    type Output = Self;
+const HULL_SPEED_RANGE: f64 = 15.0; // rad/s either side of zero
 
-    fn mul(self, rhs: f32) -> Self {
-        Self {
-            theta_1: self.theta_1 * rhs,
-            theta_2: self.theta_2 * rhs,
-            dot_theta_1: self.dot_theta_1 * rhs,
-            dot_theta_2: self.dot_theta_2 * rhs,
+fn draw_phase_hull(draw: &Draw, model: &Model, window: Rect, dpi: f32) {
+
+    let theme = &model.theme;
+    let plot = Rect::from_x_y_w_h(window.left() + 120.0, window.y(), 200.0, 200.0);
+    let shift = std::f64::consts::TAU * turns(model.state.theta_2) as f64;
+
+    let to_plot = |(theta, speed): (f64, f64)| {
+        let x = ((theta - shift) / std::f64::consts::PI).clamp(-1.0, 1.0);
+        let y = (speed / HULL_SPEED_RANGE).clamp(-1.0, 1.0);
+        plot.xy() + vec2(x as f32, y as f32) * plot.wh() / 2.0
+    };
+
+    let points: Vec<(f64, f64)> = model.ensemble.iter()
+        .chain(std::iter::once(&model.state))
+        .map(|state| (state.theta_2, state.dot_theta_2))
+        .collect();
+
+    let hull: Vec<Point2> = convex_hull(&points).into_iter().map(to_plot).collect();
+    let color = theme.trace_end.into_format::<f32>();
+
+    draw.rect()
+        .xy(plot.xy())
+        .wh(plot.wh())
+        .no_fill()
+        .stroke(theme.mechanism)
+        .stroke_weight(1.0);
+
+    match hull.len() {
+        0 => {}
+        1 => {
+            draw.ellipse().xy(hull[0]).radius(2.0 * dpi).color(theme.trace_end);
+        }
+        2 => {
+            draw.line().start(hull[0]).end(hull[1]).weight(2.0 * dpi).color(theme.trace_end);
+        }
+        _ => {
+            draw.polygon()
+                .points(hull.iter().copied())
+                .color(srgba(color.red, color.green, color.blue, 0.35));
+
+            draw.polyline()
+                .weight(dpi)
+                .points_closed(hull.iter().copied())
+                .color(theme.trace_end);
         }
     }
+
+    draw.text("theta_2 against dot_theta_2")
+        .xy(plot.mid_bottom() - vec2(0.0, 12.0))
+        .wh(vec2(plot.w(), 20.0))
+        .color(theme.text);
+
 }
 
-impl std::ops::Div<f32> for State {
-    type Output = Self;
+// each bob's velocity along and across its rod, as numbers under the parameter readout and
+// (if enabled) arrows from the bobs drawn at a fifth of the drawing scale, so 5 m/s is as
+// long as a 1 m arm
 
-    fn div(self, rhs: f32) -> Self {
-        Self {
-            theta_1: self.theta_1 / rhs,
-            theta_2: self.theta_2 / rhs,
-            dot_theta_1: self.dot_theta_1 / rhs,
-            dot_theta_2: self.dot_theta_2 / rhs,
+fn draw_velocities(draw: &Draw, model: &Model, window: Rect, scale: f32, dpi: f32, bobs: (Point2, Point2)) {
+
+    let theme = &model.theme;
+    let components = velocity_components(&model.state, &model.params);
+
+    let readout = format!(
+        "bob 1: radial {:+.3} m/s   tangential {:+.3} m/s\nbob 2: radial {:+.3} m/s   tangential {:+.3} m/s",
+        components[0].0, components[0].1, components[1].0, components[1].1,
+    );
+
+    draw.text(&readout)
+        .xy(window.top_left() + vec2(210.0, -80.0))
+        .wh(vec2(400.0, 40.0))
+        .left_justify()
+        .align_text_top()
+        .color(theme.text);
+
+    if model.velocity_display != VelocityDisplay::Arrows {
+        return;
+    }
+
+    let thetas = [model.state.theta_1, model.state.theta_2];
+
+    for ((bob, (radial, tangential)), theta) in [bobs.0, bobs.1].into_iter().zip(components).zip(thetas) {
+
+        let along = vec2(theta.sin() as f32, -theta.cos() as f32);
+        let across = vec2(theta.cos() as f32, theta.sin() as f32);
+
+        for (direction, size, color) in [(along, radial, theme.radial), (across, tangential, theme.tangential)] {
+
+            let end = bob + direction * size as f32 * 0.2 * scale;
+
+            if bob.distance(end) < 1.0 {
+                continue;
+            }
+
+            draw.arrow()
+                .start(bob)
+                .end(end)
+                .weight(2.0 * dpi)
+                .color(color);
         }
     }
+
 }
 
-impl std::ops::Add<State> for State {
-    type Output = Self;
+// a bob is drawn bigger and in its own colour while it's held, so it's clear which one is
+// being moved even where the two overlap
 
-    fn add(self, other: State) -> Self {
-        Self {
-            theta_1: self.theta_1 + other.theta_1,
-            theta_2: self.theta_2 + other.theta_2,
-            dot_theta_1: self.dot_theta_1 + other.dot_theta_1,
-            dot_theta_2: self.dot_theta_2 + other.dot_theta_2,
+fn draw_bob(draw: &Draw, model: &Model, bob: usize, position: Point2, dpi: f32) {
+
+    let theme = &model.theme;
+    let held = model.grabbed_bob == Some(bob);
+
+    let color = if held {
+        theme.grabbed.into_format()
+    } else if model.show_acceleration {
+        let accelerations = bob_accelerations(&model.state, &model.params);
+        let acceleration = if bob == 1 { accelerations.0 } else { accelerations.1 };
+        let t = (acceleration as f32 / theme.max_acceleration).min(1.0);
+
+        lerp_color(theme.acceleration_calm, theme.acceleration_hot, t)
+    } else {
+        theme.mechanism.into_format()
+    };
+
+    let radius = theme.bob_radius * dpi * if held { 1.5 } else { 1.0 };
+
+    draw.ellipse()
+        .color(color)
+        .radius(radius)
+        .xy(position);
+
+    // a ring that spreads out and fades after a kick
+
+    let flash = model.kick_flash[bob - 1] / KICK_FLASH;
+
+    if flash > 0.0 {
+        let c = theme.kick.into_format::<f32>();
+
+        draw.ellipse()
+            .xy(position)
+            .radius(radius * (1.0 + 2.0 * (1.0 - flash)))
+            .no_fill()
+            .stroke(srgba(c.red, c.green, c.blue, flash))
+            .stroke_weight(2.0 * dpi);
+    }
+
+    // the label hangs off the bob's upper right, clear of the ellipse
+
+    if model.show_mass_labels {
+        let mass = if bob == 1 { model.params.m1 } else { model.params.m2 };
+
+        draw.text(&format!("m{} = {:.2} {}", bob, mass, units(model).mass))
+            .xy(position + vec2(radius + 50.0, radius + 4.0))
+            .w_h(100.0, 20.0)
+            .left_justify()
+            .color(theme.text);
+    }
+
+}
+
+// the strobe's afterimages, oldest (and faintest) first so newer ones sit on top
+
+fn draw_strobe(draw: &Draw, model: &Model, pivot: Point2, scale: f32, dpi: f32) {
+
+    let theme = &model.theme;
+    let newest = model.states_seen.len().saturating_sub(1);
+    let c = theme.mechanism.into_format::<f32>();
+
+    for k in (1..=model.strobe_count).rev() {
+
+        let Some(state) = newest.checked_sub(k * model.strobe_stride).map(|i| model.states_seen[i]) else {
+            continue;
+        };
+
+        let q1 = to_cartesian(scale * model.params.l1, state.theta_1);
+        let q2 = to_cartesian(scale * model.params.l2, state.theta_2);
+        let end_1 = pivot + vec2(q1.x, q1.y);
+        let end_2 = end_1 + vec2(q2.x, q2.y);
+
+        let color = srgba(c.red, c.green, c.blue, 0.5 * (1.0 - k as f32 / (model.strobe_count + 1) as f32));
+
+        draw.polyline()
+            .weight(theme.rod_weight * dpi)
+            .points([pivot, end_1, end_2])
+            .color(color);
+
+        for end in [end_1, end_2] {
+            draw.ellipse()
+                .color(color)
+                .radius(theme.bob_radius * dpi)
+                .xy(end);
         }
     }
+
 }
 
-// for easier conversion between polar and cartesian for drawing
+// the weight and colour to draw each rod with. with rod forces on, the force is measured against
+// what the rod carries hanging still, so the scale doesn't change with the masses or gravity
+// (and without gravity, against it as if it were earth's)
 
-struct Cartesian {
+fn rod_styles(model: &Model, dpi: f32) -> [(f32, Srgb); 2] {
 
-    x: f32,
-    y: f32,
+    let theme = &model.theme;
+    let plain = (theme.rod_weight * dpi, theme.mechanism.into_format());
+
+    if !model.show_rod_forces {
+        return [plain; 2];
+    }
+
+    let params = &model.params;
+    let gravity = if params.gravity == 0.0 { 9.81 } else { params.gravity.abs() };
+    let (t1, t2) = rod_forces(&model.state, params);
+
+    [(t1, (params.m1 + params.m2) * gravity), (t2, params.m2 * gravity)].map(|(force, load)| {
+        let t = (force as f32 / (load * theme.max_rod_load)).clamp(-1.0, 1.0);
+
+        if t >= 0.0 {
+            (plain.0 * (1.0 + 2.0 * t), lerp_color(theme.mechanism, theme.tension, t))
+        } else {
+            (plain.0 * (1.0 + 0.75 * t), lerp_color(theme.mechanism, theme.compression, -t))
+        }
+    })
 
 }
 
-fn to_cartesian(r: f32, theta: f32) -> Cartesian {
-    
-    // polar to cartesian
+// one rod from where it hangs to its bob, in the weight and colour rod_styles gives it. tapered,
+// that weight is its width at the top, narrowing to rod_taper of it at the bob, which covers
+// the narrow end
+
+fn draw_rod(draw: &Draw, theme: &Theme, start: Point2, end: Point2, (weight, color): (f32, Srgb)) {
+
+    let Some(taper) = theme.rod_taper else {
+        draw.line().start(start).end(end).weight(weight).color(color);
+        return;
+    };
 
-    let x = r * theta.sin();
-    let y = - r * theta.cos();
+    // half the width at each end, across the rod
 
-    Cartesian { x, y }
+    let across = (end - start).normalize_or_zero().perp();
+    let (top, bottom) = (0.5 * weight * across, 0.5 * weight * taper * across);
+
+    draw.quad()
+        .points(start + top, end + bottom, end - bottom, start - top)
+        .color(color);
 
 }
 
-fn derivatives(state: &State, model: &Model) -> State {
+// the comparison in the right half of the window, zoomed and panned along with the main run,
+// with a line down the middle between them and what's different written above it
+
+fn draw_comparison(draw: &Draw, model: &Model, comparison: &Comparison, window: Rect, pivot: Point2, scale: f32, dpi: f32) {
+
+    let theme = &model.theme;
+    let pivot = pivot + vec2(window.w() / 2.0, 0.0);
+
+    draw.line()
+        .start(window.mid_top())
+        .end(window.mid_bottom())
+        .weight(dpi)
+        .color(theme.mechanism);
 
-    // cache reused values to reduce memory calls
-    
-    let g = model.gravity;
-    let m1 = model.m1;
-    let m2 = model.m2;
-    let l1 = model.l1;
-    let l2 = model.l2;
+    let weight = theme.trace_weight * dpi;
 
-    let dot_theta_1 = state.dot_theta_1;
-    let dot_theta_2 = state.dot_theta_2;
+    for transform in model.mirror.images() {
+        let image = comparison.trail.iter().map(|point| point.map(transform)).collect();
+        draw_trail(draw, &image, (theme.trace_start, theme.trace_end), pivot, scale, weight, theme.trace_glow, model.fade);
+    }
 
-    // cache calculated values to reduce computation
+    let q1 = to_cartesian(scale * comparison.params.l1, comparison.state.theta_1);
+    let q2 = to_cartesian(scale * comparison.params.l2, comparison.state.theta_2);
+    let bob_1 = pivot + vec2(q1.x, q1.y);
+    let bob_2 = bob_1 + vec2(q2.x, q2.y);
 
-    let mratio = m2 / m1;
-    let mrat_plus = mratio + 1.0;
-    let lratio = l2 / l1;
-    let gamma = g / l1;
-    let dtheta = state.theta_1 - state.theta_2;
+    draw.polyline()
+        .weight(theme.rod_weight * dpi)
+        .points([pivot, bob_1, bob_2])
+        .color(theme.mechanism);
 
-    let sin_theta_1 = state.theta_1.sin();
-    let sin_theta_2 = state.theta_2.sin();
+    for position in [pivot, bob_1, bob_2] {
+        draw.ellipse()
+            .xy(position)
+            .radius(theme.bob_radius * dpi)
+            .color(theme.mechanism);
+    }
 
-    let sin_dtheta = dtheta.sin();
-    let cos_dtheta = dtheta.cos();
+    let half = window.w() / 4.0;
 
-    let denominator = 1.0 + (mratio * sin_dtheta * sin_dtheta);
+    for (x, label) in [(window.x() - half, "this run".to_string()), (window.x() + half, format!("compared: {}", comparison.label))] {
+        draw.text(&label)
+            .x_y(x, window.top() - 60.0)
+            .w_h(2.0 * half, 20.0)
+            .color(theme.text);
+    }
 
-    // equations from uni edinburgh (page 30-31):
-    // https://www2.ph.ed.ac.uk/~dmarendu/MVP/DoublePendulumTutorial.pdf
+}
 
-    let num_1 = (mrat_plus * gamma * sin_theta_1) + (mratio * lratio * dot_theta_2 * dot_theta_2 * sin_dtheta) + (mratio * cos_dtheta * (dot_theta_1 * dot_theta_1 * sin_dtheta - gamma * sin_theta_2));
+// the rods' smear over the last few frames, oldest (and faintest) first. it's just the one
+// polyline per frame, so it costs next to nothing at a handful of frames
 
-    // pendulum 1 acceleration
+fn draw_ghost_rods(draw: &Draw, model: &Model, pivot: Point2, scale: f32, dpi: f32) {
 
-    let ddot_theta_1 = - num_1 / denominator;
+    let theme = &model.theme;
+    let c = theme.mechanism.into_format::<f32>();
+    let count = model.ghost_frames.min(model.states_seen.len().saturating_sub(1));
 
-    let num_2 = mrat_plus * (dot_theta_1 * dot_theta_1 * sin_dtheta - gamma * sin_theta_2) + cos_dtheta * (mrat_plus * gamma * sin_theta_1 + mratio * lratio * dot_theta_2 * dot_theta_2 * sin_dtheta);
+    // the newest state is the one being drawn properly, so start from the one before
 
-    // pendulum 2 acceleration
+    for (k, state) in model.states_seen.iter().rev().skip(1).take(count).enumerate().rev() {
 
-    let ddot_theta_2 = num_2 / (lratio * denominator);
+        let q1 = to_cartesian(scale * model.params.l1, state.theta_1);
+        let q2 = to_cartesian(scale * model.params.l2, state.theta_2);
+        let end_1 = pivot + vec2(q1.x, q1.y);
+        let end_2 = end_1 + vec2(q2.x, q2.y);
 
-    // return new values from initial state
+        let alpha = 0.4 * (1.0 - (k + 1) as f32 / (model.ghost_frames + 1) as f32);
 
-    State { 
-        theta_1: dot_theta_1,
-        theta_2: dot_theta_2,
-        dot_theta_1: ddot_theta_1,
-        dot_theta_2: ddot_theta_2,
+        draw.polyline()
+            .weight(theme.rod_weight * dpi)
+            .points([pivot, end_1, end_2])
+            .color(srgba(c.red, c.green, c.blue, alpha));
     }
 
 }
 
-fn rk4(state: &State, model: &Model, dt: f32) -> State {
+// pixels per metre: 100 as standard (see model()), but shrunk if needed so the full reach of
+// the pendulum stays inside the window from wherever the pivot is
 
-    // rk4 implementation
-    // essentially a weighted average of iterative euler's method steps
+fn pixel_scale(window: Rect, model: &Model) -> f32 {
 
-    // k1 is equivalent to euler's method (poor performance)
+    let view = viewport(window, model);
+    let pivot = view.xy() + model.pivot;
 
-    let k1 = derivatives(state, model) * dt;
+    // with a comparison both halves are drawn to one scale, so it has to fit both pendulums
 
-    // we then take the new state estimate that k1 gives and make another state estimate
-    // for what the derivatives would look like after half of the time step
+    let reach = match &model.comparison {
+        Some(comparison) => (model.params.l1 + model.params.l2).max(comparison.params.l1 + comparison.params.l2),
+        None => model.params.l1 + model.params.l2,
+    };
 
-    let k2_state = *state + k1 * 0.5;
-    let k2 = derivatives(&k2_state, model) * dt;
+    let room = (view.right() - pivot.x)
+        .min(pivot.x - view.left())
+        .min(view.top() - pivot.y)
+        .min(pivot.y - view.bottom());
 
-    // and then use the k2 estimate for k3... etc
+    model.zoom * (0.95 * room / reach).clamp(1.0, 100.0)
 
-    let k3_state = *state + k2 * 0.5;
-    let k3 = derivatives(&k3_state, model) * dt;
-    
-    let k4_state = *state + k3;
-    let k4 = derivatives(&k4_state, model) * dt;
+}
 
-    // return new state from weighted averages
+// where the pivot is drawn, once zoomed and panned
 
-    *state + (k1 + k2 * 2.0 + k3 * 2.0 + k4) / 6.0
+fn screen_pivot(window: Rect, model: &Model) -> Point2 {
+    viewport(window, model).xy() + model.pivot * model.zoom + model.camera_offset * pixel_scale(window, model)
 }
 
+// the part of the window the main run is drawn in: the left half with a comparison beside it,
+// which gets the right half, and otherwise all of it
 
-struct Model {
-    state: State, // vector [theta_1, theta_2, dot_theta_1, dot_theta_2]
+fn viewport(window: Rect, model: &Model) -> Rect {
+    match model.comparison {
+        Some(_) => Rect::from_x_y_w_h(window.x() - window.w() / 4.0, window.y(), window.w() / 2.0, window.h()),
+        None => window,
+    }
+}
 
-    // arm lengths
+fn view(app: &App, model: &Model, frame: Frame) {
 
-    l1: f32,
-    l2: f32,
+    let draw = app.draw();
 
-    // bob masses
+    // scalar for visibility
+    let window = app.window_rect();
+    let scale = pixel_scale(window, model);
 
-    m1: f32,
-    m2: f32,
+    // each bob is offset from the end of the arm above it
 
-    gravity: f32,
-    // dampening: f32, // not implemented but would slow divergence and add friction
+    let pivot = screen_pivot(window, model);
+    let hanging = hanging_point(window, model);
+    let (bob_1, bob_2) = bob_positions(window, model);
 
-    // a vector of max previous points used for tracing the path of the second pendulum
+    let theme = &model.theme;
+    let dpi = if theme.scale_with_dpi { app.main_window().scale_factor() } else { 1.0 };
 
-    path: Vec<Point2>,
-    max_path_length: usize,
-}
+    match theme.background {
+        Background::Solid(color) => {
+            draw.background().color(color);
+        }
 
-fn model(_app: &App) -> Model {
-    Model {
+        // a quad over the window, its colours blended between the corners
 
-        state: State {
-            
-            // initial displacements
+        Background::Gradient(top, bottom) => {
+            let (top, bottom) = (top.into_format::<f32>(), bottom.into_format::<f32>());
+            let corners = [
+                (pt3(window.left(), window.top(), 0.0), top),
+                (pt3(window.right(), window.top(), 0.0), top),
+                (pt3(window.right(), window.bottom(), 0.0), bottom),
+                (pt3(window.left(), window.bottom(), 0.0), bottom),
+            ];
 
-            theta_1: 0.0,
-            theta_2: 2.0,
+            draw.background().color(bottom);
+            draw.mesh().indexed_colored(corners, [0, 1, 2, 0, 2, 3]);
+        }
 
-            // initial velocities ('kick')
+        Background::Image(_) => {
+            draw.background().color(BLACK);
 
-            dot_theta_1: 0.0,
-            dot_theta_2: 0.0,
-        },
+            if let Some(texture) = &model.background_image {
+                draw.texture(texture).wh(window.wh());
+            }
+        }
+    }
 
-        // worth noting that smaller values make the physics feel more realistic (1m is more
-        // familiar than 100...) but whenever these are drawn we multiply by 100 to make them
-        // visible without changing the physics
+    // heatmap sits underneath everything else
 
-        l1: 1.0,
-        l2: 1.0,
+    if model.show_density {
 
-        m1: 1.0,
-        m2: 1.0,
+        let density = &model.density;
+        let extent = scale * (model.params.l1 + model.params.l2);
+        let cell = 2.0 * extent / density.resolution as f32;
+        let color = theme.density.into_format::<f32>();
 
-        gravity: 10.0,
+        for (i, value) in density.cells.iter().enumerate() {
 
-        path: Vec::new(),
-        max_path_length: 500, // determines trace decay
+            let strength = density.strength(*value);
+
+            if strength < 0.01 {
+                continue;
+            }
+
+            let (row, col) = (i / density.resolution, i % density.resolution);
+
+            draw.rect()
+                .xy(pivot + vec2(-extent + (col as f32 + 0.5) * cell, -extent + (row as f32 + 0.5) * cell))
+                .w_h(cell, cell)
+                .color(srgba(color.red, color.green, color.blue, strength));
+        }
     }
-}
 
-fn update(app: &App, model: &mut Model, _update: Update) {
+    // draw traces first so they are behind, with the reference further back still
 
-    // scalar on dt for visualisation speed
+    let weight = theme.trace_weight * dpi;
 
-    let dt = 1.0 * app.duration.since_prev_update.as_secs_f32();
+    if model.show_reference {
+        let color = theme.reference.into_format::<f32>();
 
-    // perform rk4 state update
+        draw.polyline()
+            .weight(weight)
+            .points(model.reference.iter().map(|point| pivot + *point * scale))
+            .color(srgba(color.red, color.green, color.blue, 0.35));
+    }
 
-    model.state = rk4(&model.state, model, dt);
-    println!("[{}, {}]", model.state.theta_1, model.state.theta_2);
+    if let Some(canvas) = &model.canvas {
+        canvas.show(&draw, window);
+    } else {
+        for transform in model.mirror.images() {
+            let image = |trail: &VecDeque<Option<Point2>>| trail.iter().map(|point| point.map(transform)).collect();
 
-    // store new point in path trace
+            draw_trail(&draw, &image(&model.trail_bob1), (theme.trace_bob1_start, theme.trace_bob1_end), pivot, scale, weight, theme.trace_glow, model.fade);
+            draw_trail(&draw, &image(&model.trail_bob2), (theme.trace_start, theme.trace_end), pivot, scale, weight, theme.trace_glow, model.fade);
+        }
+    }
 
-    let p1 = to_cartesian(100.0 * model.l1, model.state.theta_1);
-    let p2 = to_cartesian(100.0 * model.l2, model.state.theta_2);
-    model.path.push(pt2(p1.x + p2.x, p1.y + p2.y));
+    if model.show_rulers {
+        draw_rulers(&draw, model, pivot, scale, dpi);
+    }
 
-    // remove values over the length cap
+    // a loop has just closed, so light up the second bob's trace over the top, fading out
 
-    if model.path.len() > model.max_path_length {
-        model.path.remove(0);
+    if model.loop_flash > 0.0 {
+        let c = theme.loop_closure.into_format::<f32>();
+        let alpha = model.loop_flash / LOOP_FLASH;
+
+        for transform in model.mirror.images() {
+            draw.polyline()
+                .weight(2.0 * weight)
+                .points(model.trail_bob2.iter().flatten().map(|point| pivot + transform(*point) * scale))
+                .color(srgba(c.red, c.green, c.blue, alpha));
+        }
     }
 
-}
+    // ensemble members as thin ghost pendulums behind the real one
 
-fn view(app: &App, model: &Model, frame: Frame) {
+    for member in &model.ensemble {
 
-    let draw = app.draw();
+        let q1 = to_cartesian(scale * model.params.l1, member.theta_1);
+        let q2 = to_cartesian(scale * model.params.l2, member.theta_2);
+        let end_1 = pivot + vec2(q1.x, q1.y);
+        let end_2 = end_1 + vec2(q2.x, q2.y);
 
-    // scalar for visibility
-    let p1 = to_cartesian(100.0 * model.l1, model.state.theta_1);
-    let p2 = to_cartesian(100.0 * model.l2, model.state.theta_2);
+        let color = ensemble_color(theme, model.ensemble_spread, phase_distance(member, &model.state));
+
+        draw.polyline()
+            .weight(dpi)
+            .points([pivot, end_1, end_2])
+            .color(color);
+    }
 
-    draw.background().color(WHITESMOKE);
+    if !model.ensemble.is_empty() {
+        draw_colorbar(&draw, theme, model.ensemble_spread, window);
+        draw_phase_hull(&draw, model, window, dpi);
+    }
 
-    // draw trace first so it is behind
+    if model.strobe {
+        draw_strobe(&draw, model, hanging, scale, dpi);
+    }
 
-    draw.polyline()
-        .color(CADETBLUE)
-        .stroke_weight(2.0)
-        .points(model.path.iter().cloned());
+    if model.ghost_rods {
+        draw_ghost_rods(&draw, model, hanging, scale, dpi);
+    }
+
+    // where the controller is pushing the first arm to, as a faint rod
+
+    let target = match model.control_mode {
+        Some(ControlMode::Hold) => Some(model.control.target),
+        Some(ControlMode::SwingUp) => model.balance_target,
+        None => None,
+    };
+
+    if let Some(target) = target {
+        let c = theme.mechanism.into_format::<f32>();
+        let target = to_cartesian(scale * model.params.l1, target);
+
+        draw.line()
+            .start(hanging)
+            .end(hanging + vec2(target.x, target.y))
+            .weight(theme.rod_weight * dpi)
+            .color(srgba(c.red, c.green, c.blue, 0.25));
+    }
+
+    // the cart's track through where it started, and the cart riding along it
+
+    if model.cart.is_some() {
+        let c = theme.mechanism.into_format::<f32>();
+
+        draw.line()
+            .start(pt2(window.left(), pivot.y))
+            .end(pt2(window.right(), pivot.y))
+            .weight(dpi)
+            .color(srgba(c.red, c.green, c.blue, 0.5));
+
+        draw.rect()
+            .xy(hanging)
+            .w_h(CART_SIZE.0 * dpi, CART_SIZE.1 * dpi)
+            .no_fill()
+            .stroke_weight(theme.rod_weight * dpi)
+            .stroke(theme.mechanism);
+    }
 
     // origin
-    
+
     draw.ellipse()
-        .color(GRAY)
-        .radius(7.0)
-        .x_y(0.0, 0.0);
+        .color(theme.mechanism)
+        .radius(theme.bob_radius * dpi)
+        .xy(hanging);
 
-    // to pendulum 1
+    let rods = rod_styles(model, dpi);
 
-    draw.line()
-        .start(pt2(0.0, 0.0))
-        .end(pt2(p1.x, p1.y)) // line.end() takes a nannou pt2 object
-        .weight(4.0)
-        .color(GRAY);
+    // to pendulum 1
 
-    draw.ellipse()
-        .color(GRAY)
-        .radius(7.0)
-        .x_y(p1.x, p1.y); // ellipse origin just takes (x, y) from Cartesian
+    draw_rod(&draw, theme, hanging, bob_1, rods[0]);
+    draw_bob(&draw, model, 1, bob_1, dpi);
 
     // to pendulum 2
 
-    draw.line()
-        .start(pt2(p1.x, p1.y))
-        .end(pt2(p1.x + p2.x, p1.y + p2.y)) // offset from endpoint of p1
-        .weight(4.0)
-        .color(GRAY);
+    draw_rod(&draw, theme, bob_1, bob_2, rods[1]); // offset from endpoint of p1
 
-    draw.ellipse()
-        .color(GRAY)
-        .radius(7.0)
-        .x_y(p1.x + p2.x, p1.y + p2.y);
+    draw_bob(&draw, model, 2, bob_2, dpi);
+
+    if let Some(comparison) = &model.comparison {
+        draw_comparison(&draw, model, comparison, window, pivot, scale, dpi);
+    }
+
+    // crosshair at the cursor while a bob is held
+
+    if model.grabbed_bob.is_some() {
+
+        let mouse = app.mouse.position();
+        let size = 2.0 * theme.bob_radius * dpi;
+
+        for direction in [vec2(1.0, 0.0), vec2(0.0, 1.0)] {
+            draw.line()
+                .start(mouse - direction * size)
+                .end(mouse + direction * size)
+                .weight(dpi)
+                .color(theme.grabbed);
+        }
+    }
+
+    // current parameters in the top left corner along with the normal mode frequencies they
+    // give, how locked together the arms' swings are, the denominator in the equations of
+    // motion along with the lowest it's been, and gravity's torque about the pivot
+
+    let modes = normal_mode_frequencies(&model.params);
+
+    let units = units(model);
+
+    let mut readout = format!(
+        "m1 = {:.2} {mass}   m2 = {:.2} {mass}   modes {:.2}, {:.2} {rate}\nl1 = {:.2} {length}   l2 = {:.2} {length}   g = {:.2} {acceleration}   sync {:.2}\ndenominator {:.3}   min {:.3}   gravity torque {:+.2} {torque}",
+        model.params.m1, model.params.m2, modes.0, modes.1, model.params.l1, model.params.l2, model.params.gravity,
+        synchronization(model.phase_history.iter().map(|(_, difference)| *difference)),
+        mass_denominator(&model.state, &model.params), model.stats.min_denominator,
+        gravity_torque(&model.state, &model.params),
+        mass = units.mass, rate = units.rate, length = units.length, acceleration = units.acceleration, torque = units.torque,
+    );
+
+    if let Some(tau) = model.tau {
+        readout += &format!("\nnatural units, time in τ = √(l1/g) = {:.3} s", tau);
+    }
+
+    if model.time_scale != 1.0 {
+        readout += &format!("\ntime running at x{:.2}", model.time_scale);
+    }
+
+    let control = match model.control_mode {
+        Some(ControlMode::Hold) => Some(format!("pd target {:.0}°", model.control.target.to_degrees())),
+        Some(ControlMode::SwingUp) if model.balance_target.is_some() => Some("swing-up: balancing".to_string()),
+        Some(ControlMode::SwingUp) => Some("swing-up: pumping".to_string()),
+        None => None,
+    };
+
+    if (model.params.gravity_scale_1, model.params.gravity_scale_2) != (1.0, 1.0) {
+        readout += &format!("\ngravity x{} on bob 1, x{} on bob 2", model.params.gravity_scale_1, model.params.gravity_scale_2);
+    }
+
+    if let Some(control) = control {
+        readout += &format!("\n{}   torque {:.1} {}", control, model.control_torque.abs(), units.torque);
+    }
+
+    if model.drive_torque.is_some() {
+        readout += &format!("\ndriving torque {:+.2} {}", model.drive_torque_now, units.torque);
+    }
+
+    if let Some(period) = model.loop_period {
+        readout += &format!("   loop closed, period ≈ {:.2} {}", period, units.time);
+    }
+
+    if let Some(budget) = model.trace_budget {
+        readout += &format!("\ntrace {:.2} of {:.2} {} ({:.0}%)", model.trace_length, budget, units.length, 100.0 * (model.trace_length / budget).min(1.0));
+    }
+
+    if let Some(cart) = &model.cart {
+        readout += &format!("\ncart at {:+.2} m, {:+.2} m/s, pushed with {:+.2} N", cart.x, cart.dot_x, cart.force_now);
+    }
+
+    draw.text(&readout)
+        .xy(window.top_left() + vec2(210.0, -35.0))
+        .wh(vec2(400.0, 50.0))
+        .left_justify()
+        .align_text_top()
+        .color(theme.text);
+
+    if model.velocity_display != VelocityDisplay::Hidden {
+        draw_velocities(&draw, model, window, scale, dpi, (bob_1, bob_2));
+    }
+
+    // parametric plot in the top right corner, scaled so either coordinate's full range fits
+
+    if let Some(axes) = model.lissajous_axes {
+
+        let plot = Rect::from_x_y_w_h(window.right() - 120.0, window.top() - 120.0, 200.0, 200.0);
+        let reach = model.params.l1 + model.params.l2;
+        let plot_scale = 0.5 * plot.w() / reach;
+
+        draw.rect()
+            .xy(plot.xy())
+            .wh(plot.wh())
+            .no_fill()
+            .stroke(theme.mechanism)
+            .stroke_weight(1.0);
+
+        let points = model.lissajous.iter().enumerate().map(|(i, point)| {
+            let t = i as f32 / model.lissajous.len() as f32;
+            (plot.xy() + *point * plot_scale, lerp_color(theme.trace_start, theme.trace_end, t))
+        });
+
+        draw.polyline()
+            .weight(dpi)
+            .points_colored(points);
+
+        let (horizontal, vertical) = axes.labels();
+
+        draw.text(&format!("{} against {}", vertical, horizontal))
+            .xy(plot.mid_bottom() - vec2(0.0, 12.0))
+            .wh(vec2(plot.w(), 20.0))
+            .color(theme.text);
+    }
+
+    // scrolling energy chart in the bottom right corner. the vertical axis fits whatever range
+    // has been seen, so even rk4's slow drift shows up, against a line at the energy being
+    // conserved
+
+    if model.show_energy {
+
+        let chart = Rect::from_x_y_w_h(window.right() - 170.0, window.bottom() + 80.0, 300.0, 120.0);
+
+        let reference = model.target_energy;
+        let (low, high) = model.energy_history.iter()
+            .fold((reference, reference), |(low, high), (_, e)| (low.min(*e), high.max(*e)));
+
+        let range = (high - low).max(1e-12);
+        let now = model.sim_time;
+
+        let to_chart = |time: f64, e: f64| {
+            let x = 1.0 - (now - time) / ENERGY_HISTORY;
+            let y = (e - low) / range;
+            pt2(chart.left() + x as f32 * chart.w(), chart.bottom() + y as f32 * chart.h())
+        };
+
+        draw.rect()
+            .xy(chart.xy())
+            .wh(chart.wh())
+            .no_fill()
+            .stroke(theme.mechanism)
+            .stroke_weight(1.0);
+
+        draw.line()
+            .start(to_chart(now - ENERGY_HISTORY, reference))
+            .end(to_chart(now, reference))
+            .weight(1.0)
+            .color(theme.mechanism);
+
+        draw.polyline()
+            .weight(dpi)
+            .points(model.energy_history.iter().map(|(time, e)| to_chart(*time, *e)))
+            .color(theme.trace_end);
+
+        let current = model.energy_history.back().map_or(reference, |(_, e)| *e);
+        let label = format!("energy {:.6} {energy}   range {:.1e} {energy}", current, high - low, energy = units.energy);
+
+        draw.text(&label)
+            .xy(chart.mid_top() + vec2(0.0, 12.0))
+            .wh(vec2(chart.w(), 20.0))
+            .color(theme.text);
+
+        // gravity's torque about the pivot above it, on an axis centred on zero so which way
+        // it's turning the pendulum reads straight off
+
+        let chart = chart.shift_y(chart.h() + 40.0);
+
+        let peak = model.torque_history.iter()
+            .fold(1e-12_f64, |peak, (_, torque)| peak.max(torque.abs()));
+
+        let to_chart = |time: f64, torque: f64| {
+            let x = 1.0 - (now - time) / ENERGY_HISTORY;
+            let y = 0.5 + 0.5 * torque / peak;
+            pt2(chart.left() + x as f32 * chart.w(), chart.bottom() + y as f32 * chart.h())
+        };
+
+        draw.rect()
+            .xy(chart.xy())
+            .wh(chart.wh())
+            .no_fill()
+            .stroke(theme.mechanism)
+            .stroke_weight(1.0);
+
+        draw.line()
+            .start(to_chart(now - ENERGY_HISTORY, 0.0))
+            .end(to_chart(now, 0.0))
+            .weight(1.0)
+            .color(theme.mechanism);
+
+        draw.polyline()
+            .weight(dpi)
+            .points(model.torque_history.iter().map(|(time, torque)| to_chart(*time, *torque)))
+            .color(theme.trace_end);
+
+        let current = model.torque_history.back().map_or(0.0, |(_, torque)| *torque);
+
+        draw.text(&format!("gravity torque {:+.2} {torque}   peak {:.2} {torque}", current, peak, torque = units.torque))
+            .xy(chart.mid_top() + vec2(0.0, 12.0))
+            .wh(vec2(chart.w(), 20.0))
+            .color(theme.text);
+    }
+
+    if let Some(drive) = &model.drive {
+        draw_drive(&draw, model, drive, window, dpi);
+    }
+
+    if model.show_monitor {
+        draw_monitor(&draw, model, window);
+    }
+
+    if let Some(rival) = &model.rival {
+        draw_separation(&draw, model, rival, window, dpi);
+    }
+
+    if model.show_dials {
+        draw_dials(&draw, model, window, dpi);
+    }
+
+    // what kind of motion it is, at the top of the window, once there's a full window of the
+    // estimate to go on
+
+    if let (Some(regime), Some(rate)) = (model.regime, model.lyapunov.exponent()) {
+
+        let badge = Rect::from_x_y_w_h(window.x(), window.top() - 22.0, 200.0, 24.0);
+        let color = theme.regime_colors[regime as usize];
+
+        draw.rect().xy(badge.xy()).wh(badge.wh()).color(color);
+
+        draw.text(&format!("{}   λ ≈ {:.2}/s", regime.name(), rate))
+            .xy(badge.xy())
+            .wh(badge.wh())
+            .color(WHITE);
+    }
+
+    // count down to the trace starting
+
+    if model.sim_time < model.trace_from {
+        draw.text(&format!("tracing in {:.1} {}", model.trace_from - model.sim_time, units.time))
+            .xy(window.mid_bottom() + vec2(0.0, 30.0))
+            .w_h(200.0, 20.0)
+            .color(theme.text);
+    }
+
+    // a reminder the simulation is held, just above the countdown
+
+    if model.paused {
+        draw.text("paused, space to resume")
+            .xy(window.mid_bottom() + vec2(0.0, 50.0))
+            .w_h(200.0, 20.0)
+            .color(theme.text);
+    }
+
+    // which integrator is stepping, above the slider's place in the bottom left
+
+    let label = match (&model.replay, &model.sim_thread) {
+        (Some(_), _) => "replaying".to_string(),
+        (_, Some(_)) => "integrator: rk4 (physics thread)".to_string(),
+        _ => format!("integrator: {}", model.sim.integrator.name()),
+    };
+
+    draw.text(&label)
+        .xy(window.bottom_left() + vec2(170.0, 80.0))
+        .w_h(300.0, 20.0)
+        .left_justify()
+        .color(theme.text);
+
+    // tolerance slider, along with the step size it's currently leading to
+
+    if let Integrator::Rkf45 = model.sim.integrator {
+
+        let slider = tolerance_slider(window);
+        let (min, max) = (TOLERANCE_RANGE.0.log10(), TOLERANCE_RANGE.1.log10());
+        let t = ((model.sim.adaptive.tolerance.log10() - min) / (max - min)) as f32;
+
+        draw.rect()
+            .xy(slider.xy())
+            .wh(vec2(slider.w(), 4.0))
+            .color(theme.mechanism);
+
+        draw.ellipse()
+            .x_y(slider.left() + t * slider.w(), slider.y())
+            .radius(slider.h() / 2.0)
+            .color(theme.trace_end);
+
+        let label = format!(
+            "tolerance {:.1e}   step {:.1e} s",
+            model.sim.adaptive.tolerance, model.sim.adaptive.step,
+        );
+
+        draw.text(&label)
+            .x_y(slider.left() + 150.0, slider.y() + 20.0)
+            .w_h(300.0, 20.0)
+            .left_justify()
+            .color(theme.text);
+
+        // a light beside it that flashes whenever a step is rejected and retried, with the
+        // running count, so it's visible where in the motion the solver struggles
+
+        let light = vec2(slider.right() + 20.0, slider.y());
+        let lit = model.rejection_flash / REJECTION_FLASH;
+
+        draw.ellipse()
+            .xy(light)
+            .radius(5.0)
+            .color(lerp_color(theme.mechanism, theme.rejection, lit));
+
+        draw.text(&format!("{} rejected", model.rejections))
+            .xy(light + vec2(65.0, 0.0))
+            .w_h(110.0, 20.0)
+            .left_justify()
+            .color(theme.text);
+
+        // how much of the error budget the last step used, as a bar under the slider filling
+        // towards a line at the tolerance. the controller aims a little under it, so a bar
+        // sitting near the line means the steps are as long as the tolerance allows
+
+        let budget = Rect::from_x_y_w_h(slider.x(), slider.y() - 16.0, slider.w(), 6.0);
+        let used = (model.sim.adaptive.last_error / model.sim.adaptive.tolerance) as f32;
+        let filled = (used / ERROR_BUDGET_SPAN).min(1.0) * budget.w();
+
+        draw.rect()
+            .xy(budget.xy())
+            .wh(budget.wh())
+            .no_fill()
+            .stroke(theme.mechanism)
+            .stroke_weight(1.0);
+
+        draw.rect()
+            .x_y(budget.left() + filled / 2.0, budget.y())
+            .w_h(filled, budget.h())
+            .color(theme.trace_end);
+
+        let line = budget.left() + budget.w() / ERROR_BUDGET_SPAN;
+
+        draw.line()
+            .start(pt2(line, budget.bottom() - 3.0))
+            .end(pt2(line, budget.top() + 3.0))
+            .weight(dpi)
+            .color(theme.rejection);
+
+        draw.text(&format!("error {:.0}% of tolerance", 100.0 * used))
+            .xy(budget.xy() + vec2(budget.w() / 2.0 + 85.0, 0.0))
+            .w_h(150.0, 20.0)
+            .left_justify()
+            .color(theme.text);
+    }
 
     // render
 
     draw.to_frame(app, &frame).unwrap();
 
+    if let Some(video) = &model.video {
+        if model.timelapse.as_ref().is_none_or(|timelapse| timelapse.due(model.sim_time)) {
+            video.capture(&frame);
+        }
+    }
+
 }
@@ -0,0 +1,73 @@
+use double_pendulum::physics::{Params, State};
+
+// the state and parameters are written here whenever the window closes, and read back by
+// --continue so a run can pick up across sessions where the last one stopped. it's one
+// `key=value` per line, like the summary printed on exit
+
+pub const PERSIST_FILE: &str = "double_pendulum.state";
+
+pub fn save(sim_time: f64, state: &State, params: &Params) -> Result<(), String> {
+
+    let contents = format!(
+        "sim_time={}\ntheta_1={}\ntheta_2={}\ndot_theta_1={}\ndot_theta_2={}\nl1={}\nl2={}\nm1={}\nm2={}\ngravity={}\n",
+        sim_time,
+        state.theta_1,
+        state.theta_2,
+        state.dot_theta_1,
+        state.dot_theta_2,
+        params.l1,
+        params.l2,
+        params.m1,
+        params.m2,
+        params.gravity,
+    );
+
+    std::fs::write(PERSIST_FILE, contents).map_err(|e| format!("couldn't write {}: {}", PERSIST_FILE, e))
+
+}
+
+// the simulated time, state and parameters saved by the last run. the applied torque isn't
+// saved, since --energy-setpoint works it out afresh every frame
+
+pub fn load() -> Result<(f64, State, Params), String> {
+
+    let contents = std::fs::read_to_string(PERSIST_FILE)
+        .map_err(|e| format!("couldn't read {}: {}", PERSIST_FILE, e))?;
+
+    let mut values = std::collections::HashMap::new();
+
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| format!("{}: expected key=value, got {:?}", PERSIST_FILE, line))?;
+        values.insert(key.trim(), value.trim());
+    }
+
+    let get = |key: &str| -> Result<f64, String> {
+        let value = values.get(key).ok_or_else(|| format!("{}: missing {}", PERSIST_FILE, key))?;
+        let value: f64 = value.parse().map_err(|_| format!("{}: {} isn't a number", PERSIST_FILE, key))?;
+
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(format!("{}: {} isn't finite", PERSIST_FILE, key))
+        }
+    };
+
+    let state = State {
+        theta_1: get("theta_1")?,
+        theta_2: get("theta_2")?,
+        dot_theta_1: get("dot_theta_1")?,
+        dot_theta_2: get("dot_theta_2")?,
+    };
+
+    let params = Params::new(
+        get("l1")? as f32,
+        get("l2")? as f32,
+        get("m1")? as f32,
+        get("m2")? as f32,
+        get("gravity")? as f32,
+    ).map_err(|e| format!("{}: {}", PERSIST_FILE, e))?;
+
+    Ok((get("sim_time")?, state, params))
+
+}
@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use double_pendulum::physics::{interpolate, State};
+
+// writes the raw state of a live run to a csv at a fixed interval of simulated time, for
+// importing into dynamical systems tools. the columns are
+//
+//     time,theta_1,dot_theta_1,theta_2,dot_theta_2
+//
+// so each angle sits next to its own angular velocity, in radians and radians per second.
+// frames don't land on the sample times, so each sample is interpolated between the states
+// either side of it
+
+pub struct PhaseExport {
+    csv: BufWriter<File>,
+    path: String,
+    interval: f64,
+    start_time: f64,
+    samples: u64, // written so far. the next is due at start_time + samples * interval
+    failed: bool, // stop trying after the first error rather than reporting every sample
+}
+
+impl PhaseExport {
+
+    pub fn create(path: &str, interval: f64, start_time: f64) -> Result<Self, String> {
+
+        let file = File::create(path).map_err(|e| format!("couldn't create {}: {}", path, e))?;
+        let mut csv = BufWriter::new(file);
+
+        writeln!(csv, "time,theta_1,dot_theta_1,theta_2,dot_theta_2")
+            .map_err(|e| format!("couldn't write {}: {}", path, e))?;
+
+        Ok(Self { csv, path: path.to_string(), interval, start_time, samples: 0, failed: false })
+
+    }
+
+    // write every sample that falls between the last frame (at previous_time) and this one
+
+    pub fn record(&mut self, previous_time: f64, previous: &State, time: f64, state: &State) {
+
+        loop {
+
+            let next = self.start_time + self.samples as f64 * self.interval;
+
+            if next > time || self.failed {
+                break;
+            }
+
+            let t = if time > previous_time { ((next - previous_time) / (time - previous_time)).max(0.0) } else { 1.0 };
+            let sample = interpolate(previous, state, t);
+
+            let written = writeln!(
+                self.csv,
+                "{},{},{},{},{}",
+                next, sample.theta_1, sample.dot_theta_1, sample.theta_2, sample.dot_theta_2,
+            );
+
+            if let Err(e) = written {
+                eprintln!("couldn't write {}, the phase export has stopped: {}", self.path, e);
+                self.failed = true;
+            }
+
+            self.samples += 1;
+        }
+
+    }
+
+    pub fn finish(mut self) {
+        if let Err(e) = self.csv.flush() {
+            eprintln!("couldn't finish writing {}: {}", self.path, e);
+        }
+    }
+
+}
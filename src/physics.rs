@@ -0,0 +1,1179 @@
+// all of the integration is done in f64: the motion is chaotic, so rounding errors grow
+// exponentially and f32 loses the trajectory much sooner. parameters are taken as f32 for
+// convenience and positions are handed back as f32 for drawing
+
+// store defining degrees of freedom as a state object
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct State {
+    pub theta_1: f64,
+    pub theta_2: f64,
+    pub dot_theta_1: f64,
+    pub dot_theta_2: f64,
+}
+
+impl State {
+
+    pub fn new(theta_1: f32, theta_2: f32, dot_theta_1: f32, dot_theta_2: f32) -> Self {
+        Self {
+            theta_1: theta_1 as f64,
+            theta_2: theta_2 as f64,
+            dot_theta_1: dot_theta_1 as f64,
+            dot_theta_2: dot_theta_2 as f64,
+        }
+    }
+
+}
+
+// implement scalar vector multiplication, division, and vector addition / subtraction on State
+// for rk4 and comparing runs
+
+impl std::ops::Mul<f64> for State {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        Self {
+            theta_1: self.theta_1 * rhs,
+            theta_2: self.theta_2 * rhs,
+            dot_theta_1: self.dot_theta_1 * rhs,
+            dot_theta_2: self.dot_theta_2 * rhs,
+        }
+    }
+}
+
+impl std::ops::Div<f64> for State {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self {
+        Self {
+            theta_1: self.theta_1 / rhs,
+            theta_2: self.theta_2 / rhs,
+            dot_theta_1: self.dot_theta_1 / rhs,
+            dot_theta_2: self.dot_theta_2 / rhs,
+        }
+    }
+}
+
+impl std::ops::Add<State> for State {
+    type Output = Self;
+
+    fn add(self, other: State) -> Self {
+        Self {
+            theta_1: self.theta_1 + other.theta_1,
+            theta_2: self.theta_2 + other.theta_2,
+            dot_theta_1: self.dot_theta_1 + other.dot_theta_1,
+            dot_theta_2: self.dot_theta_2 + other.dot_theta_2,
+        }
+    }
+}
+
+impl std::ops::Sub<State> for State {
+    type Output = Self;
+
+    fn sub(self, other: State) -> Self {
+        Self {
+            theta_1: self.theta_1 - other.theta_1,
+            theta_2: self.theta_2 - other.theta_2,
+            dot_theta_1: self.dot_theta_1 - other.dot_theta_1,
+            dot_theta_2: self.dot_theta_2 - other.dot_theta_2,
+        }
+    }
+}
+
+// the state a fraction t of the way from a to b, for drawing between two steps. each angle
+// goes the short way round, so a pair either side of a wrap doesn't swing back through a whole
+// turn, and the angular velocities are blended directly
+
+pub fn interpolate(a: &State, b: &State, t: f64) -> State {
+
+    let arc = |from: f64, to: f64| {
+        let difference = (to - from + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI;
+        from + t * difference
+    };
+
+    State {
+        theta_1: arc(a.theta_1, b.theta_1),
+        theta_2: arc(a.theta_2, b.theta_2),
+        dot_theta_1: a.dot_theta_1 + t * (b.dot_theta_1 - a.dot_theta_1),
+        dot_theta_2: a.dot_theta_2 + t * (b.dot_theta_2 - a.dot_theta_2),
+    }
+
+}
+
+// physical parameters of the pendulum
+
+#[derive(Copy, Clone, Debug)]
+pub struct Params {
+
+    // arm lengths
+
+    pub l1: f32,
+    pub l2: f32,
+
+    // bob masses
+
+    pub m1: f32,
+    pub m2: f32,
+
+    pub gravity: f32,
+
+    // how much of the gravity acts on each bob, 1 for all of it. anything else is artificial,
+    // but pulling only one bob down shows what each one's weight does to the motion
+
+    pub gravity_scale_1: f32,
+    pub gravity_scale_2: f32,
+
+    // dampening: f32, // not implemented but would slow divergence and add friction
+
+    // external torques driving each arm's angle, e.g. from energy_pump_torque. they're held
+    // constant through a step, so anything depending on the state should be recomputed between
+    // steps
+
+    pub torque: (f32, f32),
+
+    pub control: JointControl, // a controller on the first joint, JointControl::OFF for none
+}
+
+impl Params {
+
+    // parameters with no torques, checked with validate
+
+    pub fn new(l1: f32, l2: f32, m1: f32, m2: f32, gravity: f32) -> Result<Self, String> {
+
+        let params = Self { l1, l2, m1, m2, gravity, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+        params.validate()?;
+
+        Ok(params)
+
+    }
+
+    // derivatives divides by both masses and both lengths, so zero or negative values (or
+    // anything not finite) would quietly fill the state with NaNs instead of failing
+
+    pub fn validate(&self) -> Result<(), String> {
+
+        let positive = [("l1", self.l1), ("l2", self.l2), ("m1", self.m1), ("m2", self.m2)];
+
+        for (name, value) in positive {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(format!("{} must be a positive number, not {}", name, value));
+            }
+        }
+
+        let gravity = [("gravity", self.gravity), ("gravity_scale_1", self.gravity_scale_1), ("gravity_scale_2", self.gravity_scale_2)];
+
+        for (name, value) in gravity {
+            if !value.is_finite() {
+                return Err(format!("{} must be a finite number, not {}", name, value));
+            }
+        }
+
+        let control = [("target", self.control.target), ("kp", self.control.kp), ("kd", self.control.kd)];
+
+        for (name, value) in control {
+            if !value.is_finite() {
+                return Err(format!("the controller's {} must be a finite number, not {}", name, value));
+            }
+        }
+
+        Ok(())
+
+    }
+
+    // the gravitational acceleration pulling on bob 1 and on bob 2, with each one's scale
+
+    pub fn bob_gravity(&self) -> (f64, f64) {
+        let g = self.gravity as f64;
+        (g * self.gravity_scale_1 as f64, g * self.gravity_scale_2 as f64)
+    }
+
+}
+
+// a pd controller on the first joint, adding a torque of
+//
+//     -kp * (theta_1 - target) - kd * dot_theta_1
+//
+// to drive theta_1 towards target (in radians, not wrapped, so an arm that's wound round a turn
+// is unwound). unlike Params::torque it's worked out from the state inside derivatives, so it
+// follows the state through every step. with both gains zero it does nothing at all
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JointControl {
+    pub target: f64,
+    pub kp: f64, // N m per radian
+    pub kd: f64, // N m per rad/s
+}
+
+impl JointControl {
+
+    pub const OFF: Self = Self { target: 0.0, kp: 0.0, kd: 0.0 };
+
+    pub fn torque(&self, state: &State) -> f64 {
+        -self.kp * (state.theta_1 - self.target) - self.kd * state.dot_theta_1
+    }
+
+}
+
+// gravity as a function of simulated time, for runs where it changes on a schedule. update
+// sets the gravity from it before each frame's steps, and it's held through them like the
+// torques
+
+#[derive(Copy, Clone, Debug)]
+pub enum GravitySchedule {
+    Constant(f32),
+
+    // from `from` to `to` over the first `duration` seconds, then holding at `to`
+
+    Ramp { from: f32, to: f32, duration: f64 },
+
+    // swinging `amplitude` either side of `mean`, once every `period` seconds
+
+    Sine { mean: f32, amplitude: f32, period: f64 },
+}
+
+impl GravitySchedule {
+
+    pub fn at(&self, time: f64) -> f32 {
+        match *self {
+            GravitySchedule::Constant(gravity) => gravity,
+            GravitySchedule::Ramp { from, to, duration } => {
+                let t = if duration > 0.0 { (time / duration).clamp(0.0, 1.0) } else { 1.0 };
+                from + (to - from) * t as f32
+            }
+            GravitySchedule::Sine { mean, amplitude, period } => {
+                mean + amplitude * (std::f64::consts::TAU * time / period).sin() as f32
+            }
+        }
+    }
+
+}
+
+// for easier conversion between polar and cartesian for drawing
+
+pub struct Cartesian {
+
+    pub x: f32,
+    pub y: f32,
+
+}
+
+pub fn to_cartesian(r: f32, theta: f64) -> Cartesian {
+
+    // polar to cartesian, only dropping to f32 once the position is known
+
+    let x = r as f64 * theta.sin();
+    let y = - r as f64 * theta.cos();
+
+    Cartesian { x: x as f32, y: y as f32 }
+
+}
+
+// energies, taking the pivot as the zero of potential energy
+
+pub fn kinetic_energy(state: &State, params: &Params) -> f64 {
+
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let w1 = state.dot_theta_1;
+    let w2 = state.dot_theta_2;
+
+    // bob 2's velocity is its own swing plus that of bob 1, so the cross term couples them
+
+    0.5 * (m1 + m2) * l1 * l1 * w1 * w1
+        + 0.5 * m2 * l2 * l2 * w2 * w2
+        + m2 * l1 * l2 * w1 * w2 * (state.theta_1 - state.theta_2).cos()
+
+}
+
+pub fn potential_energy(state: &State, params: &Params) -> f64 {
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    - (m1 * g1 + m2 * g2) * l1 * state.theta_1.cos() - m2 * g2 * l2 * state.theta_2.cos()
+
+}
+
+// the net torque (in newton metres) gravity exerts about the pivot, positive in the direction
+// of increasing angle. it's the sum over the bobs of r x m g, and only their horizontal offsets
+// from the pivot matter: bob 1 at l1 sin(theta_1), bob 2 that plus l2 sin(theta_2). it's zero
+// hanging straight down or balanced straight up, and is how fast the potential energy falls
+// if the whole pendulum is turned rigidly about the pivot
+
+pub fn gravity_torque(state: &State, params: &Params) -> f64 {
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let x1 = l1 * state.theta_1.sin();
+    let x2 = x1 + l2 * state.theta_2.sin();
+
+    - (m1 * g1 * x1 + m2 * g2 * x2)
+
+}
+
+pub fn energy(state: &State, params: &Params) -> f64 {
+    kinetic_energy(state, params) + potential_energy(state, params)
+}
+
+// the total angular momentum about the pivot (in kg m^2/s), positive in the direction of
+// increasing angle. bob 1 contributes m1 l1^2 w1, and bob 2 the same as a rigid rod plus its
+// own swing, coupled like the kinetic energy. gravity turns the pendulum about the pivot, so
+// it's only conserved with gravity off on both bobs (and no torques applied)
+
+pub fn angular_momentum(state: &State, params: &Params) -> f64 {
+
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let w1 = state.dot_theta_1;
+    let w2 = state.dot_theta_2;
+
+    m1 * l1 * l1 * w1
+        + m2 * (l1 * l1 * w1 + l2 * l2 * w2 + l1 * l2 * (w1 + w2) * (state.theta_1 - state.theta_2).cos())
+
+}
+
+// rescale the velocities so the total energy is `target`, leaving the angles alone. kinetic
+// energy is quadratic in the velocities, so scaling them all by s scales it by s^2. if there's
+// no motion to scale, or the potential energy alone is already above target, nothing can be
+// done and the state is returned unchanged
+
+pub fn project_energy(state: &State, params: &Params, target: f64) -> State {
+
+    let kinetic = kinetic_energy(state, params);
+    let wanted = target - potential_energy(state, params);
+
+    if kinetic < 1e-12 || wanted < 0.0 {
+        return *state;
+    }
+
+    let s = (wanted / kinetic).sqrt();
+
+    State {
+        dot_theta_1: state.dot_theta_1 * s,
+        dot_theta_2: state.dot_theta_2 * s,
+        ..*state
+    }
+
+}
+
+// torques that drive the total energy towards setpoint: each arm is pushed along (or against)
+// its own motion in proportion to how far off the energy is, so the power put in is
+// -gain * error * (w1^2 + w2^2) and always closes the gap. a pendulum at rest can't be pumped
+
+pub fn energy_pump_torque(state: &State, params: &Params, setpoint: f64, gain: f64) -> (f32, f32) {
+
+    let error = energy(state, params) - setpoint;
+
+    (
+        (- gain * error * state.dot_theta_1) as f32,
+        (- gain * error * state.dot_theta_2) as f32,
+    )
+
+}
+
+// a torque on the first joint that swings it up towards upright by pumping energy into it. the
+// first arm is treated as a single pendulum with both masses at its end, whose energy would be
+// (m1 + m2) g l1 balanced at the top: it's pushed along its motion while it has less than that
+// and against it while it has more, in proportion to the difference (by gain, per second) but
+// never harder than max_torque. from rest it starts by pushing anticlockwise
+
+pub fn swing_up_torque(state: &State, params: &Params, gain: f64, max_torque: f64) -> f64 {
+
+    let mass = (params.m1 + params.m2) as f64;
+    let (g1, g2) = params.bob_gravity();
+    let weight = params.m1 as f64 * g1 + params.m2 as f64 * g2;
+    let (l1, w) = (params.l1 as f64, state.dot_theta_1);
+
+    let arm_energy = 0.5 * mass * l1 * l1 * w * w - weight * l1 * state.theta_1.cos();
+    let shortfall = weight * l1 - arm_energy;
+
+    (gain * shortfall * w.signum()).clamp(-max_torque, max_torque)
+
+}
+
+// velocities (x, y) of bob 1 and bob 2. bob 2 moves with bob 1 as well as swinging about
+// it, so its velocity is the sum of both arms' contributions
+
+pub fn bob_velocities(state: &State, params: &Params) -> ((f64, f64), (f64, f64)) {
+
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let v1 = (l1 * state.dot_theta_1 * state.theta_1.cos(), l1 * state.dot_theta_1 * state.theta_1.sin());
+    let v2 = (v1.0 + l2 * state.dot_theta_2 * state.theta_2.cos(), v1.1 + l2 * state.dot_theta_2 * state.theta_2.sin());
+
+    (v1, v2)
+
+}
+
+pub fn bob_speeds(state: &State, params: &Params) -> (f64, f64) {
+
+    let (v1, v2) = bob_velocities(state, params);
+
+    ((v1.0 * v1.0 + v1.1 * v1.1).sqrt(), (v2.0 * v2.0 + v2.1 * v2.1).sqrt())
+
+}
+
+// bob 1's and bob 2's accelerations (x, y). each arm swinging at angular velocity w with
+// angular acceleration a adds l * a across the arm and l * w^2 in towards the top of it
+
+fn bob_acceleration_vectors(state: &State, params: &Params) -> ((f64, f64), (f64, f64)) {
+
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let rates = derivatives(state, params);
+
+    let arm = |l: f64, theta: f64, w: f64, a: f64| {
+        let (sin, cos) = theta.sin_cos();
+        (l * (a * cos - w * w * sin), l * (a * sin + w * w * cos))
+    };
+
+    let a1 = arm(l1, state.theta_1, state.dot_theta_1, rates.dot_theta_1);
+    let a2 = arm(l2, state.theta_2, state.dot_theta_2, rates.dot_theta_2);
+
+    (a1, (a1.0 + a2.0, a1.1 + a2.1))
+
+}
+
+// sizes of bob 1's and bob 2's accelerations
+
+pub fn bob_accelerations(state: &State, params: &Params) -> (f64, f64) {
+
+    let (a1, a2) = bob_acceleration_vectors(state, params);
+
+    ((a1.0 * a1.0 + a1.1 * a1.1).sqrt(), (a2.0 * a2.0 + a2.1 * a2.1).sqrt())
+
+}
+
+// the force (in newtons) each rod pulls on the bob at its lower end with, positive under
+// tension and negative under compression. it's whatever of the bob's mass times acceleration
+// gravity doesn't already account for, taken along the rod: for bob 2 that's all down to rod 2,
+// while bob 1 is also pulled down its rod by rod 2. the torques are taken to push square to the
+// rods, so they don't come into it
+//
+// hanging still, rod 2 holds up m2 g and rod 1 both bobs, (m1 + m2) g
+
+pub fn rod_forces(state: &State, params: &Params) -> (f64, f64) {
+
+    let (m1, m2) = (params.m1 as f64, params.m2 as f64);
+    let (g1, g2) = params.bob_gravity();
+    let (a1, a2) = bob_acceleration_vectors(state, params);
+
+    // unit vectors up each rod, from its bob towards the top of it
+
+    let up_1 = (-state.theta_1.sin(), state.theta_1.cos());
+    let up_2 = (-state.theta_2.sin(), state.theta_2.cos());
+
+    let tension_2 = m2 * (a2.0 * up_2.0 + (a2.1 + g2) * up_2.1);
+
+    let pull = (m1 * a1.0 + tension_2 * up_2.0, m1 * (a1.1 + g1) + tension_2 * up_2.1);
+    let tension_1 = pull.0 * up_1.0 + pull.1 * up_1.1;
+
+    (tension_1, tension_2)
+
+}
+
+// each bob's velocity split into (radial, tangential) parts: along its own rod, pointing away
+// from the top of it, and square to the rod in the direction of increasing angle. a rigid rod
+// can't stretch, so bob 1's radial part is always zero; bob 2's isn't, since its rod is
+// carried along by bob 1
+
+pub fn velocity_components(state: &State, params: &Params) -> [(f64, f64); 2] {
+
+    let (v1, v2) = bob_velocities(state, params);
+
+    let split = |v: (f64, f64), theta: f64| {
+        let radial = (theta.sin(), -theta.cos());
+        let tangential = (theta.cos(), theta.sin());
+        (v.0 * radial.0 + v.1 * radial.1, v.0 * tangential.0 + v.1 * tangential.1)
+    };
+
+    [split(v1, state.theta_1), split(v2, state.theta_2)]
+
+}
+
+// the two natural angular frequencies (rad/s) of small swings about hanging straight down,
+// slowest first. linearised, the motion is M q'' + K q = 0 with the mass matrix
+// M = [[(m1 + m2) l1^2, m2 l1 l2], [m2 l1 l2, m2 l2^2]] at zero angle and the stiffness
+// K = diag((m1 g1 + m2 g2) l1, m2 g2 l2), with g1 and g2 the gravity on each bob, so the
+// squared frequencies are the roots of det(K - w^2 M) = 0, a quadratic in w^2. with gravity
+// zero or negative there's nothing to swing about, and they come back as zero or NaN
+
+pub fn normal_mode_frequencies(params: &Params) -> (f64, f64) {
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let (m11, m12, m22) = ((m1 + m2) * l1 * l1, m2 * l1 * l2, m2 * l2 * l2);
+    let (k11, k22) = ((m1 * g1 + m2 * g2) * l1, m2 * g2 * l2);
+
+    let a = m11 * m22 - m12 * m12;
+    let b = k11 * m22 + k22 * m11;
+    let c = k11 * k22;
+
+    let root = (b * b - 4.0 * a * c).sqrt();
+
+    (((b - root) / (2.0 * a)).sqrt(), ((b + root) / (2.0 * a)).sqrt())
+
+}
+
+// the same pendulum in natural units, for comparing with nondimensional analyses: lengths
+// measured in l1, masses in m1 and time in tau = sqrt(l1 / g), so l1 = m1 = g = 1 and only
+// l2/l1, m2/m1 (and the gravity scales) are left to shape the motion. derivatives then gives
+// the accelerations in those units unchanged. the angles stay as they are, the angular
+// velocities are multiplied by tau, and torques are measured in m1 g l1 (the controller's
+// gains with them). tau comes back too, to convert times. with gravity zero or pushing up
+// there's no tau to scale by
+
+pub fn natural_units(state: &State, params: &Params) -> Result<(State, Params, f64), String> {
+
+    if params.gravity.is_nan() || params.gravity <= 0.0 {
+        return Err(format!("natural units need gravity pulling down, not {}", params.gravity));
+    }
+
+    let tau = (params.l1 as f64 / params.gravity as f64).sqrt();
+    let torque_unit = params.m1 * params.gravity * params.l1;
+
+    let state = State { dot_theta_1: tau * state.dot_theta_1, dot_theta_2: tau * state.dot_theta_2, ..*state };
+
+    let control = JointControl {
+        target: params.control.target,
+        kp: params.control.kp / torque_unit as f64,
+        kd: params.control.kd / (torque_unit as f64 * tau),
+    };
+
+    let params = Params {
+        l1: 1.0,
+        l2: params.l2 / params.l1,
+        m1: 1.0,
+        m2: params.m2 / params.m1,
+        gravity: 1.0,
+        torque: (params.torque.0 / torque_unit, params.torque.1 / torque_unit),
+        control,
+        ..*params
+    };
+
+    Ok((state, params, tau))
+
+}
+
+// how many times an arm at angle theta has gone over the top (through pi, or any odd
+// multiple of it) from hanging down, counting anticlockwise as positive. the difference between
+// two consecutive values is how many flips happened in between
+
+pub fn turns(theta: f64) -> i64 {
+    ((theta + std::f64::consts::PI) / std::f64::consts::TAU).floor() as i64
+}
+
+// the denominator derivatives divides both accelerations by, 1 + (m2 / m1) sin^2(theta_1 -
+// theta_2). it comes from the determinant of the mass matrix, and can't drop below 1 while the
+// masses are positive, but it's smallest (and the equations most delicate) with the arms in
+// line, so this is exposed to keep an eye on it
+
+pub fn mass_denominator(state: &State, params: &Params) -> f64 {
+
+    let sin_dtheta = (state.theta_1 - state.theta_2).sin();
+
+    1.0 + (params.m2 as f64 / params.m1 as f64) * sin_dtheta * sin_dtheta
+
+}
+
+// below this the accelerations are close to dividing by zero. only parameters that have gone
+// bad (a negative mass) can get it there
+
+pub const DENOMINATOR_WARNING: f64 = 1e-3;
+
+pub fn derivatives(state: &State, params: &Params) -> State {
+
+    // cache reused values to reduce memory calls
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+
+    let dot_theta_1 = state.dot_theta_1;
+    let dot_theta_2 = state.dot_theta_2;
+
+    // cache calculated values to reduce computation. the masses and lengths are divided by,
+    // which is only safe for parameters that pass Params::validate
+
+    debug_assert!(params.validate().is_ok(), "{:?}", params.validate());
+
+    let mratio = m2 / m1;
+    let mrat_plus = mratio + 1.0;
+    let lratio = l2 / l1;
+    let dtheta = state.theta_1 - state.theta_2;
+
+    // gravity's share of each equation: the weight of both bobs turning the first arm (which
+    // is mrat_plus * g / l1 when they're pulled down equally), and bob 2's the second
+
+    let gamma_1 = (g1 + mratio * g2) / l1;
+    let gamma_2 = g2 / l1;
+
+    let sin_theta_1 = state.theta_1.sin();
+    let sin_theta_2 = state.theta_2.sin();
+
+    let sin_dtheta = dtheta.sin();
+    let cos_dtheta = dtheta.cos();
+
+    let denominator = 1.0 + (mratio * sin_dtheta * sin_dtheta);
+
+    // equations from uni edinburgh (page 30-31):
+    // https://www2.ph.ed.ac.uk/~dmarendu/MVP/DoublePendulumTutorial.pdf
+
+    let num_1 = (gamma_1 * sin_theta_1) + (mratio * lratio * dot_theta_2 * dot_theta_2 * sin_dtheta) + (mratio * cos_dtheta * (dot_theta_1 * dot_theta_1 * sin_dtheta - gamma_2 * sin_theta_2));
+
+    // pendulum 1 acceleration
+
+    let ddot_theta_1 = - num_1 / denominator;
+
+    let num_2 = mrat_plus * (dot_theta_1 * dot_theta_1 * sin_dtheta - gamma_2 * sin_theta_2) + cos_dtheta * (gamma_1 * sin_theta_1 + mratio * lratio * dot_theta_2 * dot_theta_2 * sin_dtheta);
+
+    // pendulum 2 acceleration
+
+    let ddot_theta_2 = num_2 / (lratio * denominator);
+
+    // external torques (and the controller's), through the inverse of the mass matrix
+    // [[(m1 + m2) l1^2, m2 l1 l2 cos], [m2 l1 l2 cos, m2 l2^2]]
+
+    let tau_1 = params.torque.0 as f64 + params.control.torque(state);
+    let tau_2 = params.torque.1 as f64;
+    let inertia = m1 + m2 * sin_dtheta * sin_dtheta;
+
+    let ddot_theta_1 = ddot_theta_1 + (l2 * tau_1 - l1 * cos_dtheta * tau_2) / (l1 * l1 * l2 * inertia);
+    let ddot_theta_2 = ddot_theta_2 + ((m1 + m2) * l1 * tau_2 - m2 * l2 * cos_dtheta * tau_1) / (m2 * l1 * l2 * l2 * inertia);
+
+    // return new values from initial state
+
+    State {
+        theta_1: dot_theta_1,
+        theta_2: dot_theta_2,
+        dot_theta_1: ddot_theta_1,
+        dot_theta_2: ddot_theta_2,
+    }
+
+}
+
+// the 4x4 jacobian of derivatives with respect to the state, row i and column j being the
+// rate of change of derivative i with respect to state variable j, both in the order
+// (theta_1, theta_2, dot_theta_1, dot_theta_2). it's worked out from the same equations by
+// the quotient rule, so see derivatives for those. the angles only appear through themselves
+// and their difference, so each "_d" value below is a derivative with respect to that
+// difference, which counts positively towards theta_1 and negatively towards theta_2. the
+// torques are held through a step, so they count as constants here, but the controller's
+// depends on theta_1 and dot_theta_1
+
+pub fn jacobian(state: &State, params: &Params) -> [[f64; 4]; 4] {
+
+    let (g1, g2) = params.bob_gravity();
+    let m1 = params.m1 as f64;
+    let m2 = params.m2 as f64;
+    let l1 = params.l1 as f64;
+    let l2 = params.l2 as f64;
+    let tau_1 = params.torque.0 as f64 + params.control.torque(state);
+    let tau_2 = params.torque.1 as f64;
+
+    let w1 = state.dot_theta_1;
+    let w2 = state.dot_theta_2;
+
+    let mratio = m2 / m1;
+    let mrat_plus = mratio + 1.0;
+    let lratio = l2 / l1;
+    let gamma_1 = (g1 + mratio * g2) / l1;
+    let gamma_2 = g2 / l1;
+
+    let (sin_1, cos_1) = state.theta_1.sin_cos();
+    let (sin_2, cos_2) = state.theta_2.sin_cos();
+    let (s, c) = (state.theta_1 - state.theta_2).sin_cos();
+
+    let denominator = 1.0 + mratio * s * s;
+    let denominator_d = 2.0 * mratio * s * c;
+
+    // numerators of the two accelerations, as in derivatives, and their derivatives
+
+    let num_1 = gamma_1 * sin_1 + mratio * lratio * w2 * w2 * s + mratio * c * (w1 * w1 * s - gamma_2 * sin_2);
+    let num_1_d = mratio * lratio * w2 * w2 * c + mratio * (-s * (w1 * w1 * s - gamma_2 * sin_2) + c * c * w1 * w1);
+
+    let dnum_1 = [
+        gamma_1 * cos_1 + num_1_d,
+        -num_1_d - mratio * c * gamma_2 * cos_2,
+        2.0 * mratio * c * s * w1,
+        2.0 * mratio * lratio * s * w2,
+    ];
+
+    let num_2 = mrat_plus * (w1 * w1 * s - gamma_2 * sin_2) + c * (gamma_1 * sin_1 + mratio * lratio * w2 * w2 * s);
+    let num_2_d = mrat_plus * w1 * w1 * c - s * (gamma_1 * sin_1 + mratio * lratio * w2 * w2 * s) + c * c * mratio * lratio * w2 * w2;
+
+    let dnum_2 = [
+        num_2_d + c * gamma_1 * cos_1,
+        -num_2_d - mrat_plus * gamma_2 * cos_2,
+        2.0 * mrat_plus * s * w1,
+        2.0 * c * mratio * lratio * s * w2,
+    ];
+
+    let ddenominator = [denominator_d, -denominator_d, 0.0, 0.0];
+
+    // the torque terms only depend on the angle difference
+
+    let inertia = m1 + m2 * s * s;
+    let inertia_d = 2.0 * m2 * s * c;
+
+    let torque_1_d = (l1 * s * tau_2 * inertia - (l2 * tau_1 - l1 * c * tau_2) * inertia_d) / (l1 * l1 * l2 * inertia * inertia);
+    let torque_2_d = (m2 * l2 * s * tau_1 * inertia - ((m1 + m2) * l1 * tau_2 - m2 * l2 * c * tau_1) * inertia_d) / (m2 * l1 * l2 * l2 * inertia * inertia);
+
+    let mut dtorque_1 = [torque_1_d, -torque_1_d, 0.0, 0.0];
+    let mut dtorque_2 = [torque_2_d, -torque_2_d, 0.0, 0.0];
+
+    // and the controller's torque on the first joint changes with theta_1 and dot_theta_1
+
+    let dtau_1 = [-params.control.kp, 0.0, -params.control.kd, 0.0];
+
+    for j in 0..4 {
+        dtorque_1[j] += dtau_1[j] / (l1 * l1 * inertia);
+        dtorque_2[j] -= c * dtau_1[j] / (l1 * l2 * inertia);
+    }
+
+    // ddot_theta_1 = -num_1 / denominator and ddot_theta_2 = num_2 / (lratio * denominator),
+    // plus the torques
+
+    let mut jacobian = [[0.0; 4]; 4];
+
+    jacobian[0][2] = 1.0;
+    jacobian[1][3] = 1.0;
+
+    for j in 0..4 {
+        jacobian[2][j] = (-dnum_1[j] * denominator + num_1 * ddenominator[j]) / (denominator * denominator) + dtorque_1[j];
+        jacobian[3][j] = (dnum_2[j] * denominator - num_2 * ddenominator[j]) / (lratio * denominator * denominator) + dtorque_2[j];
+    }
+
+    jacobian
+
+}
+
+// the accelerations worked out numerically from the lagrangian L = T - V instead, as a check
+// on the equations transcribed into derivatives. lagrange's equations
+//
+//     d/dt dL/dw - dL/dtheta = torque
+//
+// expand to H * ddot_theta = dL/dtheta - C * w + torque, where H holds the second derivatives
+// of L with respect to the velocities w and C the mixed ones; all of those are estimated by
+// central differences and the 2x2 system solved directly. only good to around 1e-7, but a
+// wrong sign or coefficient in derivatives would be far off
+
+pub fn lagrangian_accelerations(state: &State, params: &Params) -> (f64, f64) {
+
+    let h = 3e-4;
+    let lagrangian = |s: &State| kinetic_energy(s, params) - potential_energy(s, params);
+
+    // the state with one coordinate (theta_1, theta_2, dot_theta_1, dot_theta_2) offset
+
+    let shift = |s: State, i: usize, d: f64| {
+        let mut s = s;
+        match i {
+            0 => s.theta_1 += d,
+            1 => s.theta_2 += d,
+            2 => s.dot_theta_1 += d,
+            _ => s.dot_theta_2 += d,
+        }
+        s
+    };
+
+    let first = |i: usize| (lagrangian(&shift(*state, i, h)) - lagrangian(&shift(*state, i, -h))) / (2.0 * h);
+
+    let second = |i: usize, j: usize| {
+        let at = |di: f64, dj: f64| lagrangian(&shift(shift(*state, i, di), j, dj));
+        (at(h, h) - at(h, -h) - at(-h, h) + at(-h, -h)) / (4.0 * h * h)
+    };
+
+    let w = [state.dot_theta_1, state.dot_theta_2];
+    let torque = [params.torque.0 as f64 + params.control.torque(state), params.torque.1 as f64];
+
+    let rhs: Vec<f64> = (0..2)
+        .map(|i| first(i) - second(2 + i, 0) * w[0] - second(2 + i, 1) * w[1] + torque[i])
+        .collect();
+
+    let (a, b, c, d) = (second(2, 2), second(2, 3), second(3, 2), second(3, 3));
+    let det = a * d - b * c;
+
+    ((d * rhs[0] - b * rhs[1]) / det, (a * rhs[1] - c * rhs[0]) / det)
+
+}
+
+pub fn rk4(state: &State, params: &Params, dt: f32) -> State {
+
+    // rk4 implementation
+    // essentially a weighted average of iterative euler's method steps
+
+    let dt = dt as f64;
+
+    // k1 is equivalent to euler's method (poor performance)
+
+    let k1 = derivatives(state, params) * dt;
+
+    // we then take the new state estimate that k1 gives and make another state estimate
+    // for what the derivatives would look like after half of the time step
+
+    let k2_state = *state + k1 * 0.5;
+    let k2 = derivatives(&k2_state, params) * dt;
+
+    // and then use the k2 estimate for k3... etc
+
+    let k3_state = *state + k2 * 0.5;
+    let k3 = derivatives(&k3_state, params) * dt;
+
+    let k4_state = *state + k3;
+    let k4 = derivatives(&k4_state, params) * dt;
+
+    // return new state from weighted averages
+
+    *state + (k1 + k2 * 2.0 + k3 * 2.0 + k4) / 6.0
+}
+
+// one fixed step of an integrator like rk4, of the given dt
+
+pub type Step = fn(&State, &Params, f32) -> State;
+
+// cover dt with as few equal steps as keep each one no longer than max_step. a single step
+// much longer than the motion's own timescale can throw the state off to infinity or NaN, so
+// this keeps a sped up simulation stable (and as accurate as a normal speed one)
+
+pub fn substep(step: Step, state: &State, params: &Params, dt: f32, max_step: f32) -> State {
+
+    let count = (dt / max_step).ceil().max(1.0) as usize;
+    let h = dt / count as f32;
+
+    let mut state = *state;
+
+    for _ in 0..count {
+        state = step(&state, params, h);
+    }
+
+    state
+
+}
+
+// the simpler and the structure-preserving integrators, for comparing against rk4. all of
+// them take one step of dt like rk4 does
+
+// forward euler: step along the derivatives at the start. only first order, and it pumps
+// energy in steadily, so the swings grow
+
+pub fn euler(state: &State, params: &Params, dt: f32) -> State {
+    *state + derivatives(state, params) * dt as f64
+}
+
+// the midpoint method (rk2): an euler half step to find the derivatives halfway across, then
+// the whole step using those. second order
+
+pub fn rk2(state: &State, params: &Params, dt: f32) -> State {
+
+    let dt = dt as f64;
+    let half = *state + derivatives(state, params) * (0.5 * dt);
+
+    *state + derivatives(&half, params) * dt
+
+}
+
+// leapfrog, as kick-drift-kick: half a step's acceleration on the angular velocities, a whole
+// step of movement at those, then the other half kick from where that lands. the accelerations
+// depend on the angular velocities too, and the second kick should use the ones at the end of
+// the step, which aren't known yet, so they're predicted with a trial kick first (using the
+// half-kicked ones instead would drop it to first order). second order, and its energy error
+// stays bounded far longer than euler's
+
+pub fn leapfrog(state: &State, params: &Params, dt: f32) -> State {
+
+    let dt = dt as f64;
+    let kick = derivatives(state, params);
+
+    let mut next = *state;
+    next.dot_theta_1 += 0.5 * dt * kick.dot_theta_1;
+    next.dot_theta_2 += 0.5 * dt * kick.dot_theta_2;
+    next.theta_1 += dt * next.dot_theta_1;
+    next.theta_2 += dt * next.dot_theta_2;
+
+    let trial = derivatives(&next, params);
+    let predicted = State {
+        dot_theta_1: next.dot_theta_1 + 0.5 * dt * trial.dot_theta_1,
+        dot_theta_2: next.dot_theta_2 + 0.5 * dt * trial.dot_theta_2,
+        ..next
+    };
+
+    let kick = derivatives(&predicted, params);
+    next.dot_theta_1 += 0.5 * dt * kick.dot_theta_1;
+    next.dot_theta_2 += 0.5 * dt * kick.dot_theta_2;
+
+    next
+
+}
+
+// the implicit midpoint rule: the step is along the derivatives at the midpoint of the step
+// itself, state + k / 2 where k = dt * derivatives(state + k / 2). that's solved for k by
+// newton's method with the analytic jacobian. symplectic, so like leapfrog it keeps the energy
+// from drifting away
+
+pub fn implicit_midpoint(state: &State, params: &Params, dt: f32) -> State {
+
+    let dt = dt as f64;
+
+    // start from the explicit euler guess, which is usually close enough that a few newton
+    // steps take it to round-off
+
+    let mut k = derivatives(state, params) * dt;
+
+    for _ in 0..8 {
+
+        let midpoint = *state + k * 0.5;
+        let residual = k - derivatives(&midpoint, params) * dt;
+        let j = jacobian(&midpoint, params);
+
+        // d(residual)/dk = I - (dt / 2) J
+
+        let mut a = [[0.0; 4]; 4];
+
+        for (r, row) in a.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = if r == c { 1.0 } else { 0.0 } - 0.5 * dt * j[r][c];
+            }
+        }
+
+        let residual = [residual.theta_1, residual.theta_2, residual.dot_theta_1, residual.dot_theta_2];
+        let d = solve(a, residual);
+
+        k = k - State { theta_1: d[0], theta_2: d[1], dot_theta_1: d[2], dot_theta_2: d[3] };
+
+        if d.iter().all(|d| d.abs() < 1e-14) {
+            break;
+        }
+    }
+
+    *state + k
+
+}
+
+// solve a x = b for a 4x4 system, by gaussian elimination with partial pivoting
+
+fn solve(mut a: [[f64; 4]; 4], mut b: [f64; 4]) -> [f64; 4] {
+
+    for col in 0..4 {
+
+        let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs())).unwrap();
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let top = a[col];
+
+        for row in col + 1..4 {
+            let factor = a[row][col] / top[col];
+
+            for (value, above) in a[row][col..].iter_mut().zip(&top[col..]) {
+                *value -= factor * above;
+            }
+
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = [0.0; 4];
+
+    for row in (0..4).rev() {
+        let known: f64 = (row + 1..4).map(|c| a[row][c] * x[c]).sum();
+        x[row] = (b[row] - known) / a[row][row];
+    }
+
+    x
+
+}
+
+// a single runge-kutta-fehlberg step of size h. this evaluates six derivatives which combine
+// into both a 4th and a 5th order estimate of the next state; the 5th order one is returned
+// along with the size of the difference between them, which estimates the local error
+
+pub fn rkf45(state: &State, params: &Params, h: f64) -> (State, f64) {
+
+    let k1 = derivatives(state, params) * h;
+    let k2 = derivatives(&(*state + k1 * (1.0 / 4.0)), params) * h;
+    let k3 = derivatives(&(*state + k1 * (3.0 / 32.0) + k2 * (9.0 / 32.0)), params) * h;
+    let k4 = derivatives(&(*state + k1 * (1932.0 / 2197.0) + k2 * (-7200.0 / 2197.0) + k3 * (7296.0 / 2197.0)), params) * h;
+    let k5 = derivatives(&(*state + k1 * (439.0 / 216.0) + k2 * -8.0 + k3 * (3680.0 / 513.0) + k4 * (-845.0 / 4104.0)), params) * h;
+    let k6 = derivatives(&(*state + k1 * (-8.0 / 27.0) + k2 * 2.0 + k3 * (-3544.0 / 2565.0) + k4 * (1859.0 / 4104.0) + k5 * (-11.0 / 40.0)), params) * h;
+
+    let next = *state + k1 * (16.0 / 135.0) + k3 * (6656.0 / 12825.0) + k4 * (28561.0 / 56430.0) + k5 * (-9.0 / 50.0) + k6 * (2.0 / 55.0);
+
+    // difference between the 5th and 4th order weights, so this is (5th - 4th order estimate)
+
+    let error = k1 * (1.0 / 360.0) + k3 * (-128.0 / 4275.0) + k4 * (-2197.0 / 75240.0) + k5 * (1.0 / 50.0) + k6 * (2.0 / 55.0);
+
+    let size = error.theta_1.abs()
+        .max(error.theta_2.abs())
+        .max(error.dot_theta_1.abs())
+        .max(error.dot_theta_2.abs());
+
+    (next, size)
+
+}
+
+// running counts of how the adaptive integrator has been stepping
+
+#[derive(Copy, Clone, Debug)]
+pub struct StepStats {
+    pub accepted: u32,
+    pub rejected: u32,
+    pub total_step: f64, // sum of accepted step sizes, for the average
+    pub min_step: f64,
+}
+
+impl StepStats {
+
+    pub fn new() -> Self {
+        Self { accepted: 0, rejected: 0, total_step: 0.0, min_step: f64::INFINITY }
+    }
+
+    pub fn mean_step(&self) -> f64 {
+        self.total_step / self.accepted.max(1) as f64
+    }
+
+}
+
+impl Default for StepStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// adaptive rkf45 integration: each frame's dt is covered with as many internal steps as it
+// takes to keep the estimated local error under the tolerance, shrinking the step where the
+// motion is violent (e.g. through a flip) and growing it again where it's calm
+
+pub struct Adaptive {
+    pub tolerance: f64,
+    pub step: f64, // step size to try next, carried over between frames
+    pub stats: StepStats,
+    pub last_error: f64, // rkf45's error estimate for the last step taken, to set against the tolerance
+}
+
+impl Adaptive {
+
+    // the step is never allowed outside this range, so it can't stall or skip whole frames
+
+    const MIN_STEP: f64 = 1e-7;
+    const MAX_STEP: f64 = 0.05;
+
+    pub fn new(tolerance: f64) -> Self {
+        Self { tolerance, step: 1e-3, stats: StepStats::new(), last_error: 0.0 }
+    }
+
+    pub fn advance(&mut self, state: &State, params: &Params, dt: f32) -> State {
+
+        let mut state = *state;
+        let mut remaining = dt as f64;
+
+        while remaining > 0.0 {
+
+            let h = self.step.min(remaining);
+            let (next, error) = rkf45(&state, params, h);
+
+            // a state the step overflowed from gives no error to go on, so try again as small as
+            // the controller goes. once that's as small as a step gets there's nothing left to
+            // try, and the rest of the frame is left where it got to rather than filled with NaNs
+
+            if !error.is_finite() {
+                self.stats.rejected += 1;
+
+                if h <= Self::MIN_STEP {
+                    break;
+                }
+
+                self.step = (0.2 * h).max(Self::MIN_STEP);
+                continue;
+            }
+
+            if error <= self.tolerance || h <= Self::MIN_STEP {
+                state = next;
+                remaining -= h;
+                self.last_error = error;
+
+                self.stats.accepted += 1;
+                self.stats.total_step += h;
+                self.stats.min_step = self.stats.min_step.min(h);
+            } else {
+                self.stats.rejected += 1;
+            }
+
+            // standard step size controller: the error scales with h^5, so aim for the
+            // tolerance with a safety margin, and don't change too much in one go
+
+            let scale = if error > 0.0 { 0.9 * (self.tolerance / error).powf(0.2) } else { 5.0 };
+            self.step = (h * scale.clamp(0.2, 5.0)).clamp(Self::MIN_STEP, Self::MAX_STEP);
+        }
+
+        state
+
+    }
+
+}
+
+// fixed steps of dt to keep up with a clock that ticks at any rate, as the physics thread
+// does. the steps due are counted from the total elapsed time rather than by adding up each
+// tick's length, so however the time arrives (many short frames or a few long ones) the same
+// number of steps has been taken by the same moment, and rounding can't build up between them
+
+pub struct FixedClock {
+    pub dt: f64,
+    steps_taken: u64,
+}
+
+impl FixedClock {
+
+    pub fn new(dt: f64) -> Self {
+        Self { dt, steps_taken: 0 }
+    }
+
+    // how many steps have come due by `elapsed` seconds since the start that haven't been
+    // taken yet, which are then taken to have been
+
+    pub fn due(&mut self, elapsed: f64) -> u64 {
+
+        // a frame that lands on a step to within rounding has reached it, so that adding up
+        // frames which divide the step evenly (1/60 s against 1/240 s) takes the same number
+        // each frame
+
+        let due = (elapsed / self.dt + 1e-9) as u64;
+        let steps = due.saturating_sub(self.steps_taken);
+
+        self.steps_taken = self.steps_taken.max(due);
+
+        steps
+
+    }
+
+    // the time after the start the last step taken is due at, and the one after it
+
+    pub fn stepped_to(&self) -> f64 {
+        self.steps_taken as f64 * self.dt
+    }
+
+    pub fn next_due(&self) -> f64 {
+        (self.steps_taken + 1) as f64 * self.dt
+    }
+
+}
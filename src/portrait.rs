@@ -0,0 +1,191 @@
+use std::f64::consts::{PI, TAU};
+
+use nannou::color::named::{CADETBLUE, DIMGRAY, MEDIUMORCHID, WHITESMOKE};
+use nannou::image::{Rgb, RgbImage};
+use nannou::text::{rt, Font, Scale};
+
+use double_pendulum::physics::State;
+
+use crate::gallery::{draw_line, lerp};
+
+// the run's whole trajectory through phase space, saved as a standalone png of one state
+// variable against another with labelled axes, for figures. it's written when the window closes
+// (with --portrait) or whenever p is pressed, and is separate from anything drawn live. the
+// states are kept in full, so any pair can be plotted from them
+
+pub struct Portrait {
+    pub axes: (Variable, Variable), // along x, then up y
+    states: Vec<Option<State>>, // None marks where the pendulum was moved by hand
+}
+
+#[derive(Clone, Copy)]
+pub enum Variable {
+    Theta1,
+    DotTheta1,
+    Theta2,
+    DotTheta2,
+}
+
+impl Variable {
+
+    // named as in the --export-phase columns
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "theta_1" => Some(Variable::Theta1),
+            "dot_theta_1" => Some(Variable::DotTheta1),
+            "theta_2" => Some(Variable::Theta2),
+            "dot_theta_2" => Some(Variable::DotTheta2),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Variable::Theta1 => "theta_1 (rad)",
+            Variable::DotTheta1 => "dot_theta_1 (rad/s)",
+            Variable::Theta2 => "theta_2 (rad)",
+            Variable::DotTheta2 => "dot_theta_2 (rad/s)",
+        }
+    }
+
+    // angles are wrapped into [-pi, pi), so each flip over the top doesn't stretch the plot
+
+    fn value(self, state: &State) -> f64 {
+        match self {
+            Variable::Theta1 => (state.theta_1 + PI).rem_euclid(TAU) - PI,
+            Variable::DotTheta1 => state.dot_theta_1,
+            Variable::Theta2 => (state.theta_2 + PI).rem_euclid(TAU) - PI,
+            Variable::DotTheta2 => state.dot_theta_2,
+        }
+    }
+
+    fn is_angle(self) -> bool {
+        matches!(self, Variable::Theta1 | Variable::Theta2)
+    }
+
+}
+
+const SIZE: u32 = 800; // pixels along each side
+const MARGIN: f32 = 70.0; // between the plot and the edge of the image, for the labels
+const TEXT_SIZE: f32 = 16.0;
+
+impl Portrait {
+
+    pub fn new(axes: (Variable, Variable)) -> Self {
+        Self { axes, states: Vec::new() }
+    }
+
+    pub fn record(&mut self, state: &State) {
+        self.states.push(Some(*state));
+    }
+
+    pub fn break_line(&mut self) {
+        self.states.push(None);
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+
+        let (x_axis, y_axis) = self.axes;
+        let points: Vec<Option<(f64, f64)>> = self.states.iter()
+            .map(|state| state.map(|state| (x_axis.value(&state), y_axis.value(&state))))
+            .collect();
+
+        // fit the axes to the points, with a little room around them
+
+        let range = |axis: fn(&(f64, f64)) -> f64| {
+            let (min, max) = points.iter().flatten().map(axis)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)));
+
+            if min > max {
+                (-1.0, 1.0)
+            } else {
+                let pad = 0.05 * (max - min).max(1e-6);
+                (min - pad, max + pad)
+            }
+        };
+
+        let (x_min, x_max) = range(|point| point.0);
+        let (y_min, y_max) = range(|point| point.1);
+
+        let plot = SIZE as f32 - 2.0 * MARGIN;
+        let to_pixel = |(x, y): (f64, f64)| (
+            MARGIN + plot * ((x - x_min) / (x_max - x_min)) as f32,
+            MARGIN + plot * ((y_max - y) / (y_max - y_min)) as f32,
+        );
+
+        let background = Rgb([WHITESMOKE.red, WHITESMOKE.green, WHITESMOKE.blue]);
+        let axis_colour = Rgb([DIMGRAY.red, DIMGRAY.green, DIMGRAY.blue]);
+        let mut image = RgbImage::from_pixel(SIZE, SIZE, background);
+
+        // same gradient as the gallery, oldest to newest. an angle wrapping round is a jump
+        // across the plot, so the line is broken there too
+
+        let last = points.len().saturating_sub(1).max(1) as f32;
+
+        for (i, pair) in points.windows(2).enumerate() {
+            if let [Some(from), Some(to)] = pair {
+
+                let wrapped = (x_axis.is_angle() && (to.0 - from.0).abs() > PI)
+                    || (y_axis.is_angle() && (to.1 - from.1).abs() > PI);
+
+                if !wrapped {
+                    draw_line(&mut image, to_pixel(*from), to_pixel(*to), lerp(CADETBLUE, MEDIUMORCHID, i as f32 / last));
+                }
+            }
+        }
+
+        // the frame, with each axis's range at its ends and its name along the middle
+
+        let (left, right, top, bottom) = (MARGIN, SIZE as f32 - MARGIN, MARGIN, SIZE as f32 - MARGIN);
+
+        for (from, to) in [((left, top), (right, top)), ((right, top), (right, bottom)), ((right, bottom), (left, bottom)), ((left, bottom), (left, top))] {
+            draw_line(&mut image, from, to, axis_colour);
+        }
+
+        let font = nannou::text::font::default_notosans();
+        let below = bottom + 1.5 * TEXT_SIZE;
+
+        draw_text(&mut image, &font, &format!("{:.2}", x_min), left, below, axis_colour);
+        draw_text(&mut image, &font, &format!("{:.2}", x_max), right - 3.0 * TEXT_SIZE, below, axis_colour);
+        draw_text(&mut image, &font, x_axis.label(), SIZE as f32 / 2.0 - 5.0 * TEXT_SIZE, below + 1.5 * TEXT_SIZE, axis_colour);
+
+        draw_text(&mut image, &font, &format!("{:.2}", y_max), 4.0, top + TEXT_SIZE / 2.0, axis_colour);
+        draw_text(&mut image, &font, &format!("{:.2}", y_min), 4.0, bottom, axis_colour);
+        draw_text(&mut image, &font, y_axis.label(), left, top - TEXT_SIZE, axis_colour);
+
+        image.save(path).map_err(|e| format!("couldn't write {}: {}", path, e))
+
+    }
+
+}
+
+// rasterises the text with its baseline starting at (x, y), blending each pixel by how much of
+// it the glyph covers
+
+fn draw_text(image: &mut RgbImage, font: &Font, text: &str, x: f32, y: f32, colour: Rgb<u8>) {
+
+    for glyph in font.layout(text, Scale::uniform(TEXT_SIZE), rt::point(x, y)) {
+
+        let Some(bounds) = glyph.pixel_bounding_box() else {
+            continue; // spaces
+        };
+
+        glyph.draw(|gx, gy, coverage| {
+            let (px, py) = (bounds.min.x + gx as i32, bounds.min.y + gy as i32);
+
+            if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+
+                for (channel, target) in pixel.0.iter_mut().zip(colour.0) {
+                    *channel = (*channel as f32 + (target as f32 - *channel as f32) * coverage).round() as u8;
+                }
+            }
+        });
+    }
+
+}
@@ -0,0 +1,73 @@
+use crate::physics::{JointControl, Params, State};
+
+// a handful of named starting setups showing off the different kinds of motion
+
+pub struct Preset {
+    pub name: &'static str,
+    pub state: State,
+    pub params: Params,
+}
+
+// the parameters main.rs starts with: 1m arms, 1kg bobs, and g = 10
+
+pub const DEFAULT_PARAMS: Params = Params { l1: 1.0, l2: 1.0, m1: 1.0, m2: 1.0, gravity: 10.0, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+
+// the preset with the given name, or an error listing the ones there are
+
+pub fn find(name: &str) -> Result<&'static Preset, String> {
+    PRESETS.iter().find(|preset| preset.name == name).ok_or_else(|| {
+        let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+        format!("no preset called {}, try one of {}", name, names.join(", "))
+    })
+}
+
+const fn at_rest(theta_1: f64, theta_2: f64) -> State {
+    State { theta_1, theta_2, dot_theta_1: 0.0, dot_theta_2: 0.0 }
+}
+
+pub const PRESETS: &[Preset] = &[
+
+    // the starting position in main.rs
+
+    Preset { name: "default", state: at_rest(0.0, 2.0), params: DEFAULT_PARAMS },
+
+    // both arms well above horizontal, fully chaotic
+
+    Preset { name: "raised", state: at_rest(2.0, 2.0), params: DEFAULT_PARAMS },
+
+    // small swings close to the two normal modes (for equal masses and lengths the arms are in
+    // the ratio 1 : sqrt(2), in phase for the slow mode and in antiphase for the fast one)
+
+    Preset { name: "slow-mode", state: at_rest(0.2, 0.2 * std::f64::consts::SQRT_2), params: DEFAULT_PARAMS },
+    Preset { name: "fast-mode", state: at_rest(0.2, -0.2 * std::f64::consts::SQRT_2), params: DEFAULT_PARAMS },
+
+    // a small mismatch from either mode, which traces out a quasiperiodic figure
+
+    Preset { name: "quasiperiodic", state: at_rest(0.9, -0.4), params: DEFAULT_PARAMS },
+
+    // hanging straight down but with the bottom bob kicked hard enough to loop over
+
+    Preset {
+        name: "kick",
+        state: State { theta_1: 0.0, theta_2: 0.0, dot_theta_1: 0.0, dot_theta_2: 9.0 },
+        params: DEFAULT_PARAMS,
+    },
+
+    // a light bob on a long arm hanging from a heavy one, which whips around
+
+    Preset {
+        name: "whip",
+        state: at_rest(1.5, 0.0),
+        params: Params { l1: 0.7, l2: 1.3, m1: 3.0, m2: 0.5, gravity: 10.0, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF },
+    },
+
+    // balanced upside down, with each arm a hair to either side of straight up. the
+    // equilibrium is unstable, so the offsets grow exponentially and it topples within a few
+    // seconds
+
+    Preset {
+        name: "inverted",
+        state: at_rest(std::f64::consts::PI + 1e-4, std::f64::consts::PI - 1e-4),
+        params: DEFAULT_PARAMS,
+    },
+];
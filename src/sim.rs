@@ -0,0 +1,231 @@
+use crate::physics::{energy, euler, implicit_midpoint, interpolate, leapfrog, rk2, rk4, substep, Adaptive, FixedClock, Params, State, Step};
+
+// every integrator but rkf45 takes fixed steps, as many per frame as keep them under max_step
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Integrator {
+    Euler,
+    Rk2,
+    Rk4,
+    Leapfrog,
+    ImplicitMidpoint,
+    Rkf45, // adaptive steps to keep the error under a tolerance
+}
+
+impl Integrator {
+
+    pub const ALL: [Integrator; 6] = [Integrator::Euler, Integrator::Rk2, Integrator::Rk4, Integrator::Leapfrog, Integrator::ImplicitMidpoint, Integrator::Rkf45];
+
+    pub fn next(self) -> Self {
+        match self {
+            Integrator::Euler => Integrator::Rk2,
+            Integrator::Rk2 => Integrator::Rk4,
+            Integrator::Rk4 => Integrator::Leapfrog,
+            Integrator::Leapfrog => Integrator::ImplicitMidpoint,
+            Integrator::ImplicitMidpoint => Integrator::Rkf45,
+            Integrator::Rkf45 => Integrator::Euler,
+        }
+    }
+
+    // the fixed step, or none for rkf45, which picks its own
+
+    pub fn step(self) -> Option<Step> {
+        match self {
+            Integrator::Euler => Some(euler),
+            Integrator::Rk2 => Some(rk2),
+            Integrator::Rk4 => Some(rk4),
+            Integrator::Leapfrog => Some(leapfrog),
+            Integrator::ImplicitMidpoint => Some(implicit_midpoint),
+            Integrator::Rkf45 => None,
+        }
+    }
+
+    // by name, with dashes for spaces, as the command line takes it
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+
+        Self::ALL.into_iter().find(|integrator| integrator.name().replace(' ', "-") == name).ok_or_else(|| {
+            let names: Vec<String> = Self::ALL.iter().map(|integrator| integrator.name().replace(' ', "-")).collect();
+            format!("no integrator called {}, try one of {}", name, names.join(", "))
+        })
+
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::Euler => "euler",
+            Integrator::Rk2 => "rk2",
+            Integrator::Rk4 => "rk4",
+            Integrator::Leapfrog => "leapfrog",
+            Integrator::ImplicitMidpoint => "implicit midpoint",
+            Integrator::Rkf45 => "rkf45",
+        }
+    }
+
+}
+
+// a pendulum on its own, for driving the physics from anywhere: the app's main run, tests,
+// benchmarks, or another front end. it keeps the state, the parameters it's stepped with and
+// the simulated time together, so stepping is one call
+//
+//     let mut sim = Simulation::new(State::new(2.0, 2.5, 0.0, 0.0), Params::new(1.0, 1.0, 1.0, 1.0, 9.81)?)?;
+//     sim.step(1.0 / 60.0);
+//
+// it takes whichever integrator is set (rk4 to begin with), covering a dt longer than max_step
+// with several shorter steps, or with rkf45 as many as adaptive picks. advance instead keeps up
+// with a clock in fixed steps, as the app's main loop does, and drawn gives the state part way
+// from the step before to smooth over where a frame falls between two of them
+
+pub struct Simulation {
+    pub state: State,
+    pub params: Params,
+    pub time: f64, // simulated seconds since it started
+    pub integrator: Integrator,
+    pub adaptive: Adaptive, // rkf45's step size and tolerance
+    pub max_step: f32, // seconds
+    pub clock: FixedClock, // the steps advance takes
+    elapsed: f64, // seconds advance and due have been handed
+    previous: State, // the state a step before, for drawn to start from
+    last_step: f64, // how long that step was, which is more than clock.dt if it covered several
+}
+
+impl Simulation {
+
+    pub const MAX_STEP: f32 = 0.02;
+    pub const FIXED_STEP: f64 = 1.0 / 240.0;
+
+    // the most steps record takes. anything near it would take hours, and much beyond it the
+    // count no longer fits in a usize
+
+    pub const MAX_RECORD_STEPS: f64 = 1e9;
+
+    // the parameters are checked, since the physics would otherwise quietly fill the state with
+    // NaNs given a zero mass or length
+
+    pub fn new(state: State, params: Params) -> Result<Self, String> {
+
+        params.validate()?;
+
+        Ok(Self {
+            state,
+            params,
+            time: 0.0,
+            integrator: Integrator::Rk4,
+            adaptive: Adaptive::new(1e-6),
+            max_step: Self::MAX_STEP,
+            clock: FixedClock::new(Self::FIXED_STEP),
+            elapsed: 0.0,
+            previous: state,
+            last_step: Self::FIXED_STEP,
+        })
+
+    }
+
+    pub fn step(&mut self, dt: f64) {
+        let params = self.params;
+        self.step_with(&params, dt);
+    }
+
+    // a step with other parameters just for it, as the app takes with the drive's and the
+    // controllers' torques on top of its own
+
+    pub fn step_with(&mut self, params: &Params, dt: f64) {
+
+        let next = match self.integrator.step() {
+            Some(step) => substep(step, &self.state, params, dt as f32, self.max_step),
+            None => self.adaptive.advance(&self.state, params, dt as f32),
+        };
+
+        self.stepped_to(next, dt);
+
+    }
+
+    // a step of dt taken some other way, as on the app's cart, which has its own equations
+
+    pub fn stepped_to(&mut self, state: State, dt: f64) {
+
+        self.previous = self.state;
+        self.state = state;
+        self.time += dt;
+        self.last_step = dt;
+
+    }
+
+    // another dt seconds have passed: take as many fixed steps of clock.dt as that brings due,
+    // banking whatever's left over towards the next. however the time is handed over, in many
+    // short frames or a few long ones, the same steps have been taken by the same moment
+
+    pub fn advance(&mut self, dt: f64) {
+
+        let h = self.clock.dt;
+
+        for _ in 0..self.due(dt) {
+            self.step(h);
+        }
+
+    }
+
+    // how many steps advance would take, for stepping them by hand with step_with
+
+    pub fn due(&mut self, dt: f64) -> u64 {
+        self.elapsed += dt;
+        self.clock.due(self.elapsed)
+    }
+
+    // the time and state to draw now the clock's part way through the step after the newest
+    // one: that far along from the step before, which keeps the motion smooth whatever the
+    // frame rate, at the cost of showing it one step late. a step covering several of the
+    // clock's (as rkf45 takes in the app) is drawn across the same fraction of its length
+
+    pub fn drawn(&self) -> (f64, State) {
+
+        let alpha = ((self.elapsed - self.clock.stepped_to()) / self.clock.dt).clamp(0.0, 1.0);
+
+        (self.time - (1.0 - alpha) * self.last_step, interpolate(&self.previous, &self.state, alpha))
+
+    }
+
+    // carry on from another state, as when the pendulum is moved by hand, with nothing to
+    // draw it coming from
+
+    pub fn place(&mut self, state: State) {
+        self.state = state;
+        self.previous = state;
+    }
+
+    // run for `duration` in steps of dt, keeping the time and state at the start and after every
+    // `every` steps, as export and the headless binary write them out. the times are counted
+    // rather than added up, so they come out round
+
+    pub fn record(&mut self, duration: f64, dt: f64, every: usize) -> Result<Vec<(f64, State)>, String> {
+
+        let steps = (duration / dt).round();
+
+        if steps > Self::MAX_RECORD_STEPS {
+            return Err(format!("the duration comes to {:.1e} steps, more than the {:.0e} a recording can take", steps, Self::MAX_RECORD_STEPS));
+        }
+
+        let start = self.time;
+        let steps = steps as usize;
+        let mut rows = Vec::with_capacity(steps / every + 1);
+
+        for step in 0..=steps {
+
+            if step > 0 {
+                self.step(dt);
+            }
+
+            if step % every == 0 {
+                rows.push((start + step as f64 * dt, self.state));
+            }
+        }
+
+        Ok(rows)
+
+    }
+
+    pub fn energy(&self) -> f64 {
+        energy(&self.state, &self.params)
+    }
+
+}
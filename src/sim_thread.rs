@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use double_pendulum::physics::{energy_pump_torque, interpolate, rk4, FixedClock, Params, State};
+
+// runs the physics on its own thread at a fixed rate, so how finely the motion is stepped has
+// nothing to do with the frame rate. it keeps up with the clock, taking as many fixed rk4 steps
+// as have come due since it started, and update just picks up the newest state each frame.
+//
+// the thread and update meet through one mutex-protected snapshot: the thread publishes the
+// state and simulated time after every batch of steps, and update hands over the parameters
+// (which the keys can change) along with, while a bob is being dragged, the state to hold at.
+//
+// a frame almost always lands part way between two steps, so rather than the newest state it
+// draws the one that far along from the step before, which keeps the motion smooth even at a
+// low rate (at the cost of showing it one step late)
+
+pub struct SimThread {
+    shared: Arc<Mutex<Shared>>,
+    start: Instant,
+    dt: f64,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+struct Shared {
+    sim_time: f64,
+    state: State,
+    previous: State, // the state one step before
+    stepped_to: f64, // seconds after the start the state is due at
+    params: Params,
+    pump_torque: f64, // the swing-up's, on top of params.torque and the energy setpoint's
+    held: bool, // a bob is being dragged, so the state is update's to set and time stands still
+    moves: u64, // how many times update has set the state, so steps from an older one are dropped
+}
+
+// more steps than this at once means the thread fell badly behind (the machine was suspended,
+// say), and is let off rather than trying to catch up all at once
+
+const MAX_CATCH_UP: u64 = 10_000;
+
+impl SimThread {
+
+    // energy_setpoint and energy_gain drive the energy as they do without the thread
+
+    pub fn start(rate: f64, sim_time: f64, state: State, params: Params, energy_setpoint: Option<f64>, energy_gain: f64) -> Self {
+
+        let shared = Arc::new(Mutex::new(Shared {
+            sim_time,
+            state,
+            previous: state,
+            stepped_to: 0.0,
+            params,
+            pump_torque: 0.0,
+            held: false,
+            moves: 0,
+        }));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let start = Instant::now();
+        let dt = 1.0 / rate;
+
+        let handle = {
+            let shared = Arc::clone(&shared);
+            let stop = Arc::clone(&stop);
+
+            std::thread::spawn(move || {
+
+                let mut clock = FixedClock::new(dt);
+
+                while !stop.load(Ordering::Relaxed) {
+
+                    let steps = clock.due(start.elapsed().as_secs_f64());
+
+                    if steps == 0 {
+                        let next = clock.next_due() - start.elapsed().as_secs_f64();
+                        std::thread::sleep(Duration::from_secs_f64(next.clamp(0.0, dt)));
+                        continue;
+                    }
+
+                    // step a copy, so update isn't kept waiting on the lock for the whole batch
+
+                    let (mut state, mut params, pump_torque, held, moves) = {
+                        let shared = shared.lock().unwrap();
+                        (shared.state, shared.params, shared.pump_torque, shared.held, shared.moves)
+                    };
+
+                    let steps = steps.min(MAX_CATCH_UP);
+
+                    if held {
+                        continue;
+                    }
+
+                    let mut previous = state;
+
+                    // the energy setpoint's torque takes the place of params.torque, and the
+                    // swing-up's goes on top of either, as in update
+
+                    for _ in 0..steps {
+                        previous = state;
+
+                        if let Some(setpoint) = energy_setpoint {
+                            params.torque = energy_pump_torque(&state, &params, setpoint, energy_gain);
+                        }
+
+                        let pumped = Params { torque: (params.torque.0 + pump_torque as f32, params.torque.1), ..params };
+
+                        state = rk4(&state, &pumped, dt as f32);
+                    }
+
+                    let mut shared = shared.lock().unwrap();
+
+                    // the pendulum may have been grabbed during the batch, in which case where it
+                    // was put wins
+
+                    if shared.moves == moves {
+                        shared.state = state;
+                        shared.previous = previous;
+                        shared.stepped_to = clock.stepped_to();
+                        shared.sim_time += steps as f64 * dt;
+                    }
+                }
+
+            })
+        };
+
+        Self { shared, start, dt, stop, handle }
+
+    }
+
+    // the simulated time and state to draw now, handing over the current parameters and the
+    // swing-up's torque for the steps to come
+
+    pub fn latest(&self, params: &Params, pump_torque: f64) -> (f64, State) {
+
+        let mut shared = self.shared.lock().unwrap();
+
+        shared.params = *params;
+        shared.pump_torque = pump_torque;
+        shared.held = false;
+
+        // how far the clock has got through the step after the newest one
+
+        let alpha = ((self.start.elapsed().as_secs_f64() - shared.stepped_to) / self.dt).clamp(0.0, 1.0);
+
+        (shared.sim_time - (1.0 - alpha) * self.dt, interpolate(&shared.previous, &shared.state, alpha))
+
+    }
+
+    // keep the pendulum at the given state, while a bob is dragged
+
+    pub fn hold(&self, state: &State) {
+
+        let mut shared = self.shared.lock().unwrap();
+
+        shared.state = *state;
+        shared.previous = *state;
+        shared.held = true;
+        shared.moves += 1;
+
+    }
+
+    // stop the thread and wait for it to finish its current batch
+
+    pub fn finish(self) {
+
+        self.stop.store(true, Ordering::Relaxed);
+
+        if self.handle.join().is_err() {
+            eprintln!("the physics thread panicked");
+        }
+
+    }
+
+}
@@ -0,0 +1,68 @@
+use std::io::Write;
+
+use crate::physics::State;
+
+// reading recorded trajectories back in. the file is csv with a header row naming its columns;
+// theta_1 and theta_2 are required, dot_theta_1 and dot_theta_2 are used if present (and are
+// taken as zero otherwise), and any other columns, such as time, are ignored
+
+pub fn read_states(csv: &str) -> Result<Vec<State>, String> {
+
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+
+    let header: Vec<&str> = lines.next().ok_or("file is empty")?.split(',').map(str::trim).collect();
+    let column = |name: &str| header.iter().position(|h| *h == name);
+
+    let theta_1 = column("theta_1").ok_or("no theta_1 column in header")?;
+    let theta_2 = column("theta_2").ok_or("no theta_2 column in header")?;
+    let dot_theta_1 = column("dot_theta_1");
+    let dot_theta_2 = column("dot_theta_2");
+
+    let mut states = Vec::new();
+
+    for (row, line) in lines.enumerate() {
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+        // rows are counted from 2 so they match line numbers in the file (the header is line 1)
+
+        let value = |i: Option<usize>| -> Result<f64, String> {
+            match i {
+                None => Ok(0.0),
+                Some(i) => fields.get(i)
+                    .ok_or(format!("line {}: missing column {}", row + 2, header[i]))?
+                    .parse()
+                    .map_err(|_| format!("line {}: {} is not a number", row + 2, header[i])),
+            }
+        };
+
+        states.push(State {
+            theta_1: value(Some(theta_1))?,
+            theta_2: value(Some(theta_2))?,
+            dot_theta_1: value(dot_theta_1)?,
+            dot_theta_2: value(dot_theta_2)?,
+        });
+    }
+
+    if states.is_empty() {
+        return Err("file has a header but no rows".to_string());
+    }
+
+    Ok(states)
+
+}
+
+// and writing them out in the same format, a time column and then the state, one row for each
+// (time, state) pair. this is what export and the headless binary write, and --replay reads
+
+pub fn write_states(out: &mut impl Write, rows: &[(f64, State)]) -> std::io::Result<()> {
+
+    writeln!(out, "time,theta_1,theta_2,dot_theta_1,dot_theta_2")?;
+
+    for (time, state) in rows {
+        writeln!(out, "{},{},{},{},{}", time, state.theta_1, state.theta_2, state.dot_theta_1, state.dot_theta_2)?;
+    }
+
+    out.flush()
+
+}
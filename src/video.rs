@@ -0,0 +1,212 @@
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::io::Write;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::task::{Context, Poll, Waker};
+
+use nannou::prelude::*;
+use nannou::wgpu;
+
+// records the window straight to a video file by piping raw rgba frames into ffmpeg.
+//
+// each frame is copied off the gpu at the end of view, but can only be read back once nannou
+// has submitted it, so it's held in `pending` until the following update writes it out. the
+// frame texture must not be multisampled for the copy to work, so the window is created without
+// msaa while recording
+
+pub struct Video {
+    ffmpeg: Child,
+    stdin: Option<ChildStdin>, // None once ffmpeg has gone away
+    size: (u32, u32),
+    capturer: wgpu::TextureCapturer,
+    pending: RefCell<Option<wgpu::TextureSnapshot>>,
+}
+
+impl Video {
+
+    // size is the window size in physical pixels, which can't change while recording
+
+    pub fn start(path: &str, fps: f32, size: (u32, u32)) -> Result<Self, String> {
+
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", size.0, size.1)])
+            .args(["-r", &fps.to_string()])
+            .args(["-i", "-"])
+
+            // most players need yuv420p, which needs even dimensions
+
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => "--video needs ffmpeg, which wasn't found on PATH".to_string(),
+                _ => format!("couldn't start ffmpeg: {}", e),
+            })?;
+
+        let stdin = ffmpeg.stdin.take();
+
+        Ok(Self {
+            ffmpeg,
+            stdin,
+            size,
+            capturer: wgpu::TextureCapturer::new(None, None),
+            pending: RefCell::new(None),
+        })
+
+    }
+
+    // call at the very end of view, once everything has been drawn to the frame
+
+    pub fn capture(&self, frame: &Frame) {
+
+        let device = frame.device_queue_pair().device();
+        let mut encoder = frame.command_encoder();
+        let snapshot = self.capturer.capture(device, &mut encoder, frame.texture());
+
+        *self.pending.borrow_mut() = Some(snapshot);
+
+    }
+
+    // read back the frame captured by the last view and send it to ffmpeg. blocks until the gpu
+    // has finished with it
+
+    pub fn write_pending(&mut self, device: &wgpu::Device) {
+
+        let Some(snapshot) = self.pending.get_mut().take() else {
+            return;
+        };
+
+        // the read maps the buffer, which only completes as the device is polled
+
+        let mut read = std::pin::pin!(snapshot.read_async());
+        let mut context = Context::from_waker(Waker::noop());
+
+        let result = loop {
+            match read.as_mut().poll(&mut context) {
+                Poll::Ready(result) => break result,
+                Poll::Pending => {
+                    device.poll(wgpu::Maintain::Wait);
+                }
+            }
+        };
+
+        let image = match result {
+            Ok(image) => image.to_owned(),
+            Err(e) => {
+                eprintln!("couldn't read back a video frame: {:?}", e);
+                return;
+            }
+        };
+
+        if image.dimensions() != self.size {
+            eprintln!("skipping a video frame of the wrong size");
+            return;
+        }
+
+        if let Some(stdin) = &mut self.stdin {
+            if let Err(e) = stdin.write_all(image.as_raw()) {
+                eprintln!("ffmpeg stopped accepting frames ({}), recording has ended", e);
+                self.stdin = None;
+            }
+        }
+
+    }
+
+    // closing ffmpeg's input tells it to finish off the file
+
+    pub fn finish(mut self, device: Option<&wgpu::Device>) {
+
+        if let Some(device) = device {
+            self.write_pending(device);
+        }
+
+        self.stdin = None;
+
+        match self.ffmpeg.wait() {
+            Ok(status) if status.success() => {}
+            Ok(status) => eprintln!("ffmpeg exited with {}", status),
+            Err(e) => eprintln!("couldn't wait for ffmpeg to finish: {}", e),
+        }
+
+    }
+
+}
+
+// with --timelapse, which frames to keep so a long run fits into a short clip: every frame at
+// first, while an ensemble is still together and the interesting part is quick, then fewer and
+// fewer as it spreads out and settles into chaos. the gap between captures starts at one frame
+// and grows by the same ratio each time, picked so the last of the frames lands exactly at the
+// end of the run. it's checked from view, which only sees the model, hence the cells
+
+pub struct TimeLapse {
+    next: Cell<f64>, // the simulated time the next capture is due at
+    gap: Cell<f64>, // after that one
+    ratio: f64,
+    frame_dt: f64,
+    remaining: Cell<u32>,
+}
+
+impl TimeLapse {
+
+    pub fn new(start: f64, duration: f64, frames: u32, frame_dt: f64) -> Result<Self, String> {
+
+        if frames < 2 {
+            return Err("--timelapse-frames needs at least 2 frames".to_string());
+        }
+
+        let gaps = (frames - 1) as f64;
+
+        if gaps * frame_dt >= duration {
+            return Err(format!(
+                "{} frames one after another already cover {}s, so there's nothing to speed up: record it with --video instead",
+                frames, duration,
+            ));
+        }
+
+        // the gaps add up to frame_dt (ratio^gaps - 1) / (ratio - 1), which grows with the
+        // ratio, so bisect for the one that makes it the duration. the gaps can't all be longer
+        // than a single one covering the whole run, which bounds it from above
+
+        let total = |ratio: f64| frame_dt * (ratio.powf(gaps) - 1.0) / (ratio - 1.0);
+        let (mut low, mut high) = (1.0, (duration / frame_dt).powf(1.0 / gaps));
+
+        for _ in 0..100 {
+            let middle = 0.5 * (low + high);
+
+            if total(middle) < duration {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        Ok(Self { next: Cell::new(start), gap: Cell::new(frame_dt), ratio: 0.5 * (low + high), frame_dt, remaining: Cell::new(frames) })
+
+    }
+
+    // whether the frame drawn at this simulated time should be kept, moving on to the next
+    // capture if so. each capture goes to the frame nearest it, so early on, with the gaps only
+    // just over a frame long, none get skipped
+
+    pub fn due(&self, sim_time: f64) -> bool {
+
+        if self.finished() || sim_time < self.next.get() - 0.5 * self.frame_dt {
+            return false;
+        }
+
+        self.remaining.set(self.remaining.get() - 1);
+        self.next.set(self.next.get() + self.gap.get());
+        self.gap.set(self.gap.get() * self.ratio);
+
+        true
+
+    }
+
+    pub fn finished(&self) -> bool {
+        self.remaining.get() == 0
+    }
+
+}
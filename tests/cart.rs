@@ -0,0 +1,78 @@
+use double_pendulum::cart::{cart_derivatives, cart_energy, cart_momentum, cart_rk4, Cart, CartState};
+use double_pendulum::physics::{derivatives, JointControl, Params, State};
+use double_pendulum::presets::PRESETS;
+
+const PARAMS: Params = Params { l1: 1.0, l2: 0.8, m1: 1.0, m2: 0.6, gravity: 9.81, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+
+const START: State = State { theta_1: 2.0, theta_2: 2.5, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+
+// a cart far heavier than the pendulum hardly moves, so the arms swing as if from a fixed
+// pivot, torques and controller included
+
+#[test]
+fn heavy_cart_is_a_fixed_pivot() {
+
+    let cart = Cart { mass: 1e9, force: 0.0 };
+
+    for preset in PRESETS {
+
+        let params = Params { torque: (0.7, -0.4), control: JointControl { target: 0.5, kp: 20.0, kd: 3.0 }, ..preset.params };
+        let state = State { theta_1: 1.3, theta_2: -0.6, dot_theta_1: 2.0, dot_theta_2: -1.5 };
+
+        let fixed = derivatives(&state, &params);
+        let carried = cart_derivatives(&CartState { x: 0.0, dot_x: 0.0, pendulum: state }, &params, &cart);
+
+        assert!(carried.dot_x.abs() < 1e-6, "{}: the cart accelerates at {}", preset.name, carried.dot_x);
+
+        for (a, b) in [(carried.pendulum.dot_theta_1, fixed.dot_theta_1), (carried.pendulum.dot_theta_2, fixed.dot_theta_2)] {
+            assert!((a - b).abs() < 1e-6 * (1.0 + b.abs()), "{}: {} against {}", preset.name, a, b);
+        }
+    }
+
+}
+
+// left to itself, with no force on the cart, the whole thing keeps its energy and its momentum
+// along the track, while the cart is pushed back and forth by the swinging
+
+#[test]
+fn free_cart_conserves_energy_and_momentum() {
+
+    let cart = Cart { mass: 2.0, force: 0.0 };
+    let mut state = CartState { x: 0.0, dot_x: 0.3, pendulum: START };
+
+    let energy = cart_energy(&state, &PARAMS, &cart);
+    let momentum = cart_momentum(&state, &PARAMS, &cart);
+    let (mut slowest, mut fastest) = (state.dot_x, state.dot_x);
+
+    for _ in 0..10_000 {
+        state = cart_rk4(&state, &PARAMS, &cart, 0.001);
+        (slowest, fastest) = (slowest.min(state.dot_x), fastest.max(state.dot_x));
+
+        assert!((cart_energy(&state, &PARAMS, &cart) - energy).abs() < 1e-6 * energy.abs().max(1.0));
+        assert!((cart_momentum(&state, &PARAMS, &cart) - momentum).abs() < 1e-6);
+    }
+
+    assert!(fastest - slowest > 1.0, "the cart's speed only varied between {} and {} m/s", slowest, fastest);
+
+}
+
+// a steady push accelerates everything together, so the momentum along the track grows by the
+// force times the time, whatever the arms are doing
+
+#[test]
+fn force_changes_momentum_at_its_rate() {
+
+    let cart = Cart { mass: 1.5, force: 4.0 };
+    let mut state = CartState { x: 0.0, dot_x: 0.0, pendulum: START };
+
+    let start = cart_momentum(&state, &PARAMS, &cart);
+
+    for _ in 0..2000 {
+        state = cart_rk4(&state, &PARAMS, &cart, 0.001);
+    }
+
+    let gained = cart_momentum(&state, &PARAMS, &cart) - start;
+
+    assert!((gained - 4.0 * 2.0).abs() < 1e-6, "gained {}", gained);
+
+}
@@ -0,0 +1,218 @@
+use double_pendulum::chaos::{convex_hull, divergence_rate, drive_torque, oscillation_phase, synchronization, LoopDetector, LyapunovEstimate, Regime, RegimeThresholds};
+use double_pendulum::physics::{normal_mode_frequencies, rk4};
+use double_pendulum::presets::PRESETS;
+
+fn preset_rate(name: &str) -> f64 {
+    let preset = PRESETS.iter().find(|preset| preset.name == name).unwrap();
+    divergence_rate(&preset.state, &preset.params, 20.0, 0.01)
+}
+
+// small swings in a normal mode are regular, so nearby runs barely separate, while the raised
+// start is fully chaotic
+
+#[test]
+fn raised_is_more_chaotic_than_a_normal_mode() {
+
+    let regular = preset_rate("slow-mode");
+    let chaotic = preset_rate("raised");
+
+    println!("divergence rates: {regular:.3}/s slow-mode, {chaotic:.3}/s raised");
+
+    assert!(regular < 0.2);
+    assert!(chaotic > 5.0 * regular);
+
+}
+
+#[test]
+fn hull_of_a_square_with_points_inside() {
+
+    let points = [(0.0, 0.0), (1.0, 0.0), (0.5, 0.5), (1.0, 1.0), (0.2, 0.7), (0.0, 1.0), (0.5, 0.0)];
+
+    assert_eq!(convex_hull(&points), vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+
+}
+
+#[test]
+fn hull_of_too_few_points() {
+
+    assert_eq!(convex_hull(&[]), vec![]);
+    assert_eq!(convex_hull(&[(1.0, 2.0), (1.0, 2.0)]), vec![(1.0, 2.0)]);
+    assert_eq!(convex_hull(&[(1.0, 2.0), (0.0, 0.0)]), vec![(0.0, 0.0), (1.0, 2.0)]);
+
+    // all on one line, which also encloses nothing
+
+    assert_eq!(convex_hull(&[(0.0, 0.0), (2.0, 2.0), (1.0, 1.0)]), vec![(0.0, 0.0), (2.0, 2.0)]);
+
+}
+
+// over 20 seconds, the arms hold a fixed phase relationship in either normal mode (in step in
+// the slow one, opposed in the fast one) but not once the motion is chaotic
+
+fn preset_synchronization(name: &str) -> f64 {
+
+    let preset = PRESETS.iter().find(|preset| preset.name == name).unwrap();
+    let frequency = (preset.params.gravity / preset.params.l1).sqrt() as f64;
+
+    let mut state = preset.state;
+    let mut differences = Vec::new();
+
+    for _ in 0..2000 {
+        state = rk4(&state, &preset.params, 0.01);
+        differences.push(
+            oscillation_phase(state.theta_1, state.dot_theta_1, frequency)
+                - oscillation_phase(state.theta_2, state.dot_theta_2, frequency)
+        );
+    }
+
+    synchronization(differences.into_iter())
+
+}
+
+#[test]
+fn normal_modes_are_synchronized() {
+
+    let slow = preset_synchronization("slow-mode");
+    let fast = preset_synchronization("fast-mode");
+    let raised = preset_synchronization("raised");
+
+    println!("synchronization: {slow:.3} slow-mode, {fast:.3} fast-mode, {raised:.3} raised");
+
+    assert!(slow > 0.95);
+    assert!(fast > 0.95);
+    assert!(raised < 0.8);
+
+}
+
+// an oscillator running 5% faster than the slow normal mode drifts steadily against the first
+// arm on its own, but coupled strongly enough it pulls the swing into step with it. with no
+// coupling there's no torque at all
+
+fn drive_synchronization(coupling: f64) -> f64 {
+
+    let preset = PRESETS.iter().find(|preset| preset.name == "slow-mode").unwrap();
+    let frequency = 1.05 * normal_mode_frequencies(&preset.params).0;
+
+    let mut state = preset.state;
+    let mut params = preset.params;
+    let mut phase = 0.0;
+    let mut differences = Vec::new();
+
+    for step in 0..20000 {
+        params.torque.0 = drive_torque(&state, phase, frequency, coupling) as f32;
+        state = rk4(&state, &params, 0.01);
+        phase += frequency * 0.01;
+
+        // leave the first 100 seconds for it to lock on
+
+        if step >= 10000 {
+            differences.push(phase - oscillation_phase(state.theta_1, state.dot_theta_1, frequency));
+        }
+    }
+
+    synchronization(differences.into_iter())
+
+}
+
+#[test]
+fn strong_coupling_entrains_the_first_arm() {
+
+    let free = drive_synchronization(0.0);
+    let coupled = drive_synchronization(1.0);
+
+    println!("synchronization with the drive: {free:.3} uncoupled, {coupled:.3} coupled");
+
+    assert!(free < 0.2);
+    assert!(coupled > 0.95);
+
+    let preset = PRESETS.iter().find(|preset| preset.name == "slow-mode").unwrap();
+    assert_eq!(drive_torque(&preset.state, 1.0, 2.0, 0.0), 0.0);
+
+}
+
+// the online estimate, fed one 60fps frame at a time, should agree with the offline one about
+// which presets are chaotic
+
+fn online_rate(name: &str) -> f64 {
+
+    let preset = PRESETS.iter().find(|preset| preset.name == name).unwrap();
+    let mut estimate = LyapunovEstimate::new(20.0);
+    let mut state = preset.state;
+
+    for _ in 0..2400 {
+        estimate.advance(&state, &preset.params, 1.0 / 60.0);
+        state = rk4(&state, &preset.params, 1.0 / 60.0);
+    }
+
+    estimate.exponent().unwrap()
+
+}
+
+#[test]
+fn online_estimate_picks_out_chaos() {
+
+    let regular = online_rate("slow-mode");
+    let chaotic = online_rate("raised");
+
+    println!("online divergence rates: {regular:.3}/s slow-mode, {chaotic:.3}/s raised");
+
+    assert!(regular.abs() < 0.2);
+    assert!(chaotic > 0.5);
+
+}
+
+#[test]
+fn regime_holds_until_clearly_past_a_threshold() {
+
+    let thresholds = RegimeThresholds { quasiperiodic: 0.1, chaotic: 0.5, hysteresis: 0.05 };
+    let classify = |rate, previous| Regime::classify(rate, previous, &thresholds);
+
+    assert_eq!(classify(0.0, None), Regime::Periodic);
+    assert_eq!(classify(0.3, None), Regime::Quasiperiodic);
+    assert_eq!(classify(0.8, None), Regime::Chaotic);
+
+    // just past a threshold isn't enough to leave, from either side
+
+    assert_eq!(classify(0.12, Some(Regime::Periodic)), Regime::Periodic);
+    assert_eq!(classify(0.2, Some(Regime::Periodic)), Regime::Quasiperiodic);
+    assert_eq!(classify(0.48, Some(Regime::Chaotic)), Regime::Chaotic);
+    assert_eq!(classify(0.4, Some(Regime::Chaotic)), Regime::Quasiperiodic);
+    assert_eq!(classify(0.08, Some(Regime::Quasiperiodic)), Regime::Quasiperiodic);
+    assert_eq!(classify(0.52, Some(Regime::Quasiperiodic)), Regime::Quasiperiodic);
+
+    // but a big jump can go straight across both
+
+    assert_eq!(classify(1.0, Some(Regime::Periodic)), Regime::Chaotic);
+    assert_eq!(classify(-0.1, Some(Regime::Chaotic)), Regime::Periodic);
+
+}
+
+// swinging in the slow mode, the run should come back round once every slow-mode period, and
+// not before
+
+#[test]
+fn loops_close_once_a_normal_mode_period() {
+
+    let preset = PRESETS.iter().find(|preset| preset.name == "slow-mode").unwrap();
+    let period = std::f64::consts::TAU / normal_mode_frequencies(&preset.params).0 as f64;
+
+    let mut detector = LoopDetector::new(0.05, 0.02, 30.0);
+    let mut state = preset.state;
+    let mut closures = Vec::new();
+    let dt = 0.01;
+
+    for step in 0..2000 {
+        if let Some(found) = detector.record(step as f64 * dt, &state) {
+            closures.push(found);
+        }
+        state = rk4(&state, &preset.params, dt as f32);
+    }
+
+    println!("slow-mode period {period:.3}s, loops closed after {closures:.3?}");
+
+    assert!(closures.len() >= 5);
+
+    for found in closures {
+        assert!((found - period).abs() < 0.05 * period);
+    }
+
+}
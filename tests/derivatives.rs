@@ -0,0 +1,153 @@
+use double_pendulum::physics::{derivatives, lagrangian_accelerations, mass_denominator, natural_units, rk4, JointControl, Params, State};
+use double_pendulum::presets::PRESETS;
+
+const CONTROL: JointControl = JointControl { target: 0.7, kp: 30.0, kd: 4.0 };
+
+// gravity on both bobs, on only one of them, and unevenly
+
+const GRAVITY_SCALES: [(f32, f32); 4] = [(1.0, 1.0), (1.0, 0.0), (0.0, 1.0), (0.5, 1.5)];
+
+// the accelerations from derivatives should agree with ones worked out numerically from the
+// energies, across a spread of states and every preset's parameters, with and without torques,
+// the controller on the first joint and gravity on only one bob
+
+#[test]
+fn derivatives_match_lagrangian() {
+
+    let mut worst: f64 = 0.0;
+
+    for preset in PRESETS {
+        for (torque, control) in [((0.0, 0.0), JointControl::OFF), ((1.5, -0.5), JointControl::OFF), ((0.5, 0.0), CONTROL)] {
+            for (gravity_scale_1, gravity_scale_2) in GRAVITY_SCALES {
+
+                let params = Params { torque, control, gravity_scale_1, gravity_scale_2, ..preset.params };
+
+                for i in 0..9 {
+                    for j in 0..9 {
+
+                        let state = State {
+                            theta_1: -3.0 + 0.75 * i as f64,
+                            theta_2: 2.5 - 0.6 * j as f64,
+                            dot_theta_1: -4.0 + i as f64,
+                            dot_theta_2: 3.0 - 0.8 * j as f64,
+                        };
+
+                        let analytic = derivatives(&state, &params);
+                        let numeric = lagrangian_accelerations(&state, &params);
+
+                        for (a, n) in [(analytic.dot_theta_1, numeric.0), (analytic.dot_theta_2, numeric.1)] {
+                            worst = worst.max((a - n).abs() / a.abs().max(1.0));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    println!("worst discrepancy: {worst:.2e}");
+
+    assert!(worst < 1e-5);
+
+}
+
+// the denominator should stay at 1 or above (so the accelerations stay finite) everywhere,
+// including with the arms in line, at extreme speeds, and with very lopsided masses
+
+#[test]
+fn denominator_stays_positive() {
+
+    let mut lowest = f64::INFINITY;
+
+    for (m1, m2) in [(1.0, 1.0), (1000.0, 0.001), (0.001, 1000.0)] {
+
+        let params = Params::new(1.0, 0.3, m1, m2, 9.81).unwrap();
+
+        for i in 0..=40 {
+            for j in 0..=40 {
+                for speed in [0.0, 1.0, 100.0, -1e4] {
+
+                    let state = State {
+                        theta_1: -6.3 + 0.315 * i as f64,
+                        theta_2: 6.3 - 0.315 * j as f64,
+                        dot_theta_1: speed,
+                        dot_theta_2: -0.5 * speed,
+                    };
+
+                    let denominator = mass_denominator(&state, &params);
+                    let rates = derivatives(&state, &params);
+
+                    lowest = lowest.min(denominator);
+
+                    assert!(denominator >= 1.0, "{denominator} at {state:?}");
+                    assert!(rates.dot_theta_1.is_finite() && rates.dot_theta_2.is_finite(), "{rates:?} at {state:?}");
+                }
+            }
+        }
+    }
+
+    println!("lowest denominator: {lowest}");
+
+}
+
+// with the gains at zero the controller leaves the equations of motion exactly as they were,
+// whatever its target. with stiff gains it pulls the first arm round from hanging to near the
+// target and holds it there, short of it by about as much as gravity pushes back
+
+#[test]
+fn joint_control_pulls_the_first_arm_to_its_target() {
+
+    let free = Params::new(1.0, 1.0, 1.0, 1.0, 10.0).unwrap();
+    let idle = Params { control: JointControl { target: 2.0, kp: 0.0, kd: 0.0 }, ..free };
+    let state = State { theta_1: 0.3, theta_2: -1.2, dot_theta_1: 2.0, dot_theta_2: -0.5 };
+
+    assert_eq!(derivatives(&state, &free), derivatives(&state, &idle));
+
+    let target = 1.0;
+    let params = Params { control: JointControl { target, kp: 400.0, kd: 40.0 }, ..free };
+    let mut state = State { theta_1: 0.0, theta_2: 0.0, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+
+    for _ in 0..2000 {
+        state = rk4(&state, &params, 0.01);
+    }
+
+    println!("theta_1 settled at {:.3}, aiming for {target}", state.theta_1);
+
+    assert!((state.theta_1 - target).abs() < 0.1);
+    assert!(state.dot_theta_1.abs() < 0.5);
+
+}
+
+// in natural units time runs in tau = sqrt(l1/g), so each rate of change is tau times the one
+// in seconds and each acceleration tau^2 times, whatever the masses, lengths and torques
+
+#[test]
+fn natural_units_rescale_time() {
+
+    for preset in PRESETS {
+
+        let params = Params { torque: (0.8, -0.3), control: CONTROL, ..preset.params };
+        let state = State { theta_1: 1.1, theta_2: -2.4, dot_theta_1: 0.7, dot_theta_2: -1.9 };
+
+        let (natural_state, natural_params, tau) = natural_units(&state, &params).unwrap();
+
+        assert_eq!((natural_params.l1, natural_params.m1, natural_params.gravity), (1.0, 1.0, 1.0));
+
+        let physical = derivatives(&state, &params);
+        let natural = derivatives(&natural_state, &natural_params);
+
+        for (a, b, power) in [
+            (natural.theta_1, physical.theta_1, 1),
+            (natural.theta_2, physical.theta_2, 1),
+            (natural.dot_theta_1, physical.dot_theta_1, 2),
+            (natural.dot_theta_2, physical.dot_theta_2, 2),
+        ] {
+            let expected = tau.powi(power) * b;
+            assert!((a - expected).abs() < 1e-5 * (1.0 + expected.abs()), "{}: {} against {}", preset.name, a, expected);
+        }
+    }
+
+    let weightless = Params { gravity: 0.0, ..PRESETS[0].params };
+    assert!(natural_units(&State::new(1.0, 1.0, 0.0, 0.0), &weightless).is_err());
+
+}
+
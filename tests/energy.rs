@@ -0,0 +1,205 @@
+use double_pendulum::physics::{angular_momentum, energy, energy_pump_torque, gravity_torque, potential_energy, project_energy, rk4, swing_up_torque, JointControl, Params, State};
+
+const PARAMS: Params = Params { l1: 1.0, l2: 1.0, m1: 1.0, m2: 1.0, gravity: 10.0, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+
+// a deliberately coarse step so plain rk4 drifts noticeably
+
+const DT: f32 = 0.01;
+
+const START: State = State { theta_1: 2.0, theta_2: 2.0, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+
+#[test]
+fn projection_pins_energy() {
+
+    let target = energy(&START, &PARAMS);
+
+    let mut plain = START;
+    let mut projected = START;
+
+    let mut plain_drift: f64 = 0.0;
+    let mut projected_drift: f64 = 0.0;
+
+    for step in 0..6000 {
+
+        plain = rk4(&plain, &PARAMS, DT);
+        projected = project_energy(&rk4(&projected, &PARAMS, DT), &PARAMS, target);
+
+        plain_drift = plain_drift.max((energy(&plain, &PARAMS) - target).abs());
+        projected_drift = projected_drift.max((energy(&projected, &PARAMS) - target).abs());
+
+        // over the first second, before chaos separates them, the projection should only
+        // nudge the trajectory and not send it somewhere else entirely
+
+        if step == 100 {
+            assert!((plain.theta_1 - projected.theta_1).abs() < 1e-3);
+            assert!((plain.theta_2 - projected.theta_2).abs() < 1e-3);
+        }
+    }
+
+    println!("max energy drift over 60s: {plain_drift:.2e} plain, {projected_drift:.2e} projected");
+
+    assert!(projected_drift < 1e-9);
+    assert!(plain_drift > 1000.0 * projected_drift);
+
+}
+
+#[test]
+fn projection_leaves_stationary_state_alone() {
+
+    // no kinetic energy to rescale, so asking for more energy can't do anything
+
+    let state = State { theta_1: 0.5, theta_2: -0.5, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+    let target = energy(&state, &PARAMS) + 1.0;
+
+    assert_eq!(project_energy(&state, &PARAMS, target), state);
+
+}
+
+#[test]
+fn pump_settles_on_setpoint() {
+
+    // both pumping energy in and draining it out, from the same chaotic start
+
+    let start_energy = energy(&START, &PARAMS);
+
+    for setpoint in [start_energy + 10.0, start_energy - 10.0] {
+
+        let mut state = START;
+        let mut params = PARAMS;
+        let mut worst_late_error: f64 = 0.0;
+
+        for step in 0..6000 {
+
+            params.torque = energy_pump_torque(&state, &params, setpoint, 0.5);
+            state = rk4(&state, &params, DT);
+
+            // once it's had time to get there, it should stay close
+
+            if step >= 3000 {
+                worst_late_error = worst_late_error.max((energy(&state, &PARAMS) - setpoint).abs());
+            }
+        }
+
+        println!("setpoint {setpoint:.2}: worst error over the last 30s {worst_late_error:.2e}");
+
+        assert!(worst_late_error < 0.01 * (setpoint - start_energy).abs());
+    }
+
+}
+
+// balanced upside down is as far from the usual start as it gets. it should topple cleanly,
+// staying finite and keeping its energy, rather than blowing up at the unstable equilibrium
+
+#[test]
+fn inverted_start_topples_cleanly() {
+
+    let preset = double_pendulum::presets::find("inverted").unwrap();
+    let start = energy(&preset.state, &preset.params);
+
+    let mut state = preset.state;
+
+    for _ in 0..2000 {
+        state = rk4(&state, &preset.params, 0.01);
+        assert!(state.theta_1.is_finite() && state.theta_2.is_finite(), "{state:?}");
+        assert!(state.dot_theta_1.is_finite() && state.dot_theta_2.is_finite(), "{state:?}");
+    }
+
+    // it's left the top by now
+
+    assert!(state.theta_1.cos() > -0.99 || state.theta_2.cos() > -0.99, "{state:?}");
+    let drift = (energy(&state, &preset.params) - start).abs();
+    println!("energy drift after 20s: {drift:.2e} J of {start:.1} J");
+
+    assert!(drift < 0.01 * start.abs());
+
+}
+
+// from hanging at rest, the swing-up torque alone (held through each frame, as update does)
+// should pump the first arm up to within a small angle of upright in a few swings, with the
+// torque kept within its limit the whole way
+
+#[test]
+fn swing_up_reaches_the_top() {
+
+    let mut state = State { theta_1: 0.0, theta_2: 0.0, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+    let max_torque = 10.0;
+
+    let reached = (0..600).find(|_| {
+
+        let torque = swing_up_torque(&state, &PARAMS, 2.0, max_torque);
+        assert!(torque.abs() <= max_torque);
+
+        let params = Params { torque: (torque as f32, 0.0), ..PARAMS };
+        state = rk4(&state, &params, 1.0 / 60.0);
+
+        (state.theta_1.rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI).abs() < 0.3
+    });
+
+    println!("first arm near upright after {:?} frames", reached);
+
+    assert!(reached.is_some());
+
+}
+
+// hanging still (or balanced straight up) gravity pulls straight along the arms, so it has no
+// turning effect about the pivot. anywhere else the torque is how fast the potential energy
+// falls as the whole pendulum is turned rigidly about the pivot
+
+#[test]
+fn gravity_torque_vanishes_hanging_still() {
+
+    let params = Params::new(1.2, 0.8, 1.5, 0.7, 9.81).unwrap();
+
+    assert!(gravity_torque(&State::new(0.0, 0.0, 0.0, 0.0), &params).abs() < 1e-9);
+    assert!(gravity_torque(&State::new(std::f32::consts::PI, std::f32::consts::PI, 0.0, 0.0), &params).abs() < 1e-5);
+
+    // both arms out horizontally to the right, so each bob's whole weight acts at its distance
+
+    let torque = gravity_torque(&State::new(std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2, 0.0, 0.0), &params);
+    let expected = -9.81 * (1.5 * 1.2 + 0.7 * 2.0);
+
+    assert!((torque - expected).abs() < 1e-4, "{torque} vs {expected}");
+
+    let state = State { theta_1: 0.7, theta_2: -2.1, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+    let h = 1e-6;
+    let turned = |by: f64| State { theta_1: state.theta_1 + by, theta_2: state.theta_2 + by, ..state };
+    let slope = (potential_energy(&turned(h), &params) - potential_energy(&turned(-h), &params)) / (2.0 * h);
+
+    assert!((gravity_torque(&state, &params) + slope).abs() < 1e-5);
+
+}
+
+// with no gravity nothing turns the pendulum about the pivot, so however chaotically the arms
+// fold the total angular momentum stays where it started, up to rk4's drift
+
+#[test]
+fn angular_momentum_conserved_without_gravity() {
+
+    let params = Params { gravity: 0.0, ..PARAMS };
+    let mut state = State { theta_1: 0.3, theta_2: 2.5, dot_theta_1: 1.0, dot_theta_2: 3.0 };
+
+    let start = angular_momentum(&state, &params);
+    let mut drift: f64 = 0.0;
+
+    for _ in 0..6000 {
+        state = rk4(&state, &params, DT);
+        drift = drift.max((angular_momentum(&state, &params) - start).abs());
+    }
+
+    assert!(start.abs() > 1.0);
+    assert!(drift < 1e-6 * start.abs(), "angular momentum drifted by {drift:.2e}");
+
+    // whereas under gravity it swings back and forth
+
+    let mut state = START;
+    let mut peak: f64 = 0.0;
+
+    for _ in 0..200 {
+        state = rk4(&state, &PARAMS, DT);
+        peak = peak.max(angular_momentum(&state, &PARAMS).abs());
+    }
+
+    assert!(peak > 1.0);
+
+}
+
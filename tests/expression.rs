@@ -0,0 +1,43 @@
+use double_pendulum::expression::Expression;
+
+fn value(text: &str, t: f64) -> f64 {
+    Expression::parse(text).unwrap().evaluate(t)
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-12 * b.abs().max(1.0)
+}
+
+// the usual precedence: powers first (right to left), then products, then sums, with a leading
+// minus applying to the power
+
+#[test]
+fn expressions_follow_the_usual_rules() {
+
+    let t = 0.7_f64;
+
+    assert!(close(value("0.5*sin(2*t) + 0.1*t", t), 0.5 * (2.0 * t).sin() + 0.1 * t));
+    assert!(close(value("1 + 2 * 3", t), 7.0));
+    assert!(close(value("(1 + 2) * 3", t), 9.0));
+    assert!(close(value("8 / 4 / 2", t), 1.0));
+    assert!(close(value("2 ^ 3 ^ 2", t), 512.0));
+    assert!(close(value("-t^2", t), -(t * t)));
+    assert!(close(value("2^-t", t), 2.0_f64.powf(-t)));
+    assert!(close(value("1e-3 * exp(t) - ln(2) + sqrt(abs(-4)) + cos(pi) + tan(0)", t), 1e-3 * t.exp() - 2.0_f64.ln() + 2.0 - 1.0));
+
+}
+
+// mistakes are caught when the expression is parsed, pointing at where it went wrong
+
+#[test]
+fn syntax_mistakes_are_reported() {
+
+    for (text, position) in [("0.5*sin(2*t", 12), ("1 +", 4), ("2 * x", 5), ("sin 2", 5), ("3 t", 3), ("1.2.3", 1), ("", 1)] {
+
+        let error = Expression::parse(text).unwrap_err();
+        println!("{text:?}: {error}");
+
+        assert!(error.ends_with(&format!("at character {}", position)), "{text:?}: {error}");
+    }
+
+}
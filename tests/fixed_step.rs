@@ -0,0 +1,170 @@
+use double_pendulum::physics::{interpolate, rk4, FixedClock, JointControl, Params, State};
+use double_pendulum::sim::Simulation;
+
+const PARAMS: Params = Params { l1: 1.0, l2: 1.0, m1: 1.0, m2: 1.0, gravity: 9.81, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+
+const START: State = State { theta_1: 2.0, theta_2: 2.5, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+
+const DT: f64 = 1e-3;
+
+// seconds of wall time, half way between two steps so however it's added up it can't round
+// onto either side of one
+
+const TOTAL: f64 = 5.0005;
+
+// steps the pendulum through time delivered in frames of these lengths, as the app's main loop
+// does, returning the state once it's caught up and the steps that took
+
+fn run(frames: &[f64]) -> (State, u64) {
+
+    let mut sim = Simulation::new(START, PARAMS).unwrap();
+    sim.clock = FixedClock::new(DT);
+
+    for frame in frames {
+        sim.advance(*frame);
+    }
+
+    (sim.state, (sim.time / DT).round() as u64)
+
+}
+
+// the same wall time in frames of the given length, with whatever's left over as a last one
+
+fn even_frames(length: f64) -> Vec<f64> {
+
+    let count = (TOTAL / length) as usize;
+    let mut frames = vec![length; count];
+
+    frames.push(TOTAL - count as f64 * length);
+
+    frames
+
+}
+
+// the trajectory depends only on how much time has passed, not on the frame rate it passed
+// at. the chaotic start would blow up even one step's difference, so the states agree closely
+// only if exactly the same steps were taken
+
+#[test]
+fn trajectory_independent_of_frame_rate() {
+
+    let (reference, steps) = run(&even_frames(1.0 / 240.0));
+
+    assert_eq!(steps, 5000);
+
+    // ragged frames, as when the app stutters, including some shorter than a step and one
+    // long stall
+
+    let mut ragged: Vec<f64> = (0..300).map(|i| [0.0003, 0.016, 0.0071, 0.033][i % 4]).collect();
+    ragged.push(0.5);
+    ragged.push(TOTAL - ragged.iter().sum::<f64>());
+
+    for frames in [even_frames(1.0 / 30.0), even_frames(1.0 / 144.0), even_frames(0.25), vec![TOTAL], ragged] {
+
+        let (state, taken) = run(&frames);
+
+        assert_eq!(taken, steps, "{} frames", frames.len());
+
+        for (a, b) in [(state.theta_1, reference.theta_1), (state.theta_2, reference.theta_2), (state.dot_theta_1, reference.dot_theta_1), (state.dot_theta_2, reference.dot_theta_2)] {
+            assert!((a - b).abs() < 1e-9, "{} frames: {state:?} against {reference:?}", frames.len());
+        }
+    }
+
+}
+
+// a frame too short to bring the next step due takes none, and the time isn't lost
+
+#[test]
+fn short_frames_bank_their_time() {
+
+    let mut clock = FixedClock::new(DT);
+
+    assert_eq!(clock.due(0.4 * DT), 0);
+    assert_eq!(clock.due(0.8 * DT), 0);
+    assert_eq!(clock.due(1.2 * DT), 1);
+    assert_eq!(clock.due(3.5 * DT), 2);
+    assert!((clock.stepped_to() - 3.0 * DT).abs() < 1e-15);
+    assert!((clock.next_due() - 4.0 * DT).abs() < 1e-15);
+
+}
+
+// the steps are the integrator's own, one of DT each, and frames that divide a step evenly take
+// the same number every time rather than one more or less as the rounding falls
+
+#[test]
+fn even_frames_take_even_steps() {
+
+    let mut sim = Simulation::new(START, PARAMS).unwrap();
+    let mut state = START;
+
+    sim.clock = FixedClock::new(1.0 / 240.0);
+
+    for frame in 0..600 {
+
+        let before = sim.time;
+        sim.advance(1.0 / 60.0);
+
+        assert!((sim.time - before - 4.0 / 240.0).abs() < 1e-12, "frame {frame}");
+
+        for _ in 0..4 {
+            state = rk4(&state, &PARAMS, (1.0 / 240.0) as f32);
+        }
+    }
+
+    assert_eq!(sim.state, state);
+
+}
+
+// a frame part way between two steps shows the pendulum that far along from the step before,
+// one step late, and nothing from before it was last put somewhere by hand
+
+#[test]
+fn drawn_between_the_last_two_steps() {
+
+    let mut sim = Simulation::new(START, PARAMS).unwrap();
+    sim.clock = FixedClock::new(DT);
+    sim.advance(2.25 * DT);
+
+    let one = rk4(&START, &PARAMS, DT as f32);
+    let two = rk4(&one, &PARAMS, DT as f32);
+    let expected = interpolate(&one, &two, 0.25);
+    let (time, state) = sim.drawn();
+
+    assert_eq!(sim.state, two);
+    assert!((time - 1.25 * DT).abs() < 1e-12, "{time}");
+
+    for (a, b) in [(state.theta_1, expected.theta_1), (state.theta_2, expected.theta_2), (state.dot_theta_1, expected.dot_theta_1), (state.dot_theta_2, expected.dot_theta_2)] {
+        assert!((a - b).abs() < 1e-9, "{state:?} against {expected:?}");
+    }
+
+    sim.place(START);
+    assert_eq!(sim.drawn().1, START);
+
+}
+
+// with frames shorter than a step, what's drawn moves a little every frame rather than
+// standing still and then jumping a whole step
+
+#[test]
+fn short_frames_draw_smoothly() {
+
+    let mut sim = Simulation::new(START, PARAMS).unwrap();
+    sim.clock = FixedClock::new(0.01);
+
+    let (mut drawn, mut newest) = (sim.drawn().1, sim.state);
+    let (mut largest_drawn, mut largest_step): (f64, f64) = (0.0, 0.0);
+
+    for _ in 0..1000 {
+
+        sim.advance(0.003);
+
+        let now = sim.drawn().1;
+        largest_drawn = largest_drawn.max((now.theta_2 - drawn.theta_2).abs());
+        largest_step = largest_step.max((sim.state.theta_2 - newest.theta_2).abs());
+
+        (drawn, newest) = (now, sim.state);
+    }
+
+    assert!(largest_drawn < 0.5 * largest_step, "drawn moved up to {largest_drawn} a frame, against {largest_step} a step");
+
+}
@@ -0,0 +1,139 @@
+use double_pendulum::physics::{euler, implicit_midpoint, leapfrog, rk2, rk4, Adaptive, JointControl, Params, State, Step};
+
+const SEED: u64 = 1;
+const CASES: usize = 200;
+const DT: f32 = 1e-3;
+const STEPS: usize = 1000; // a simulated second for each
+
+// splitmix64, which is plenty random for picking test cases, written out here so the tests
+// don't need nannou's rand and still run with the library alone
+
+struct Rng(u64);
+
+impl Rng {
+
+    fn next(&mut self) -> u64 {
+
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        z ^ (z >> 31)
+
+    }
+
+    // uniform over low..high, from the top 53 bits
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+}
+
+// parameters and a start drawn from anywhere physically reasonable: arms from 10 cm to 3 m,
+// bobs from 100 g to 10 kg (so either can be a hundred times the other), gravity from none to
+// twice earth's, either way up on each bob, a few newton metres of torque and a controller
+// that may or may not be on. the speeds are kept to what a flip reaches, as with much more
+// than that a fixed step of DT is too coarse for anything to be expected of it
+
+fn sample(rng: &mut Rng) -> (State, Params) {
+
+    let pi = std::f64::consts::PI;
+
+    let params = Params {
+        l1: rng.range(0.1, 3.0) as f32,
+        l2: rng.range(0.1, 3.0) as f32,
+        m1: rng.range(0.1, 10.0) as f32,
+        m2: rng.range(0.1, 10.0) as f32,
+        gravity: rng.range(0.0, 20.0) as f32,
+        gravity_scale_1: rng.range(-1.0, 2.0) as f32,
+        gravity_scale_2: rng.range(-1.0, 2.0) as f32,
+        torque: (rng.range(-5.0, 5.0) as f32, rng.range(-5.0, 5.0) as f32),
+        control: match rng.next().is_multiple_of(2) {
+            true => JointControl { target: rng.range(-pi, pi), kp: rng.range(0.0, 20.0), kd: rng.range(0.0, 5.0) },
+            false => JointControl::OFF,
+        },
+    };
+
+    params.validate().expect("sampled parameters should be valid");
+
+    let state = State {
+        theta_1: rng.range(-pi, pi),
+        theta_2: rng.range(-pi, pi),
+        dot_theta_1: rng.range(-10.0, 10.0),
+        dot_theta_2: rng.range(-10.0, 10.0),
+    };
+
+    (state, params)
+
+}
+
+fn finite(state: &State) -> bool {
+    [state.theta_1, state.theta_2, state.dot_theta_1, state.dot_theta_2].iter().all(|x| x.is_finite())
+}
+
+// every fixed step integrator keeps every state it steps to a finite number, however the
+// parameters and start are drawn. the seed is fixed so a failure can be reproduced
+
+#[test]
+fn fixed_steps_stay_finite() {
+
+    let methods: [(&str, Step); 5] = [("euler", euler), ("rk2", rk2), ("rk4", rk4), ("leapfrog", leapfrog), ("implicit midpoint", implicit_midpoint)];
+    let mut rng = Rng(SEED);
+
+    for case in 0..CASES {
+
+        let (start, params) = sample(&mut rng);
+
+        for (name, step) in methods {
+
+            let mut state = start;
+
+            for i in 0..STEPS {
+                state = step(&state, &params, DT);
+                assert!(finite(&state), "{name}, case {case}, step {i}: {state:?} from {start:?} with {params:?}");
+            }
+        }
+    }
+
+}
+
+// and the same for rkf45, which picks its own steps within each frame
+
+#[test]
+fn adaptive_steps_stay_finite() {
+
+    let mut rng = Rng(SEED);
+
+    for case in 0..CASES {
+
+        let (start, params) = sample(&mut rng);
+        let mut adaptive = Adaptive::new(1e-9);
+        let mut state = start;
+
+        for frame in 0..60 {
+            state = adaptive.advance(&state, &params, 1.0 / 60.0);
+            assert!(finite(&state), "case {case}, frame {frame}: {state:?} from {start:?} with {params:?}");
+        }
+    }
+
+}
+
+// a state too violent for the step to come out as a number stops where it is instead of
+// stepping on forever in search of a step size that works
+
+#[test]
+fn adaptive_gives_up_on_overflow() {
+
+    let params = Params::new(1.0, 1.0, 1.0, 1.0, 9.81).unwrap();
+    let start = State { theta_1: 1.0, theta_2: 2.0, dot_theta_1: 1e200, dot_theta_2: -1e200 };
+    let mut adaptive = Adaptive::new(1e-6);
+
+    let state = adaptive.advance(&start, &params, 1.0 / 60.0);
+
+    assert!(finite(&state), "{state:?}");
+    assert!(adaptive.stats.rejected > 0);
+
+}
@@ -0,0 +1,24 @@
+use std::f64::consts::PI;
+
+use double_pendulum::physics::to_cartesian;
+
+// the drawing, the mouse, the dials and the energies all assume theta = 0 hangs straight down
+// and increasing theta swings anticlockwise, out to the right first. pin that down, so the
+// geometry can't be flipped by accident
+
+#[test]
+fn to_cartesian_hangs_down_at_zero_and_turns_anticlockwise() {
+
+    let r = 1.5;
+
+    for (theta, expected) in [(0.0, (0.0, -r)), (PI / 2.0, (r, 0.0)), (PI, (0.0, r)), (3.0 * PI / 2.0, (-r, 0.0))] {
+
+        let point = to_cartesian(r, theta);
+
+        assert!(
+            (point.x - expected.0).abs() < 1e-6 && (point.y - expected.1).abs() < 1e-6,
+            "theta = {theta}: ({}, {}), expected {:?}", point.x, point.y, expected,
+        );
+    }
+
+}
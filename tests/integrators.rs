@@ -0,0 +1,98 @@
+use double_pendulum::physics::{energy, euler, implicit_midpoint, leapfrog, rk2, rk4, substep, JointControl, Params, State, Step};
+
+const PARAMS: Params = Params { l1: 1.0, l2: 1.0, m1: 1.0, m2: 1.0, gravity: 10.0, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+
+const START: State = State { theta_1: 1.0, theta_2: -0.5, dot_theta_1: 0.0, dot_theta_2: 1.0 };
+
+// how far a run of one simulated second at the given step ends up from a very fine rk4 run
+
+fn error(step: Step, dt: f32) -> f64 {
+
+    let mut state = START;
+    let mut reference = START;
+
+    for _ in 0..(1.0 / dt).round() as usize {
+        state = step(&state, &PARAMS, dt);
+    }
+
+    for _ in 0..10_000 {
+        reference = rk4(&reference, &PARAMS, 1e-4);
+    }
+
+    (state.theta_1 - reference.theta_1).abs().max((state.theta_2 - reference.theta_2).abs())
+
+}
+
+// halving the step should cut the error by 2 to the power of each method's order
+
+#[test]
+fn integrators_converge_at_their_order() {
+
+    let methods: [(&str, Step, f64); 4] = [
+        ("euler", euler, 1.0),
+        ("rk2", rk2, 2.0),
+        ("leapfrog", leapfrog, 2.0),
+        ("implicit midpoint", implicit_midpoint, 2.0),
+    ];
+
+    for (name, step, order) in methods {
+
+        let coarse = error(step, 0.004);
+        let fine = error(step, 0.002);
+        let measured = (coarse / fine).log2();
+
+        println!("{name}: error {coarse:.2e} then {fine:.2e}, order {measured:.2}");
+
+        assert!((measured - order).abs() < 0.3, "{name} converged at order {measured}");
+    }
+
+}
+
+// rk4 against a much finer rk4 run over a short horizon, halving the step twice. the steps are
+// powers of two so they land exactly on the end time in f32. each halving should cut the error
+// by about 16, which checks the weights as well as derivatives (a mistake in either drops the
+// order)
+
+#[test]
+fn rk4_converges_at_fourth_order() {
+
+    let horizon = 0.5;
+    let run = |dt: f32| (0..(horizon / dt) as usize).fold(START, |state, _| rk4(&state, &PARAMS, dt));
+
+    let reference = run(1.0 / 8192.0);
+    let error = |state: State| {
+        [state.theta_1 - reference.theta_1, state.theta_2 - reference.theta_2,
+         state.dot_theta_1 - reference.dot_theta_1, state.dot_theta_2 - reference.dot_theta_2]
+            .iter()
+            .fold(0.0_f64, |max, e| max.max(e.abs()))
+    };
+
+    let errors: Vec<f64> = [1.0 / 16.0, 1.0 / 32.0, 1.0 / 64.0].into_iter().map(|dt| error(run(dt))).collect();
+
+    for pair in errors.windows(2) {
+        let order = (pair[0] / pair[1]).log2();
+        println!("rk4: error {:.2e} then {:.2e}, ratio {:.1}, order {order:.2}", pair[0], pair[1], pair[0] / pair[1]);
+        assert!((order - 4.0).abs() < 0.3, "rk4 converged at order {order}");
+    }
+
+}
+
+// a frame's worth of time at a huge speed up is far too long for one rk4 step, which flies off
+// to infinity, but cut into short enough substeps it stays finite and keeps its energy
+
+#[test]
+fn substepping_survives_a_huge_time_scale() {
+
+    let dt = 200.0 / 60.0;
+    let start = energy(&START, &PARAMS);
+
+    let single = (0..10).fold(START, |state, _| rk4(&state, &PARAMS, dt));
+    let split = (0..10).fold(START, |state, _| substep(rk4, &state, &PARAMS, dt, 0.01));
+
+    println!("after 10 frames: {single:?} in single steps, {split:?} substepped");
+
+    assert!(!(single.theta_1.is_finite() && (energy(&single, &PARAMS) - start).abs() < 1.0));
+    assert!(split.theta_1.is_finite() && split.dot_theta_2.is_finite());
+    assert!((energy(&split, &PARAMS) - start).abs() < 0.05, "{}", energy(&split, &PARAMS) - start);
+
+}
@@ -0,0 +1,22 @@
+use std::f64::consts::PI;
+
+use double_pendulum::physics::{interpolate, State};
+
+// halfway between two angles either side of straight up is straight up, not hanging down, and
+// the velocities are simply averaged
+
+#[test]
+fn interpolation_takes_the_short_way_round() {
+
+    let a = State { theta_1: PI - 0.1, theta_2: 0.2, dot_theta_1: 2.0, dot_theta_2: -1.0 };
+    let b = State { theta_1: -PI + 0.1, theta_2: 0.4, dot_theta_1: 4.0, dot_theta_2: 1.0 };
+
+    let halfway = interpolate(&a, &b, 0.5);
+
+    assert!((halfway.theta_1 - PI).abs() < 1e-12, "{halfway:?}");
+    assert!((halfway.theta_2 - 0.3).abs() < 1e-12, "{halfway:?}");
+    assert_eq!((halfway.dot_theta_1, halfway.dot_theta_2), (3.0, 0.0));
+
+    assert_eq!(interpolate(&a, &b, 0.0), a);
+
+}
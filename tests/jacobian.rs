@@ -0,0 +1,81 @@
+use double_pendulum::physics::{derivatives, jacobian, JointControl, Params, State};
+use double_pendulum::presets::PRESETS;
+
+const CONTROL: JointControl = JointControl { target: 0.7, kp: 30.0, kd: 4.0 };
+
+// gravity on both bobs, on only one of them, and unevenly
+
+const GRAVITY_SCALES: [(f32, f32); 4] = [(1.0, 1.0), (1.0, 0.0), (0.0, 1.0), (0.5, 1.5)];
+
+// each variable of the state, so it can be nudged by index
+
+fn shift(state: &State, j: usize, d: f64) -> State {
+
+    let mut state = *state;
+
+    match j {
+        0 => state.theta_1 += d,
+        1 => state.theta_2 += d,
+        2 => state.dot_theta_1 += d,
+        _ => state.dot_theta_2 += d,
+    }
+
+    state
+
+}
+
+fn as_array(state: &State) -> [f64; 4] {
+    [state.theta_1, state.theta_2, state.dot_theta_1, state.dot_theta_2]
+}
+
+// the analytic jacobian should match central differences of derivatives, across a spread of
+// states and every preset's parameters, with and without torques, the controller on the first
+// joint and gravity on only one bob
+
+#[test]
+fn jacobian_matches_finite_differences() {
+
+    let h = 1e-6;
+    let mut worst: f64 = 0.0;
+
+    for preset in PRESETS {
+        for (torque, control) in [((0.0, 0.0), JointControl::OFF), ((1.5, -0.5), JointControl::OFF), ((0.5, 0.0), CONTROL)] {
+            for (gravity_scale_1, gravity_scale_2) in GRAVITY_SCALES {
+
+                let params = Params { torque, control, gravity_scale_1, gravity_scale_2, ..preset.params };
+
+                for i in 0..7 {
+                    for j in 0..7 {
+
+                        let state = State {
+                            theta_1: -3.0 + 0.9 * i as f64,
+                            theta_2: 2.5 - 0.8 * j as f64,
+                            dot_theta_1: -4.0 + 1.3 * i as f64,
+                            dot_theta_2: 3.0 - j as f64,
+                        };
+
+                        let analytic = jacobian(&state, &params);
+
+                        for column in 0..4 {
+
+                            let up = as_array(&derivatives(&shift(&state, column, h), &params));
+                            let down = as_array(&derivatives(&shift(&state, column, -h), &params));
+
+                            for (row, analytic_row) in analytic.iter().enumerate() {
+                                let numeric = (up[row] - down[row]) / (2.0 * h);
+                                let a = analytic_row[column];
+
+                                worst = worst.max((a - numeric).abs() / a.abs().max(1.0));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    println!("worst discrepancy: {worst:.2e}");
+
+    assert!(worst < 1e-6);
+
+}
@@ -0,0 +1,40 @@
+use double_pendulum::physics::{normal_mode_frequencies, Params};
+
+// for equal masses and lengths the textbook answer is w^2 = (g / l)(2 -+ sqrt(2))
+
+#[test]
+fn equal_masses_and_lengths_match_the_closed_form() {
+
+    for (l, g) in [(1.0, 10.0), (0.5, 9.81), (2.0, 1.62)] {
+
+        let params = Params::new(l, l, 3.0, 3.0, g).unwrap();
+        let (slow, fast) = normal_mode_frequencies(&params);
+
+        let base = (g / l) as f64;
+        let expected = ((base * (2.0 - 2f64.sqrt())).sqrt(), (base * (2.0 + 2f64.sqrt())).sqrt());
+
+        assert!((slow - expected.0).abs() < 1e-5 * expected.0, "{slow} against {}", expected.0);
+        assert!((fast - expected.1).abs() < 1e-5 * expected.1, "{fast} against {}", expected.1);
+    }
+
+}
+
+// otherwise each frequency should make det(K - w^2 M) vanish
+
+#[test]
+fn lopsided_frequencies_solve_the_eigenproblem() {
+
+    let params = Params::new(0.7, 1.3, 3.0, 0.5, 10.0).unwrap();
+    let (slow, fast) = normal_mode_frequencies(&params);
+
+    let (g, m1, m2, l1, l2) = (10.0, 3.0, 0.5, 0.7, 1.3);
+
+    let det = |w: f64| {
+        let w2 = w * w;
+        ((m1 + m2) * g * l1 - w2 * (m1 + m2) * l1 * l1) * (m2 * g * l2 - w2 * m2 * l2 * l2) - (w2 * m2 * l1 * l2).powi(2)
+    };
+
+    assert!(slow < fast);
+    assert!(det(slow).abs() < 1e-4 && det(fast).abs() < 1e-4, "{} {}", det(slow), det(fast));
+
+}
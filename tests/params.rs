@@ -0,0 +1,75 @@
+use double_pendulum::physics::{rk4, GravitySchedule, Params};
+use double_pendulum::presets::PRESETS;
+
+#[test]
+fn rejects_masses_and_lengths_that_arent_positive() {
+
+    assert!(Params::new(1.0, 1.0, 1.0, 1.0, 10.0).is_ok());
+
+    // anywhere above zero is fine, however small
+
+    assert!(Params::new(1e-6, 1e-6, 1e-6, 1e-6, 10.0).is_ok());
+
+    for bad in [0.0, -0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(Params::new(bad, 1.0, 1.0, 1.0, 10.0).is_err());
+        assert!(Params::new(1.0, bad, 1.0, 1.0, 10.0).is_err());
+        assert!(Params::new(1.0, 1.0, bad, 1.0, 10.0).is_err());
+        assert!(Params::new(1.0, 1.0, 1.0, bad, 10.0).is_err());
+    }
+
+    // the message should say which one is wrong
+
+    let error = Params::new(1.0, 1.0, 0.0, 1.0, 10.0).unwrap_err();
+    assert!(error.contains("m1"), "{}", error);
+
+}
+
+#[test]
+fn gravity_can_be_anything_finite() {
+
+    for gravity in [0.0, -10.0, 1e3] {
+        assert!(Params::new(1.0, 1.0, 1.0, 1.0, gravity).is_ok());
+    }
+
+    assert!(Params::new(1.0, 1.0, 1.0, 1.0, f32::NAN).is_err());
+
+}
+
+#[test]
+fn presets_are_valid() {
+    for preset in PRESETS {
+        assert!(preset.params.validate().is_ok(), "{}", preset.name);
+    }
+}
+
+#[test]
+fn gravity_schedules() {
+
+    let ramp = GravitySchedule::Ramp { from: 0.0, to: 10.0, duration: 4.0 };
+    assert_eq!(ramp.at(0.0), 0.0);
+    assert_eq!(ramp.at(1.0), 2.5);
+    assert_eq!(ramp.at(4.0), 10.0);
+    assert_eq!(ramp.at(100.0), 10.0);
+
+    let sine = GravitySchedule::Sine { mean: 10.0, amplitude: 2.0, period: 8.0 };
+    assert_eq!(sine.at(0.0), 10.0);
+    assert!((sine.at(2.0) - 12.0).abs() < 1e-6);
+    assert!((sine.at(6.0) - 8.0).abs() < 1e-6);
+
+    // a constant schedule steps exactly as the plain parameters do
+
+    let preset = &PRESETS[0];
+    let schedule = GravitySchedule::Constant(preset.params.gravity);
+
+    let (mut plain, mut scheduled) = (preset.state, preset.state);
+
+    for step in 0..1000 {
+        let params = Params { gravity: schedule.at(step as f64 * 0.01), ..preset.params };
+        plain = rk4(&plain, &preset.params, 0.01);
+        scheduled = rk4(&scheduled, &params, 0.01);
+    }
+
+    assert_eq!(plain.theta_2, scheduled.theta_2);
+    assert_eq!(plain.dot_theta_2, scheduled.dot_theta_2);
+
+}
@@ -0,0 +1,50 @@
+use double_pendulum::physics::{rk4, JointControl, Params, State};
+
+const PARAMS: Params = Params { l1: 1.0, l2: 1.0, m1: 1.0, m2: 1.0, gravity: 10.0, gravity_scale_1: 1.0, gravity_scale_2: 1.0, torque: (0.0, 0.0), control: JointControl::OFF };
+
+const DT: f32 = 0.001;
+
+// both arms raised well above horizontal, which is firmly in the chaotic regime
+
+fn start(offset: f64) -> State {
+    State { theta_1: 2.0, theta_2: 2.0 + offset, dot_theta_1: 0.0, dot_theta_2: 0.0 }
+}
+
+// simulated time until two runs differing by `offset` in theta_2 are visibly apart
+
+fn divergence_time(offset: f64) -> f64 {
+
+    let mut a = start(0.0);
+    let mut b = start(offset);
+    let mut t = 0.0;
+
+    while (a.theta_2 - b.theta_2).abs() < 0.1 && t < 200.0 {
+        a = rk4(&a, &PARAMS, DT);
+        b = rk4(&b, &PARAMS, DT);
+        t += DT as f64;
+    }
+
+    t
+
+}
+
+#[test]
+fn f64_state_outlasts_f32_rounding() {
+
+    // one ulp of 2.0 in each precision: the smallest difference each can even represent,
+    // so roughly the best case for how long each can track the true trajectory
+
+    let f32_ulp = (2.0f32.next_up() - 2.0) as f64;
+    let f64_ulp = 2.0f64.next_up() - 2.0;
+
+    let t_f32 = divergence_time(f32_ulp);
+    let t_f64 = divergence_time(f64_ulp);
+
+    // both do come apart well inside the 200s divergence_time gives up at, the f32 one only
+    // after a few seconds of tracking, and the f64 one lasting well over ten seconds longer
+
+    assert!(t_f32 > 5.0 && t_f32 < 20.0, "diverged after {t_f32:.2}s at f32 resolution");
+    assert!(t_f64 < 50.0, "diverged after {t_f64:.2}s at f64 resolution");
+    assert!(t_f64 > t_f32 + 10.0, "{t_f64:.2}s at f64 resolution against {t_f32:.2}s at f32");
+
+}
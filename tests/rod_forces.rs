@@ -0,0 +1,42 @@
+use double_pendulum::physics::{rod_forces, Params, State};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-4 * b.abs().max(1.0)
+}
+
+// hanging still, the bottom rod holds up the bottom bob and the top rod both of them. balanced
+// upside down, the same forces push instead
+
+#[test]
+fn static_hang_holds_up_the_weight() {
+
+    let params = Params::new(1.2, 0.8, 1.5, 0.7, 9.81).unwrap();
+    let (m1, m2, g) = (1.5, 0.7, 9.81);
+
+    let (t1, t2) = rod_forces(&State::new(0.0, 0.0, 0.0, 0.0), &params);
+
+    assert!(close(t2, m2 * g), "{t2}");
+    assert!(close(t1, (m1 + m2) * g), "{t1}");
+
+    let (t1, t2) = rod_forces(&State::new(std::f32::consts::PI, std::f32::consts::PI, 0.0, 0.0), &params);
+
+    assert!(close(t2, -m2 * g), "{t2}");
+    assert!(close(t1, -(m1 + m2) * g), "{t1}");
+
+}
+
+// with no gravity and both arms in line spinning together, it turns as one rigid rod, so each
+// rod supplies just the centripetal pull for everything below it
+
+#[test]
+fn rigid_spin_needs_centripetal_tension() {
+
+    let params = Params::new(1.0, 0.5, 2.0, 1.0, 0.0).unwrap();
+    let w: f64 = 3.0;
+
+    let (t1, t2) = rod_forces(&State::new(0.4, 0.4, w as f32, w as f32), &params);
+
+    assert!(close(t2, 1.0 * w * w * 1.5), "{t2}");
+    assert!(close(t1, 2.0 * w * w * 1.0 + t2), "{t1}");
+
+}
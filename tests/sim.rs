@@ -0,0 +1,84 @@
+use double_pendulum::physics::{leapfrog, rk4, Params, State};
+use double_pendulum::sim::{Integrator, Simulation};
+use double_pendulum::trajectory::{read_states, write_states};
+
+const START: State = State { theta_1: 2.0, theta_2: 2.5, dot_theta_1: 0.0, dot_theta_2: 0.0 };
+
+// stepping a Simulation is the same as stepping the state by hand, and the time keeps up
+
+#[test]
+fn step_matches_the_integrator() {
+
+    let params = Params::new(1.0, 1.0, 1.0, 1.0, 9.81).unwrap();
+    let mut sim = Simulation::new(START, params).unwrap();
+    let mut state = START;
+
+    for _ in 0..600 {
+        sim.step(1.0 / 600.0);
+        state = rk4(&state, &params, 1.0 / 600.0);
+    }
+
+    assert_eq!(sim.state, state);
+    assert!((sim.time - 1.0).abs() < 1e-12);
+
+    // another method, and a frame longer than max_step covered in four steps of it
+
+    sim.integrator = Integrator::Leapfrog;
+    sim.max_step = 0.01;
+    sim.step(0.04);
+
+    for _ in 0..4 {
+        state = leapfrog(&state, &params, 0.01);
+    }
+
+    assert_eq!(sim.state, state);
+
+}
+
+// parameters the physics can't take are turned away rather than left to give NaNs
+
+#[test]
+fn new_rejects_invalid_params() {
+
+    let mut params = Params::new(1.0, 1.0, 1.0, 1.0, 9.81).unwrap();
+    params.m1 = 0.0;
+
+    assert!(Simulation::new(START, params).is_err());
+
+}
+
+// a recording keeps the start and every few steps after it, and comes back from the csv the
+// way it went in
+
+#[test]
+fn record_round_trips_through_csv() {
+
+    let params = Params::new(1.0, 1.0, 1.0, 1.0, 9.81).unwrap();
+    let mut sim = Simulation::new(START, params).unwrap();
+    let rows = sim.record(1.0, 0.01, 10).unwrap();
+
+    assert_eq!(rows.len(), 11);
+    assert_eq!(rows[0], (0.0, START));
+    assert_eq!(rows[10].1, sim.state);
+
+    let mut csv = Vec::new();
+    write_states(&mut csv, &rows).unwrap();
+
+    let states = read_states(&String::from_utf8(csv).unwrap()).unwrap();
+    assert_eq!(states, rows.iter().map(|(_, state)| *state).collect::<Vec<_>>());
+
+}
+
+// a step count too big to ever finish is turned away up front instead of overflowing
+
+#[test]
+fn record_rejects_endless_runs() {
+
+    let params = Params::new(1.0, 1.0, 1.0, 1.0, 9.81).unwrap();
+    let mut sim = Simulation::new(START, params).unwrap();
+
+    assert!(sim.record(1e300, 1e-300, 1).is_err());
+    assert!(sim.record(1e5, 1e-5, 1).is_err());
+    assert_eq!(sim.state, START);
+
+}